@@ -68,7 +68,9 @@ pub async fn fetch_releases(
 
     let now = Utc::now();
     let window = release_window(now);
-    Ok(tmdb_client.fetch_digital_releases(window).await?)
+    let (releases, report) = tmdb_client.fetch_digital_releases(window).await?;
+    tmdb_client.check_error_budget(&report)?;
+    Ok(releases)
 }
 
 pub async fn dispatch_and_persist<D: ReleaseDispatcher, C: ArtifactStore>(
@@ -177,6 +179,11 @@ mod tests {
             vote_count: None,
             homepage: None,
             watch_providers: Vec::new(),
+            genres: Vec::new(),
+            regional_digital_dates: Default::default(),
+            only_for_regions: false,
+            collection: None,
+            only_for_followers: false,
         }
     }
 
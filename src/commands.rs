@@ -0,0 +1,352 @@
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+use crate::config::{ChatConfig, TelegramConfig};
+use crate::state::ChatSubscription;
+
+pub const HELP_TEXT: &str = "Команды бота:\n\
+/subscribe — подписать чат на уведомления о цифровых релизах\n\
+/unsubscribe — отписать чат\n\
+/filters — показать фильтры чата\n\
+/filters lang ru,en — языки оригинала (без значений — все)\n\
+/filters region US,GB — регионы цифрового релиза (без значений — отбор по языку)\n\
+/filters collection 10,1241 — коллекции TMDB, за которыми следит чат\n\
+/filters reset — сбросить фильтры\n\
+/upcoming — релизы ближайших двух недель\n\
+/help — эта справка\n\n\
+В группах менять подписку могут только администраторы.";
+
+const START_TEXT: &str = "Привет! Я присылаю уведомления о фильмах и сериалах, вышедших в цифре.";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BotCommand {
+    Start,
+    Subscribe,
+    Unsubscribe,
+    Filters(FiltersAction),
+    Upcoming,
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FiltersAction {
+    Show,
+    Languages(Vec<String>),
+    Regions(Vec<String>),
+    Collections(Vec<u64>),
+    Reset,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CommandError {
+    #[error("неизвестная команда {0}")]
+    Unknown(String),
+    #[error("некорректные аргументы: {0}")]
+    InvalidArguments(String),
+}
+
+impl BotCommand {
+    /// Команда меняет настройки чата, и в группах её выполняют только администраторы.
+    pub fn changes_settings(&self) -> bool {
+        match self {
+            Self::Subscribe | Self::Unsubscribe => true,
+            Self::Filters(action) => *action != FiltersAction::Show,
+            Self::Start | Self::Upcoming | Self::Help => false,
+        }
+    }
+}
+
+/// Разбирает текст сообщения; `None`, если это не команда или она адресована другому боту.
+///
+/// Поддерживается форма `/command@BotName`, которую Telegram подставляет в группах:
+/// суффикс сверяется с `bot_username` без учёта регистра.
+pub fn parse_command(text: &str, bot_username: &str) -> Option<Result<BotCommand, CommandError>> {
+    let text = text.trim();
+    let rest = text.strip_prefix('/')?;
+    let (head, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let (name, addressee) = head.split_once('@').unwrap_or((head, ""));
+    if !addressee.is_empty() && !addressee.eq_ignore_ascii_case(bot_username) {
+        return None;
+    }
+    let name = name.to_lowercase();
+    let args: Vec<&str> = args
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|arg| !arg.is_empty())
+        .collect();
+
+    let command = match name.as_str() {
+        "start" => Ok(BotCommand::Start),
+        "subscribe" => Ok(BotCommand::Subscribe),
+        "unsubscribe" => Ok(BotCommand::Unsubscribe),
+        "filters" => parse_filters(&args).map(BotCommand::Filters),
+        "upcoming" => Ok(BotCommand::Upcoming),
+        "help" => Ok(BotCommand::Help),
+        _ => Err(CommandError::Unknown(format!("/{name}"))),
+    };
+    Some(command)
+}
+
+fn parse_filters(args: &[&str]) -> Result<FiltersAction, CommandError> {
+    let Some((kind, values)) = args.split_first() else {
+        return Ok(FiltersAction::Show);
+    };
+
+    match kind.to_lowercase().as_str() {
+        "lang" => values
+            .iter()
+            .map(|value| {
+                let value = value.to_lowercase();
+                if (2..=3).contains(&value.len()) && value.chars().all(|c| c.is_ascii_lowercase()) {
+                    Ok(value)
+                } else {
+                    Err(CommandError::InvalidArguments(format!("язык {value}")))
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(FiltersAction::Languages),
+        "region" => values
+            .iter()
+            .map(|value| {
+                let value = value.to_uppercase();
+                if value.len() == 2 && value.chars().all(|c| c.is_ascii_uppercase()) {
+                    Ok(value)
+                } else {
+                    Err(CommandError::InvalidArguments(format!("регион {value}")))
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(FiltersAction::Regions),
+        "collection" => values
+            .iter()
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| CommandError::InvalidArguments(format!("коллекция {value}")))
+            })
+            .collect::<Result<_, _>>()
+            .map(FiltersAction::Collections),
+        "reset" => Ok(FiltersAction::Reset),
+        other => Err(CommandError::InvalidArguments(format!("фильтр {other}"))),
+    }
+}
+
+/// Выполняет команду над подписками и возвращает текст ответа.
+///
+/// `None` для `/upcoming`: команда требует загрузки релизов и выполняется оркестратором.
+pub fn apply_command(
+    command: &BotCommand,
+    chat_id: i64,
+    base: &TelegramConfig,
+    subscriptions: &mut BTreeMap<i64, ChatSubscription>,
+) -> Option<String> {
+    let reply = match command {
+        BotCommand::Upcoming => return None,
+        BotCommand::Start => format!("{START_TEXT}\n\n{HELP_TEXT}"),
+        BotCommand::Help => HELP_TEXT.to_string(),
+        BotCommand::Subscribe => {
+            let subscription = subscription_entry(chat_id, base, subscriptions);
+            subscription.active = true;
+            "Чат подписан на уведомления о цифровых релизах.".to_string()
+        }
+        BotCommand::Unsubscribe => {
+            let subscription = subscription_entry(chat_id, base, subscriptions);
+            subscription.active = false;
+            "Чат отписан от уведомлений.".to_string()
+        }
+        BotCommand::Filters(action) => {
+            let subscription = subscription_entry(chat_id, base, subscriptions);
+            match action {
+                FiltersAction::Show => {}
+                FiltersAction::Languages(locales) => subscription.locales = locales.clone(),
+                FiltersAction::Regions(regions) => subscription.regions = regions.clone(),
+                FiltersAction::Collections(collections) => {
+                    subscription.collections = collections.clone()
+                }
+                FiltersAction::Reset => {
+                    subscription.locales.clear();
+                    subscription.regions.clear();
+                    subscription.collections.clear();
+                }
+            }
+            render_filters(subscription)
+        }
+    };
+    Some(reply)
+}
+
+/// Конфигурация чатов с учётом подписок: активные подписки добавляют или
+/// перекрывают чаты из окружения, отписавшиеся чаты исключаются.
+pub fn effective_config(
+    base: &TelegramConfig,
+    subscriptions: &BTreeMap<i64, ChatSubscription>,
+) -> TelegramConfig {
+    let mut chats: Vec<ChatConfig> = base
+        .chats
+        .iter()
+        .filter(|chat| !subscriptions.contains_key(&chat.chat_id))
+        .cloned()
+        .collect();
+    chats.extend(
+        subscriptions
+            .iter()
+            .filter(|(_, subscription)| subscription.active)
+            .map(|(chat_id, subscription)| ChatConfig {
+                chat_id: *chat_id,
+                locales: subscription.locales.clone(),
+                regions: subscription.regions.clone(),
+                collections: subscription.collections.clone(),
+                ..base
+                    .chats
+                    .iter()
+                    .find(|chat| chat.chat_id == *chat_id)
+                    .cloned()
+                    .unwrap_or_else(|| ChatConfig::new(*chat_id))
+            }),
+    );
+
+    TelegramConfig { chats }
+}
+
+/// Переносит настройки группы, ставшей супергруппой, на новый `chat_id`.
+///
+/// Старый id остаётся неактивной подпиской, чтобы чат из окружения не
+/// получал рассылку по старому адресу.
+pub fn migrate_chat(
+    from: i64,
+    to: i64,
+    base: &TelegramConfig,
+    subscriptions: &mut BTreeMap<i64, ChatSubscription>,
+) {
+    let old = subscription_entry(from, base, subscriptions);
+    let mut migrated = old.clone();
+    old.active = false;
+    migrated.active = true;
+    subscriptions.insert(to, migrated);
+}
+
+/// Отключает рассылку в чат, из которого бот удалён или где он заблокирован.
+pub fn deactivate_chat(
+    chat_id: i64,
+    base: &TelegramConfig,
+    subscriptions: &mut BTreeMap<i64, ChatSubscription>,
+) {
+    subscription_entry(chat_id, base, subscriptions).active = false;
+}
+
+/// Подписка чата; для чата из окружения создаётся из его текущей конфигурации.
+fn subscription_entry<'a>(
+    chat_id: i64,
+    base: &TelegramConfig,
+    subscriptions: &'a mut BTreeMap<i64, ChatSubscription>,
+) -> &'a mut ChatSubscription {
+    subscriptions.entry(chat_id).or_insert_with(|| {
+        base.chats
+            .iter()
+            .find(|chat| chat.chat_id == chat_id)
+            .map(|chat| ChatSubscription {
+                active: true,
+                locales: chat.locales.clone(),
+                regions: chat.regions.clone(),
+                collections: chat.collections.clone(),
+            })
+            .unwrap_or_default()
+    })
+}
+
+fn render_filters(subscription: &ChatSubscription) -> String {
+    let list = |values: Vec<String>, empty: &str| {
+        if values.is_empty() {
+            empty.to_string()
+        } else {
+            values.join(", ")
+        }
+    };
+
+    format!(
+        "Подписка: {}\nЯзыки: {}\nРегионы: {}\nКоллекции: {}",
+        if subscription.active {
+            "активна"
+        } else {
+            "не активна"
+        },
+        list(subscription.locales.clone(), "все"),
+        list(subscription.regions.clone(), "не заданы"),
+        list(
+            subscription
+                .collections
+                .iter()
+                .map(u64::to_string)
+                .collect(),
+            "нет"
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_with_bot_suffix_and_arguments() {
+        assert_eq!(
+            parse_command("/subscribe@Movie_Bot", "movie_bot"),
+            Some(Ok(BotCommand::Subscribe))
+        );
+        assert_eq!(parse_command("/subscribe@other_bot", "movie_bot"), None);
+        assert_eq!(
+            parse_command("/filters region us, gb", "movie_bot"),
+            Some(Ok(BotCommand::Filters(FiltersAction::Regions(vec![
+                "US".to_string(),
+                "GB".to_string()
+            ]))))
+        );
+        assert_eq!(parse_command("просто текст", "movie_bot"), None);
+        assert!(matches!(
+            parse_command("/filters collection abc", "movie_bot"),
+            Some(Err(CommandError::InvalidArguments(_)))
+        ));
+        assert!(matches!(
+            parse_command("/unknown", "movie_bot"),
+            Some(Err(CommandError::Unknown(_)))
+        ));
+    }
+
+    #[test]
+    fn subscriptions_override_environment_chats() {
+        let base = TelegramConfig::single_global_chat(1);
+        let mut subscriptions = BTreeMap::new();
+
+        apply_command(&BotCommand::Unsubscribe, 1, &base, &mut subscriptions);
+        apply_command(&BotCommand::Subscribe, 2, &base, &mut subscriptions);
+        let reply = apply_command(
+            &BotCommand::Filters(FiltersAction::Languages(vec!["ko".to_string()])),
+            2,
+            &base,
+            &mut subscriptions,
+        )
+        .expect("фильтры меняются без оркестратора");
+
+        let config = effective_config(&base, &subscriptions);
+        assert_eq!(config.chats.len(), 1);
+        assert_eq!(config.chats[0].chat_id, 2);
+        assert_eq!(config.chats[0].locales, vec!["ko".to_string()]);
+        assert!(reply.contains("Языки: ko"));
+    }
+
+    #[test]
+    fn migrated_chat_keeps_filters_under_new_id() {
+        let mut base = TelegramConfig::single_global_chat(-100);
+        base.chats[0].regions = vec!["US".to_string()];
+        let mut subscriptions = BTreeMap::new();
+
+        migrate_chat(-100, -100200, &base, &mut subscriptions);
+        deactivate_chat(5, &base, &mut subscriptions);
+
+        let config = effective_config(&base, &subscriptions);
+        assert_eq!(config.chats.len(), 1);
+        assert_eq!(config.chats[0].chat_id, -100200);
+        assert_eq!(config.chats[0].regions, vec!["US".to_string()]);
+        assert!(!subscriptions[&5].active);
+    }
+}
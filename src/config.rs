@@ -1,15 +1,270 @@
 #![allow(dead_code)]
 
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ChatConfig {
     pub chat_id: i64,
     pub locales: Vec<String>,
+    /// Регионы (ISO 3166-1), цифровой выход в которых интересен чату.
+    /// Пустой список означает отбор только по языку оригинала.
+    pub regions: Vec<String>,
+    /// Коллекции TMDB, о новых частях которых чат узнаёт независимо от фильтров.
+    pub collections: Vec<u64>,
+    pub cadence: Cadence,
+    /// Часовой пояс чата (IANA); в нём с учётом перехода на летнее время
+    /// считаются слоты дайджестов и тихие часы.
+    pub timezone: Tz,
+    pub quiet_hours: Option<QuietHours>,
+    /// Темы форума супергруппы, по которым раскладываются релизы.
+    pub topics: ForumTopics,
+    /// Параметры `sendMessage` чата: превью ссылок, тихая отправка, защита от
+    /// пересылки, ответ на сообщение.
+    pub message_options: ChatMessageOptions,
 }
 
 impl ChatConfig {
+    /// Чат без фильтров с мгновенной рассылкой по UTC.
+    pub fn new(chat_id: i64) -> Self {
+        Self {
+            chat_id,
+            locales: Vec::new(),
+            regions: Vec::new(),
+            collections: Vec::new(),
+            cadence: Cadence::Instant,
+            timezone: Tz::UTC,
+            quiet_hours: None,
+            topics: ForumTopics::default(),
+            message_options: ChatMessageOptions::default(),
+        }
+    }
+
     pub fn matches_locale(&self, locale: &str) -> bool {
         self.locales.is_empty() || self.locales.iter().any(|l| l == locale)
     }
+
+    pub fn targets_regions(&self) -> bool {
+        !self.regions.is_empty()
+    }
+
+    pub fn follows_collection(&self, collection_id: u64) -> bool {
+        self.collections.contains(&collection_id)
+    }
+
+    /// Тихие часы, действующие в момент `now`, если сейчас они идут.
+    pub fn quiet_at(&self, now: DateTime<Utc>) -> Option<QuietHours> {
+        let quiet = self.quiet_hours?;
+        quiet
+            .contains(now.with_timezone(&self.timezone).hour())
+            .then_some(quiet)
+    }
+}
+
+/// `message_thread_id` тем форума для релизов чата. Жанр проверяется раньше
+/// типа релиза; релиз без подходящей темы уходит в общую ленту чата.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ForumTopics {
+    pub movies: Option<i64>,
+    pub tv_premieres: Option<i64>,
+    pub tv_seasons: Option<i64>,
+    /// Тема для дайджестов; дайджест не делится по темам.
+    pub digest: Option<i64>,
+    /// Жанр TMDB в нижнем регистре (`horror`, `animation`) → тема.
+    pub genres: BTreeMap<String, i64>,
+}
+
+impl ForumTopics {
+    /// Тема для релиза с жанрами `genres`: первый жанр с темой, иначе `by_kind`.
+    pub fn for_genres<'a>(
+        &self,
+        genres: impl IntoIterator<Item = &'a String>,
+        by_kind: Option<i64>,
+    ) -> Option<i64> {
+        genres
+            .into_iter()
+            .find_map(|genre| self.genres.get(&genre.to_lowercase()).copied())
+            .or(by_kind)
+    }
+}
+
+/// Тип сообщения рассылки, для которого можно задать свои параметры отправки.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MessageKind {
+    Movie,
+    TvPremiere,
+    TvSeason,
+    Digest,
+}
+
+/// Параметры отправки сообщений чата: общие и переопределения по типам.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChatMessageOptions {
+    pub default: MessageOptions,
+    pub kinds: BTreeMap<MessageKind, MessageOptions>,
+}
+
+impl ChatMessageOptions {
+    pub fn for_kind(&self, kind: MessageKind) -> &MessageOptions {
+        self.kinds.get(&kind).unwrap_or(&self.default)
+    }
+}
+
+/// Параметры Bot API `sendMessage`, кроме чата, темы и текста.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageOptions {
+    #[serde(default)]
+    pub link_preview: LinkPreview,
+    /// Доставить без звука уведомления.
+    #[serde(default)]
+    pub disable_notification: bool,
+    /// Запретить пересылку и сохранение сообщения.
+    #[serde(default)]
+    pub protect_content: bool,
+    /// Отправлять ответом на это сообщение чата (например, на закреплённый пост).
+    #[serde(default)]
+    pub reply_to_message_id: Option<i64>,
+    /// Разметка текста; без неё сообщение уходит простым текстом.
+    #[serde(default)]
+    pub parse_mode: Option<ParseMode>,
+}
+
+/// Разметка `parse_mode`. Тексты анонсов экранируются под неё; сообщение,
+/// которое Telegram всё же не смог разобрать, переотправляется простым текстом.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParseMode {
+    #[serde(rename = "HTML")]
+    Html,
+    MarkdownV2,
+}
+
+/// Превью ссылок (`link_preview_options`). По умолчанию выключено: карточка
+/// TMDB занимает больше места, чем сам анонс.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkPreview {
+    pub is_disabled: bool,
+    /// Ссылка для превью; без неё Telegram берёт первую ссылку сообщения.
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub prefer_large_media: bool,
+    #[serde(default)]
+    pub show_above_text: bool,
+}
+
+impl Default for LinkPreview {
+    fn default() -> Self {
+        Self {
+            is_disabled: true,
+            url: None,
+            prefer_large_media: false,
+            show_above_text: false,
+        }
+    }
+}
+
+/// Часы, в которые чат не хочет получать уведомления, по местному времени чата.
+/// `start` больше `end` означает интервал через полночь, например 23–8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: u32,
+    pub end: u32,
+    pub mode: QuietMode,
+}
+
+impl QuietHours {
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&hour)
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuietMode {
+    /// Отложить рассылку до первого прогона после тихих часов.
+    #[default]
+    Defer,
+    /// Отправить сразу, но без звука (`disable_notification`).
+    Silent,
+}
+
+/// Как часто чат получает рассылку. Время слотов — местное время чата.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Cadence {
+    /// Всё, что нашёл прогон, сразу.
+    #[default]
+    Instant,
+    /// Один дайджест в день в указанный час.
+    Daily { hour: u32 },
+    /// Один дайджест в неделю в указанный день и час.
+    Weekly { weekday: Weekday, hour: u32 },
+}
+
+impl Cadence {
+    pub fn is_digest(&self) -> bool {
+        !matches!(self, Self::Instant)
+    }
+
+    /// Подошёл ли слот дайджеста: последний слот не позже `now` ещё не отправлен.
+    pub fn is_due(
+        &self,
+        last_sent: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+        timezone: Tz,
+    ) -> bool {
+        match self.latest_slot(now, timezone) {
+            Some(slot) => last_sent.is_none_or(|sent| sent < slot),
+            None => true,
+        }
+    }
+
+    /// Последний слот не позже `now`; `None` для мгновенной рассылки.
+    fn latest_slot(&self, now: DateTime<Utc>, timezone: Tz) -> Option<DateTime<Utc>> {
+        let (weekday, hour) = match *self {
+            Self::Instant => return None,
+            Self::Daily { hour } => (None, hour),
+            Self::Weekly { weekday, hour } => (Some(weekday), hour),
+        };
+        let mut date = now.with_timezone(&timezone).date_naive();
+        for _ in 0..=7 {
+            let slot = local_slot(timezone, date.and_hms_opt(hour, 0, 0)?)?;
+            if slot <= now && weekday.is_none_or(|weekday| date.weekday() == weekday) {
+                return Some(slot);
+            }
+            date -= Duration::days(1);
+        }
+        None
+    }
+
+    /// Заголовок дайджеста; `None` для мгновенной рассылки.
+    pub fn digest_title(&self) -> Option<&'static str> {
+        match self {
+            Self::Instant => None,
+            Self::Daily { .. } => Some("🗓 Дайджест релизов за день"),
+            Self::Weekly { .. } => Some("🗓 Дайджест релизов за неделю"),
+        }
+    }
+}
+
+/// Момент местного времени `local` в поясе `timezone`. Час, повторяющийся при
+/// переводе часов назад, берётся в первый раз; пропущенный при переводе вперёд
+/// сдвигается на час позже.
+fn local_slot(timezone: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|slot| slot.with_timezone(&Utc))
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -20,10 +275,7 @@ pub struct TelegramConfig {
 impl TelegramConfig {
     pub fn single_global_chat(chat_id: i64) -> Self {
         Self {
-            chats: vec![ChatConfig {
-                chat_id,
-                locales: Vec::new(),
-            }],
+            chats: vec![ChatConfig::new(chat_id)],
         }
     }
 }
@@ -33,3 +285,141 @@ impl Default for TelegramConfig {
         Self::single_global_chat(-1_000_000_000_000)
     }
 }
+
+/// Сколько релизов чат получает за прогон и как долго ждут отложенные.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BacklogPolicy {
+    pub max_releases_per_run: usize,
+    /// Отложенный релиз отбрасывается, когда его дата старше стольких дней.
+    pub max_age_days: i64,
+}
+
+impl Default for BacklogPolicy {
+    fn default() -> Self {
+        Self {
+            max_releases_per_run: 10,
+            max_age_days: 30,
+        }
+    }
+}
+
+/// Политики очереди: общая и переопределения для отдельных чатов.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BacklogConfig {
+    pub default: BacklogPolicy,
+    pub chats: HashMap<i64, BacklogPolicy>,
+}
+
+impl BacklogConfig {
+    pub fn policy_for(&self, chat_id: i64) -> BacklogPolicy {
+        self.chats.get(&chat_id).copied().unwrap_or(self.default)
+    }
+}
+
+/// Как окно поиска релизов строится от прошлого успешного прогона.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowPolicy {
+    /// Насколько окно заходит назад за прошлый успешный прогон: TMDB публикует
+    /// цифровые даты с опозданием.
+    pub overlap_hours: i64,
+    /// Самое длинное окно после простоя; более ранние релизы не сканируются.
+    pub max_catch_up_days: i64,
+}
+
+impl Default for WindowPolicy {
+    fn default() -> Self {
+        Self {
+            overlap_hours: 48,
+            max_catch_up_days: 30,
+        }
+    }
+}
+
+/// Как бот исправляет анонс фильма, чья цифровая дата перенесена в будущее
+/// или отозвана.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CorrectionPolicy {
+    /// Сколько дней после анонса дата фильма перепроверяется; `0` отключает проверку.
+    pub recheck_days: i64,
+    /// Сколько фильмов перепроверяется за прогон; остальные проверяются
+    /// следующими прогонами по кругу.
+    pub max_rechecks: usize,
+    pub action: CorrectionAction,
+}
+
+impl Default for CorrectionPolicy {
+    fn default() -> Self {
+        Self {
+            recheck_days: 14,
+            max_rechecks: 50,
+            action: CorrectionAction::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CorrectionAction {
+    /// Отдельное сообщение с поправкой.
+    #[default]
+    Post,
+    /// Строка фильма в исходном сообщении заменяется поправкой.
+    Edit,
+    /// Исходное сообщение удаляется; если в нём были и другие релизы — правится.
+    Delete,
+}
+
+/// Веса модели приоритета релизов; см. `formatter::score_release`.
+///
+/// Каждый признак нормирован к диапазону 0..1 и умножается на свой вес,
+/// вес типа релиза добавляется как есть.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScoringWeights {
+    pub popularity: f64,
+    pub vote_average: f64,
+    pub vote_count: f64,
+    /// Свежесть: 1 в день выхода, линейно до 0 через две недели.
+    pub recency: f64,
+    /// Фильм уже доступен у стриминговых сервисов.
+    pub providers: f64,
+    /// Чат следит за коллекцией релиза.
+    pub follow: f64,
+    pub movie: f64,
+    pub tv_premiere: f64,
+    pub tv_season: f64,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            popularity: 2.0,
+            vote_average: 3.0,
+            vote_count: 1.0,
+            recency: 2.0,
+            providers: 1.0,
+            follow: 3.0,
+            movie: 1.0,
+            tv_premiere: 1.5,
+            tv_season: 0.5,
+        }
+    }
+}
+
+/// Как оценка релиза влияет на рассылку.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScoringConfig {
+    pub weights: ScoringWeights,
+    /// Сколько лучших релизов рассылки отмечаются 🔥.
+    pub highlight_top: usize,
+    /// Рассылка, где все релизы оценены ниже порога, уходит без звука.
+    pub silent_below: Option<f64>,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            weights: ScoringWeights::default(),
+            highlight_top: 3,
+            silent_below: None,
+        }
+    }
+}
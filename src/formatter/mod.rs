@@ -1,14 +1,21 @@
 #![allow(dead_code)]
 
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
 use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
 
-use crate::config::TelegramConfig;
+use crate::config::{ChatConfig, ParseMode, TelegramConfig};
+use crate::tmdb::CollectionEntry;
+
+mod score;
+
+pub use score::{Score, rank_chat_releases};
 
 const TELEGRAM_MESSAGE_LIMIT: usize = 4096;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReleaseKind {
     Movie,
     TvPremiere,
@@ -24,10 +31,21 @@ pub struct DigitalRelease {
     pub kind: ReleaseKind,
     pub vote_average: Option<f64>,
     pub vote_count: Option<u32>,
+    pub popularity: Option<f64>,
+    /// Фильм уже доступен у стриминговых сервисов.
+    pub has_providers: bool,
+    pub genres: Vec<String>,
     pub event_key: String,
+    /// Цифровые даты по регионам; пусто, если релиз не привязан к регионам (сериалы).
+    pub regional_dates: BTreeMap<String, NaiveDate>,
+    /// `event_date` вне окна прогона: релиз адресован только чатам с регионами.
+    pub only_for_regions: bool,
+    pub collection: Option<CollectionEntry>,
+    /// Релиз адресован только чатам, которые следят за его коллекцией.
+    pub only_for_followers: bool,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ChatRelease {
     pub id: u64,
     pub title: String,
@@ -35,6 +53,27 @@ pub struct ChatRelease {
     pub kind: ReleaseKind,
     pub vote_average: Option<f64>,
     pub vote_count: Option<u32>,
+    #[serde(default)]
+    pub popularity: Option<f64>,
+    #[serde(default)]
+    pub has_providers: bool,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    /// Чат следит за коллекцией релиза.
+    #[serde(default)]
+    pub followed: bool,
+    pub collection: Option<CollectionEntry>,
+    pub event_key: String,
+    /// Регион чата, по дате которого релиз анонсирован; `None` — дата
+    /// приоритетных регионов.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Оценка приоритета; считается заново при каждом ранжировании.
+    #[serde(default)]
+    pub score: Score,
+    /// Релиз среди лучших в рассылке и отмечается 🔥.
+    #[serde(default)]
+    pub highlighted: bool,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -43,20 +82,17 @@ pub struct ChatPayload {
     pub releases: Vec<ChatRelease>,
 }
 
+/// Текст сообщения для чата. Параметры отправки (превью ссылок и т.п.) задаёт
+/// [`crate::config::MessageOptions`] чата.
 #[derive(Clone, Debug, PartialEq)]
 pub struct TelegramMessage {
     pub chat_id: i64,
     pub text: String,
-    pub disable_web_page_preview: bool,
 }
 
 impl TelegramMessage {
     pub fn new(chat_id: i64, text: String) -> Self {
-        Self {
-            chat_id,
-            text,
-            disable_web_page_preview: true,
-        }
+        Self { chat_id, text }
     }
 }
 
@@ -72,9 +108,17 @@ fn compare_release_priority(a: &DigitalRelease, b: &DigitalRelease) -> Ordering
         .then_with(|| a.title.cmp(&b.title))
 }
 
+/// Порядок релизов внутри чата: тот же, в котором они попадают в сообщение.
+pub fn sort_chat_releases_by_priority(releases: &mut [ChatRelease]) {
+    releases.sort_by(compare_chat_release_priority);
+}
+
 fn compare_chat_release_priority(a: &ChatRelease, b: &ChatRelease) -> Ordering {
-    b.event_date
-        .cmp(&a.event_date)
+    b.score
+        .total
+        .partial_cmp(&a.score.total)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| b.event_date.cmp(&a.event_date))
         .then_with(|| compare_optional_f64(b.vote_average, a.vote_average))
         .then_with(|| compare_optional_u32(b.vote_count, a.vote_count))
         .then_with(|| a.title.cmp(&b.title))
@@ -107,14 +151,28 @@ pub fn group_releases_by_chat(
     for chat in &config.chats {
         let mut chat_releases: Vec<ChatRelease> = releases
             .iter()
-            .filter(|release| chat.matches_locale(&release.locale))
-            .map(|release| ChatRelease {
-                id: release.id,
-                title: release.title.clone(),
-                event_date: release.event_date,
-                kind: release.kind.clone(),
-                vote_average: release.vote_average,
-                vote_count: release.vote_count,
+            .filter_map(|release| {
+                let (event_date, region) = chat_event_date(chat, release)?;
+                Some(ChatRelease {
+                    id: release.id,
+                    title: release.title.clone(),
+                    event_date,
+                    kind: release.kind.clone(),
+                    vote_average: release.vote_average,
+                    vote_count: release.vote_count,
+                    popularity: release.popularity,
+                    has_providers: release.has_providers,
+                    genres: release.genres.clone(),
+                    followed: release
+                        .collection
+                        .as_ref()
+                        .is_some_and(|collection| chat.follows_collection(collection.id)),
+                    collection: release.collection.clone(),
+                    event_key: release.event_key.clone(),
+                    region,
+                    score: Score::default(),
+                    highlighted: false,
+                })
             })
             .collect();
 
@@ -133,49 +191,192 @@ pub fn group_releases_by_chat(
     payloads
 }
 
+/// Дата, под которой релиз попадает в чат, и регион этой даты (`None` для даты
+/// приоритетных регионов), или `None`, если чату релиз не нужен.
+///
+/// Подписчики коллекции получают её новые части всегда. Чат с регионами получает
+/// релиз по самой ранней дате в своих регионах; релизы без региональных дат
+/// и чаты без регионов отбираются по языку и только по дате приоритетных регионов.
+fn chat_event_date(
+    chat: &ChatConfig,
+    release: &DigitalRelease,
+) -> Option<(NaiveDate, Option<String>)> {
+    let follows = release
+        .collection
+        .as_ref()
+        .is_some_and(|collection| chat.follows_collection(collection.id));
+    if follows && !release.only_for_regions {
+        return Some((release.event_date, None));
+    }
+    if release.only_for_followers && !follows {
+        return None;
+    }
+    if !chat.targets_regions() || release.regional_dates.is_empty() {
+        return (!release.only_for_regions && (follows || chat.matches_locale(&release.locale)))
+            .then_some((release.event_date, None));
+    }
+
+    chat.regions
+        .iter()
+        .filter_map(|region| Some((*release.regional_dates.get(region)?, region)))
+        .min()
+        .map(|(date, region)| (date, Some(region.clone())))
+}
+
+/// Нужен ли релиз чату с учётом его языков, регионов и коллекций.
+pub fn release_targets_chat(chat: &ChatConfig, release: &DigitalRelease) -> bool {
+    chat_event_date(chat, release).is_some()
+}
+
 pub fn build_messages(
     releases: &[DigitalRelease],
     config: &TelegramConfig,
 ) -> Vec<TelegramMessage> {
-    group_releases_by_chat(releases, config)
+    build_payload_messages(group_releases_by_chat(releases, config))
+}
+
+/// Сообщения для уже сгруппированных по чатам релизов.
+pub fn build_payload_messages(payloads: Vec<ChatPayload>) -> Vec<TelegramMessage> {
+    payloads
         .into_iter()
         .flat_map(|payload| {
             let header = "";
-            let mut lines = Vec::new();
-            for release in payload.releases {
-                let line = match release.kind {
-                    ReleaseKind::Movie => {
-                        let title = release.title;
-                        let date = release.event_date.format("%Y-%m-%d").to_string();
-                        format!(
-                            "🔥 {title} — {date}\nhttps://www.themoviedb.org/movie/{}",
-                            release.id
-                        )
-                    }
-                    ReleaseKind::TvPremiere => {
-                        let title = release.title;
-                        let year = release.event_date.year();
-                        format!(
-                            "📺 Премьера сериала: {title} ({year})\nhttps://www.themoviedb.org/tv/{}",
-                            release.id
-                        )
-                    }
-                    ReleaseKind::TvSeason { season_number } => {
-                        let title = release.title;
-                        format!(
-                            "📺 Новый сезон: {title} — сезон {season_number}\nhttps://www.themoviedb.org/tv/{}",
-                            release.id
-                        )
-                    }
-                };
-                lines.push(line);
-            }
+            let lines: Vec<String> = payload.releases.iter().map(release_line).collect();
 
             chunk_lines(payload.chat_id, header, &lines, TELEGRAM_MESSAGE_LIMIT)
         })
         .collect()
 }
 
+/// Один дайджест чата: заголовок и разделы фильмов и сериалов. Если дайджест
+/// не помещается в сообщение, заголовок повторяется в каждой части.
+pub fn build_digest_messages(payload: ChatPayload, title: &str) -> Vec<TelegramMessage> {
+    let chat_id = payload.chat_id;
+    chunk_lines(chat_id, title, &digest_lines(payload), TELEGRAM_MESSAGE_LIMIT)
+}
+
+/// Сообщения рассылки чата: дайджест под заголовком `digest_title` или
+/// мгновенная рассылка. Строки экранируются под `parse_mode` до разбивки,
+/// поэтому лимит Telegram считается по итоговому тексту.
+pub fn build_chat_messages(
+    payload: ChatPayload,
+    digest_title: Option<&str>,
+    parse_mode: Option<ParseMode>,
+) -> Vec<TelegramMessage> {
+    let chat_id = payload.chat_id;
+    let (header, lines) = match digest_title {
+        Some(title) => (title, digest_lines(payload)),
+        None => ("", payload.releases.iter().map(release_line).collect()),
+    };
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| escape_markup(line, parse_mode))
+        .collect();
+
+    chunk_lines(
+        chat_id,
+        &escape_markup(header, parse_mode),
+        &lines,
+        TELEGRAM_MESSAGE_LIMIT,
+    )
+}
+
+/// Строки дайджеста: разделы фильмов и сериалов с релизами.
+fn digest_lines(payload: ChatPayload) -> Vec<String> {
+    let (movies, series): (Vec<ChatRelease>, Vec<ChatRelease>) = payload
+        .releases
+        .into_iter()
+        .partition(|release| release.kind == ReleaseKind::Movie);
+
+    let mut lines = Vec::new();
+    for (section, releases) in [("🎬 Фильмы", movies), ("📺 Сериалы", series)] {
+        if releases.is_empty() {
+            continue;
+        }
+        lines.push(format!("\n{section} ({}):", releases.len()));
+        lines.extend(releases.iter().map(release_line));
+    }
+    lines
+}
+
+/// Экранирует простой текст под разметку `parse_mode`, чтобы Telegram показал
+/// его как есть; без разметки текст не меняется.
+pub fn escape_markup(text: &str, parse_mode: Option<ParseMode>) -> String {
+    match parse_mode {
+        None => text.to_string(),
+        Some(ParseMode::Html) => text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+        Some(ParseMode::MarkdownV2) => {
+            let mut escaped = String::with_capacity(text.len());
+            for ch in text.chars() {
+                if "_*[]()~`>#+-=|{}.!\\".contains(ch) {
+                    escaped.push('\\');
+                }
+                escaped.push(ch);
+            }
+            escaped
+        }
+    }
+}
+
+/// Строка релиза в сообщении. По ней же сообщение находится при правке: если
+/// свежие данные TMDB дают другую строку, она заменяется в тексте сообщения.
+pub fn release_line(release: &ChatRelease) -> String {
+    let highlight = if release.highlighted { "🔥 " } else { "" };
+    let title = &release.title;
+    let rating = release
+        .vote_average
+        .map(|vote| format!(" · ⭐ {vote:.1}"))
+        .unwrap_or_default();
+    match release.kind {
+        ReleaseKind::Movie => {
+            let marker = if release.highlighted { "🔥" } else { "🎬" };
+            let date = release.event_date.format("%Y-%m-%d").to_string();
+            let collection = release
+                .collection
+                .as_ref()
+                .map(format_collection_line)
+                .unwrap_or_default();
+            let providers = if release.has_providers {
+                "\n📲 Уже на стриминге"
+            } else {
+                ""
+            };
+            format!(
+                "{marker} {title} — {date}{rating}{collection}{providers}\nhttps://www.themoviedb.org/movie/{}",
+                release.id
+            )
+        }
+        ReleaseKind::TvPremiere => {
+            let year = release.event_date.year();
+            format!(
+                "{highlight}📺 Премьера сериала: {title} ({year}){rating}\nhttps://www.themoviedb.org/tv/{}",
+                release.id
+            )
+        }
+        ReleaseKind::TvSeason { season_number } => {
+            format!(
+                "{highlight}📺 Новый сезон: {title} — сезон {season_number}{rating}\nhttps://www.themoviedb.org/tv/{}",
+                release.id
+            )
+        }
+    }
+}
+
+fn format_collection_line(collection: &CollectionEntry) -> String {
+    match (collection.part, collection.total) {
+        (Some(part), Some(total)) => {
+            format!("\n📚 {}: часть {part} из {total}", collection.name)
+        }
+        _ => format!("\n📚 {}", collection.name),
+    }
+}
+
+/// Раскладывает строки по сообщениям не длиннее `max_len` байт (а значит, и
+/// символов), повторяя `header` в каждом. Строка, не помещающаяся даже в пустое
+/// сообщение, режется на части, поэтому каждое сообщение уходит одним запросом.
 fn chunk_lines(
     chat_id: i64,
     header: &str,
@@ -186,15 +387,17 @@ fn chunk_lines(
         return Vec::new();
     }
 
+    let room = max_len.saturating_sub(header.len() + 1).max(1);
     let mut messages = Vec::new();
     let mut current = header.to_string();
-    for line in lines {
+    let mut has_lines = false;
+    for line in lines.iter().flat_map(|line| split_long_line(line, room)) {
         let additional = if current.is_empty() {
             line.len()
         } else {
             1 + line.len()
         };
-        if current.len() + additional > max_len {
+        if has_lines && current.len() + additional > max_len {
             messages.push(TelegramMessage::new(chat_id, current));
             current = header.to_string();
         }
@@ -203,6 +406,7 @@ fn chunk_lines(
             current.push('\n');
         }
         current.push_str(line);
+        has_lines = true;
     }
 
     if !current.is_empty() {
@@ -212,16 +416,59 @@ fn chunk_lines(
     messages
 }
 
+/// Режет строку длиннее `max_len` байт на части: по переводу строки, если он
+/// есть, иначе по границе символа, не разрывая экранирование разметки (`\.`
+/// в MarkdownV2, `&amp;` в HTML).
+fn split_long_line(line: &str, max_len: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = line;
+    while rest.len() > max_len {
+        let mut cut = max_len;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        if let Some(newline) = rest[..cut].rfind('\n').filter(|newline| *newline > 0) {
+            cut = newline;
+        } else {
+            let backslashes = rest[..cut].chars().rev().take_while(|ch| *ch == '\\').count();
+            if backslashes % 2 == 1 {
+                cut -= 1;
+            }
+            if let Some(amp) = rest[..cut]
+                .rfind('&')
+                .filter(|amp| cut - amp < 8 && !rest[*amp..cut].contains(';'))
+            {
+                cut = amp;
+            }
+        }
+        if cut == 0 {
+            cut = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        parts.push(&rest[..cut]);
+        rest = rest[cut..].trim_start_matches('\n');
+    }
+    parts.push(rest);
+    parts
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::ChatConfig;
+    use crate::config::{Cadence, ChatConfig, ChatMessageOptions, ForumTopics};
+    use chrono_tz::Tz;
 
     fn test_config() -> TelegramConfig {
         TelegramConfig {
             chats: vec![ChatConfig {
                 chat_id: 1,
                 locales: vec!["ru".to_string()],
+                regions: Vec::new(),
+                collections: Vec::new(),
+                cadence: Cadence::Instant,
+                timezone: Tz::UTC,
+                quiet_hours: None,
+                topics: ForumTopics::default(),
+                message_options: ChatMessageOptions::default(),
             }],
         }
     }
@@ -235,7 +482,14 @@ mod tests {
             kind: ReleaseKind::Movie,
             vote_average: Some(7.5),
             vote_count: Some(100),
+            popularity: None,
+            has_providers: false,
+            genres: Vec::new(),
             event_key: format!("movie:{id}"),
+            regional_dates: BTreeMap::new(),
+            only_for_regions: false,
+            collection: None,
+            only_for_followers: false,
         }
     }
 
@@ -263,8 +517,8 @@ mod tests {
         assert_eq!(messages.len(), 1);
         let text = &messages[0].text;
         let lines: Vec<&str> = text.lines().collect();
-        assert!(lines[0].starts_with("🔥"));
-        assert!(lines[1].starts_with("🔥"));
+        assert!(lines[0].starts_with("🎬"));
+        assert!(lines[2].starts_with("🎬"));
         assert!(lines[0].contains("Свежий релиз"));
         assert!(text.contains("https://www.themoviedb.org/movie/2"));
     }
@@ -281,7 +535,14 @@ mod tests {
             kind: ReleaseKind::TvSeason { season_number: 2 },
             vote_average: None,
             vote_count: None,
+            popularity: None,
+            has_providers: false,
+            genres: Vec::new(),
             event_key: "tv:10:season:2".to_string(),
+            regional_dates: BTreeMap::new(),
+            only_for_regions: false,
+            collection: None,
+            only_for_followers: false,
         };
 
         let messages = build_messages(&[release], &config);
@@ -294,6 +555,164 @@ mod tests {
         assert!(messages[0].text.contains("https://www.themoviedb.org/tv/10"));
     }
 
+    #[test]
+    fn digest_groups_releases_into_sections() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 1).expect("валидная дата");
+        let release = |id: u64, kind: ReleaseKind| ChatRelease {
+            id,
+            title: format!("Релиз {id}"),
+            event_date: date,
+            kind,
+            vote_average: None,
+            vote_count: None,
+            popularity: None,
+            has_providers: false,
+            genres: Vec::new(),
+            followed: false,
+            collection: None,
+            event_key: format!("key:{id}"),
+            region: None,
+            score: Score::default(),
+            highlighted: id == 2,
+        };
+        let payload = ChatPayload {
+            chat_id: 7,
+            releases: vec![
+                release(1, ReleaseKind::TvPremiere),
+                release(2, ReleaseKind::Movie),
+                release(3, ReleaseKind::Movie),
+            ],
+        };
+
+        let messages = build_digest_messages(payload, "🗓 Дайджест");
+        assert_eq!(messages.len(), 1);
+        let text = &messages[0].text;
+        assert!(text.starts_with(
+            "🗓 Дайджест\n\n🎬 Фильмы (2):\n🔥 Релиз 2 — 2024-06-01\nhttps://www.themoviedb.org/movie/2\n🎬 Релиз 3"
+        ));
+        assert!(text.contains("\n\n📺 Сериалы (1):\n📺 Премьера сериала: Релиз 1"));
+    }
+
+    #[test]
+    fn regional_chat_receives_release_by_its_region_date() {
+        let us_date = NaiveDate::from_ymd_opt(2024, 3, 1).expect("валидная дата");
+        let gb_date = NaiveDate::from_ymd_opt(2024, 3, 4).expect("валидная дата");
+        let mut release = sample_release(us_date, "Корейский фильм", 5);
+        release.locale = "ko".to_string();
+        release.regional_dates =
+            BTreeMap::from([("US".to_string(), us_date), ("GB".to_string(), gb_date)]);
+        let config = TelegramConfig {
+            chats: vec![
+                ChatConfig {
+                    chat_id: 1,
+                    locales: vec!["ru".to_string()],
+                    regions: vec!["GB".to_string()],
+                    collections: Vec::new(),
+                    cadence: Cadence::Instant,
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                },
+                ChatConfig {
+                    chat_id: 2,
+                    locales: Vec::new(),
+                    regions: vec!["DE".to_string()],
+                    collections: Vec::new(),
+                    cadence: Cadence::Instant,
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                },
+                ChatConfig {
+                    chat_id: 3,
+                    locales: vec!["ru".to_string()],
+                    regions: Vec::new(),
+                    collections: Vec::new(),
+                    cadence: Cadence::Instant,
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                },
+            ],
+        };
+
+        let payloads = group_releases_by_chat(&[release], &config);
+
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].chat_id, 1);
+        assert_eq!(payloads[0].releases[0].event_date, gb_date);
+    }
+
+    #[test]
+    fn release_dated_only_by_other_region_skips_locale_chats() {
+        let us_date = NaiveDate::from_ymd_opt(2024, 4, 20).expect("валидная дата");
+        let br_date = NaiveDate::from_ymd_opt(2024, 3, 2).expect("валидная дата");
+        let mut release = sample_release(us_date, "Фильм", 6);
+        release.regional_dates = BTreeMap::from([("BR".to_string(), br_date)]);
+        release.only_for_regions = true;
+        let chat = |chat_id, regions: &[&str]| ChatConfig {
+            regions: regions.iter().map(|region| region.to_string()).collect(),
+            ..ChatConfig::new(chat_id)
+        };
+        let config = TelegramConfig {
+            chats: vec![chat(1, &[]), chat(2, &["BR"]), chat(3, &["US"])],
+        };
+
+        let payloads = group_releases_by_chat(&[release], &config);
+
+        assert_eq!(payloads.len(), 1, "чаты без BR не получают релиз по дате BR");
+        assert_eq!(payloads[0].chat_id, 2);
+        assert_eq!(payloads[0].releases[0].event_date, br_date);
+    }
+
+    #[test]
+    fn followed_collection_part_reaches_only_its_followers() {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).expect("валидная дата");
+        let mut release = sample_release(date, "Франшиза 4", 8);
+        release.collection = Some(CollectionEntry {
+            id: 77,
+            name: "Франшиза".to_string(),
+            part: Some(4),
+            total: Some(5),
+        });
+        release.only_for_followers = true;
+        let config = TelegramConfig {
+            chats: vec![
+                ChatConfig {
+                    chat_id: 1,
+                    locales: vec!["en".to_string()],
+                    regions: Vec::new(),
+                    collections: vec![77],
+                    cadence: Cadence::Instant,
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                },
+                ChatConfig {
+                    chat_id: 2,
+                    locales: Vec::new(),
+                    regions: Vec::new(),
+                    collections: Vec::new(),
+                    cadence: Cadence::Instant,
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                },
+            ],
+        };
+
+        let messages = build_messages(&[release], &config);
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].chat_id, 1);
+        assert!(messages[0].text.contains("📚 Франшиза: часть 4 из 5"));
+    }
+
     #[test]
     fn messages_are_chunked_by_limit() {
         let lines = vec![
@@ -310,4 +729,60 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn overlong_line_is_split_without_empty_messages() {
+        let lines = vec![format!("{}\\.{}", "а".repeat(9), "б".repeat(20))];
+
+        let messages = chunk_lines(1, "", &lines, 20);
+
+        assert!(messages.iter().all(|message| !message.text.is_empty()));
+        assert!(messages.iter().all(|message| message.text.len() <= 20));
+        assert_eq!(
+            messages
+                .iter()
+                .map(|message| message.text.as_str())
+                .collect::<String>(),
+            lines[0]
+        );
+        assert!(
+            messages[0].text.ends_with('а'),
+            "экранирование не разрывается"
+        );
+    }
+
+    #[test]
+    fn markup_is_escaped_for_parse_mode() {
+        let text = "<Бег> & «Мы» — 2024-01-01 · ⭐ 7.5";
+
+        assert_eq!(escape_markup(text, None), text);
+        assert_eq!(
+            escape_markup(text, Some(ParseMode::Html)),
+            "&lt;Бег&gt; &amp; «Мы» — 2024-01-01 · ⭐ 7.5"
+        );
+        assert_eq!(
+            escape_markup(text, Some(ParseMode::MarkdownV2)),
+            "<Бег\\> & «Мы» — 2024\\-01\\-01 · ⭐ 7\\.5"
+        );
+    }
+
+    #[test]
+    fn chat_messages_escape_lines_before_chunking() {
+        let now = NaiveDate::from_ymd_opt(2024, 6, 1).expect("валидная дата");
+        let payloads = group_releases_by_chat(
+            &[sample_release(now, "Фильм (2024)", 1)],
+            &test_config(),
+        );
+        let payload = payloads.into_iter().next().expect("рассылка для чата");
+
+        let plain = build_chat_messages(payload.clone(), None, None);
+        let marked = build_chat_messages(payload, None, Some(ParseMode::MarkdownV2));
+
+        assert!(plain[0].text.contains("Фильм (2024) — 2024-06-01"));
+        assert!(
+            marked[0]
+                .text
+                .contains("Фильм \\(2024\\) — 2024\\-06\\-01")
+        );
+    }
 }
@@ -0,0 +1,169 @@
+use std::fmt::Write as _;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ScoringConfig, ScoringWeights};
+
+use super::{ChatRelease, ReleaseKind, sort_chat_releases_by_priority};
+
+/// Через столько дней после выхода релиз перестаёт считаться свежим.
+const RECENCY_DAYS: f64 = 14.0;
+/// Популярность TMDB, которая уже считается максимальной.
+const POPULARITY_CAP: f64 = 1000.0;
+/// Число голосов, которое уже считается максимальным.
+const VOTE_COUNT_CAP: f64 = 10_000.0;
+
+/// Оценка приоритета релиза с вкладом каждого признака.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub total: f64,
+    pub parts: Vec<ScorePart>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScorePart {
+    pub name: String,
+    pub value: f64,
+}
+
+impl Score {
+    fn add(&mut self, name: &str, value: f64) {
+        if value == 0.0 {
+            return;
+        }
+        self.total += value;
+        self.parts.push(ScorePart {
+            name: name.to_string(),
+            value,
+        });
+    }
+
+    /// Разбор оценки для отладки: `5.20 = оценка 2.25 + свежесть 2.00 + тип 1.00`.
+    pub fn explain(&self) -> String {
+        let mut out = format!("{:.2}", self.total);
+        for (index, part) in self.parts.iter().enumerate() {
+            let separator = if index == 0 { " = " } else { " + " };
+            let _ = write!(out, "{separator}{} {:.2}", part.name, part.value);
+        }
+        out
+    }
+}
+
+/// Оценивает релиз: признаки нормируются к 0..1 и взвешиваются, вес типа
+/// релиза добавляется целиком. Свежесть считается от `today`.
+pub fn score_release(release: &ChatRelease, weights: &ScoringWeights, today: NaiveDate) -> Score {
+    let mut score = Score::default();
+
+    if let Some(popularity) = release.popularity {
+        score.add(
+            "популярность",
+            weights.popularity * log_share(popularity, POPULARITY_CAP),
+        );
+    }
+    if let Some(vote_average) = release.vote_average {
+        score.add(
+            "оценка",
+            weights.vote_average * (vote_average / 10.0).clamp(0.0, 1.0),
+        );
+    }
+    if let Some(vote_count) = release.vote_count {
+        score.add(
+            "голоса",
+            weights.vote_count * log_share(f64::from(vote_count), VOTE_COUNT_CAP),
+        );
+    }
+    let age_days = (today - release.event_date).num_days().max(0) as f64;
+    score.add(
+        "свежесть",
+        weights.recency * (1.0 - age_days / RECENCY_DAYS).max(0.0),
+    );
+    if release.has_providers {
+        score.add("стриминг", weights.providers);
+    }
+    if release.followed {
+        score.add("подписка", weights.follow);
+    }
+    let kind = match release.kind {
+        ReleaseKind::Movie => weights.movie,
+        ReleaseKind::TvPremiere => weights.tv_premiere,
+        ReleaseKind::TvSeason { .. } => weights.tv_season,
+    };
+    score.add("тип", kind);
+
+    score
+}
+
+/// Оценивает и сортирует релизы рассылки, отмечая лучшие `highlight_top`.
+pub fn rank_chat_releases(releases: &mut [ChatRelease], scoring: &ScoringConfig, today: NaiveDate) {
+    for release in releases.iter_mut() {
+        release.score = score_release(release, &scoring.weights, today);
+    }
+    sort_chat_releases_by_priority(releases);
+    for (index, release) in releases.iter_mut().enumerate() {
+        release.highlighted = index < scoring.highlight_top;
+    }
+}
+
+/// Логарифмическая доля от `cap`: длинный хвост популярности не забивает
+/// остальные признаки.
+fn log_share(value: f64, cap: f64) -> f64 {
+    ((1.0 + value.max(0.0)).ln() / (1.0 + cap).ln()).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(id: u64, event_date: NaiveDate) -> ChatRelease {
+        ChatRelease {
+            id,
+            title: format!("Релиз {id}"),
+            event_date,
+            kind: ReleaseKind::Movie,
+            vote_average: None,
+            vote_count: None,
+            popularity: None,
+            has_providers: false,
+            genres: Vec::new(),
+            followed: false,
+            collection: None,
+            event_key: format!("movie:{id}"),
+            region: None,
+            score: Score::default(),
+            highlighted: false,
+        }
+    }
+
+    #[test]
+    fn ranking_prefers_followed_and_popular_releases_over_fresh_ones() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).expect("валидная дата");
+        let week_ago = today - chrono::Duration::days(7);
+        let mut fresh = release(1, today);
+        fresh.vote_average = Some(5.0);
+        let mut popular = release(2, week_ago);
+        popular.popularity = Some(999.0);
+        popular.vote_average = Some(8.5);
+        popular.vote_count = Some(9_999);
+        popular.has_providers = true;
+        let mut followed = release(3, week_ago);
+        followed.followed = true;
+        followed.vote_average = Some(7.0);
+        let mut releases = vec![fresh, followed, popular];
+
+        let scoring = ScoringConfig {
+            highlight_top: 1,
+            ..ScoringConfig::default()
+        };
+        rank_chat_releases(&mut releases, &scoring, today);
+
+        let order: Vec<u64> = releases.iter().map(|release| release.id).collect();
+        assert_eq!(order, vec![2, 3, 1]);
+        assert!(releases[0].highlighted);
+        assert!(!releases[1].highlighted);
+        assert_eq!(
+            releases[2].score.explain(),
+            "4.50 = оценка 1.50 + свежесть 2.00 + тип 1.00"
+        );
+    }
+}
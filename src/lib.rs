@@ -1,9 +1,13 @@
 mod formatter;
 
 pub mod app;
+pub mod commands;
 pub mod config;
 pub mod github;
 pub mod orchestrator;
+pub mod preview;
+pub mod schedule;
 pub mod state;
 pub mod telegram;
 pub mod tmdb;
+pub mod webhook;
@@ -1,23 +1,44 @@
 #![allow(dead_code)]
 
+use std::collections::HashMap;
 use std::env;
 
 use chrono::Utc;
+use chrono_tz::Tz;
 use thiserror::Error;
 
-use movie_notifier_bot::config::{ChatConfig, TelegramConfig};
+use movie_notifier_bot::config::{
+    BacklogConfig, BacklogPolicy, Cadence, ChatConfig, ChatMessageOptions, CorrectionAction,
+    CorrectionPolicy, ForumTopics, MessageKind, MessageOptions, ParseMode, QuietHours, QuietMode,
+    ScoringConfig, ScoringWeights, TelegramConfig, WindowPolicy,
+};
 use movie_notifier_bot::github::artifacts::{GitHubArtifactsClient, GitHubCredentials};
-use movie_notifier_bot::orchestrator::{Orchestrator, OrchestratorError};
-use movie_notifier_bot::state::{SentEventHistory, SentHistory, StateError};
+use movie_notifier_bot::orchestrator::{Orchestrator, OrchestratorError, OrchestratorSettings};
+use movie_notifier_bot::preview::{self, PreviewFormat, PreviewFormatError};
+use movie_notifier_bot::schedule::{Schedule, ScheduleError};
+use movie_notifier_bot::state::{
+    BotStateStore, ChatHistoryStore, ReportArchive, SentEventHistory, SentHistory, StateError,
+};
 use movie_notifier_bot::telegram::{
     ConfigError as TelegramConfigError, TelegramDispatcher, TelegramError,
 };
 use movie_notifier_bot::tmdb::{TmdbClient, TmdbError};
+use movie_notifier_bot::webhook::{self, WebhookConfig, WebhookError};
 
 const DEFAULT_HISTORY_FILE_PATH: &str = "state/sent_movie_ids.txt";
 const DEFAULT_HISTORY_ARTIFACT_NAME: &str = "sent-movie-ids";
 const DEFAULT_TV_HISTORY_FILE_PATH: &str = "state/sent_tv_events.txt";
 const DEFAULT_TV_HISTORY_ARTIFACT_NAME: &str = "sent-tv-events";
+const DEFAULT_CHAT_HISTORY_FILE_PATH: &str = "state/sent_per_chat.json";
+const DEFAULT_CHAT_HISTORY_ARTIFACT_NAME: &str = "sent-per-chat";
+const DEFAULT_BOT_STATE_FILE_PATH: &str = "state/bot_state.json";
+const DEFAULT_BOT_STATE_ARTIFACT_NAME: &str = "bot-state";
+const DEFAULT_REPORTS_DIR: &str = "reports";
+const POLL_TIMEOUT_SECS: u64 = 30;
+const POLL_ERROR_BACKOFF: std::time::Duration = std::time::Duration::from_secs(5);
+const DEFAULT_SCHEDULE: &str = "0 */6 * * *";
+const DEFAULT_WEBHOOK_LISTEN: &str = "0.0.0.0:8080";
+const DEFAULT_WEBHOOK_PATH: &str = "/telegram/webhook";
 
 #[derive(Debug, Error)]
 enum AppError {
@@ -27,6 +48,68 @@ enum AppError {
     InvalidRepositoryFormat(String),
     #[error("некорректное значение TELEGRAM_CHAT_ID: {0}")]
     InvalidChatId(String),
+    #[error("некорректное значение TELEGRAM_CHAT_REGIONS: {0}")]
+    InvalidChatRegions(String),
+    #[error("некорректное значение TELEGRAM_CHAT_COLLECTIONS: {0}")]
+    InvalidChatCollections(String),
+    #[error("некорректное значение TELEGRAM_CHAT_CADENCE: {0}")]
+    InvalidChatCadence(String),
+    #[error("некорректное значение TELEGRAM_CHAT_TIMEZONE: {0}")]
+    InvalidChatTimezone(String),
+    #[error("некорректное значение TELEGRAM_CHAT_QUIET_HOURS: {0}")]
+    InvalidChatQuietHours(String),
+    #[error("некорректное значение TELEGRAM_CHAT_TOPICS: {0}")]
+    InvalidChatTopics(String),
+    #[error("некорректное значение TELEGRAM_MESSAGE_OPTIONS: {0}")]
+    InvalidMessageOptions(String),
+    #[error("некорректное значение MAX_RELEASES_PER_RUN: {0}")]
+    InvalidMaxReleases(String),
+    #[error("некорректное значение BACKLOG_MAX_AGE_DAYS: {0}")]
+    InvalidBacklogAge(String),
+    #[error("некорректное значение TELEGRAM_CHAT_BACKLOG: {0}")]
+    InvalidChatBacklog(String),
+    #[error("некорректное значение RELEASE_WINDOW_OVERLAP_HOURS: {0}")]
+    InvalidWindowOverlap(String),
+    #[error("некорректное значение RELEASE_WINDOW_MAX_CATCH_UP_DAYS: {0}")]
+    InvalidCatchUp(String),
+    #[error("некорректное значение SCORING_WEIGHTS: {0}")]
+    InvalidScoringWeights(String),
+    #[error("некорректное значение HIGHLIGHT_TOP_N: {0}")]
+    InvalidHighlightTop(String),
+    #[error("некорректное значение SILENT_SCORE_BELOW: {0}")]
+    InvalidSilentScore(String),
+    #[error("некорректное значение CORRECTION_RECHECK_DAYS: {0}")]
+    InvalidRecheckDays(String),
+    #[error("некорректное значение CORRECTION_MAX_RECHECKS: {0}")]
+    InvalidMaxRechecks(String),
+    #[error("некорректное значение CORRECTION_ACTION: {0} (ожидается post, edit или delete)")]
+    InvalidCorrectionAction(String),
+    #[error("некорректное значение BOT_MODE: {0}")]
+    InvalidMode(String),
+    #[error("не удалось открыть порт для вебхука {address}: {source}")]
+    WebhookBind {
+        address: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Webhook(#[from] WebhookError),
+    #[error("некорректное значение BOT_SCHEDULE: {0}")]
+    Schedule(#[from] ScheduleError),
+    #[error("расписание BOT_SCHEDULE не даёт ни одного запуска")]
+    EmptySchedule,
+    #[error(transparent)]
+    PreviewFormat(#[from] PreviewFormatError),
+    #[error("не удалось записать предпросмотр в {path}: {source}")]
+    PreviewOutput {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("не удалось подписаться на сигналы завершения: {0}")]
+    Signal(std::io::Error),
+    #[error("не удалось доставить уведомления в чаты {0:?}")]
+    DeliveryFailed(Vec<i64>),
     #[error(transparent)]
     State(#[from] StateError),
     #[error(transparent)]
@@ -42,13 +125,51 @@ enum AppError {
 #[tokio::main]
 async fn main() -> Result<(), AppError> {
     let config = AppConfig::from_env()?;
+    let mode = config.mode;
+    let commands = config.commands;
+    let schedule = config.schedule.clone();
+    match mode {
+        RunMode::SetWebhook => {
+            let url = required_env("WEBHOOK_URL")?;
+            let secret = required_env("WEBHOOK_SECRET")?;
+            config.dispatcher().set_webhook(&url, &secret).await?;
+            println!("Вебхук установлен: {url}");
+            return Ok(());
+        }
+        RunMode::DeleteWebhook => {
+            config.dispatcher().delete_webhook().await?;
+            println!("Вебхук снят");
+            return Ok(());
+        }
+        RunMode::Run | RunMode::Poll | RunMode::Webhook | RunMode::Daemon | RunMode::Preview => {}
+    }
+
     let mut orchestrator = config.build_orchestrator()?;
 
+    match mode {
+        RunMode::Poll => return poll_updates(&mut orchestrator).await,
+        RunMode::Webhook => return serve_webhook(&mut orchestrator).await,
+        RunMode::Daemon => {
+            let schedule = schedule.expect("расписание разбирается для режима daemon");
+            return run_daemon(&mut orchestrator, &schedule, commands).await;
+        }
+        RunMode::Preview => return preview(&mut orchestrator).await,
+        RunMode::Run | RunMode::SetWebhook | RunMode::DeleteWebhook => {}
+    }
+
     let now = Utc::now();
+    if commands {
+        orchestrator.process_updates(now, 0).await?;
+    }
     let summary = orchestrator.run(now).await?;
 
     println!("{}", summary.render_markdown());
 
+    let failed = summary.failed_chats();
+    if !failed.is_empty() {
+        return Err(AppError::DeliveryFailed(failed));
+    }
+
     Ok(())
 }
 
@@ -56,13 +177,196 @@ fn required_env(name: &str) -> Result<String, AppError> {
     env::var(name).map_err(|_| AppError::MissingEnv(name.to_owned()))
 }
 
+/// Бесконечный long polling: команды обрабатываются по мере поступления.
+async fn poll_updates(
+    orchestrator: &mut Orchestrator<GitHubArtifactsClient, TmdbClient, TelegramDispatcher>,
+) -> Result<(), AppError> {
+    loop {
+        if let Err(err) = orchestrator
+            .process_updates(Utc::now(), POLL_TIMEOUT_SECS)
+            .await
+        {
+            eprintln!("WARN: ошибка обработки обновлений Telegram, повтор через паузу: {err}");
+            tokio::time::sleep(POLL_ERROR_BACKOFF).await;
+        }
+    }
+}
+
+/// Долгоживущий режим: прогоны по расписанию, между ними — команды чатов.
+///
+/// Прогоны идут в одной задаче друг за другом, поэтому не перекрываются: если
+/// прогон затянулся дольше интервала, пропущенные слоты не догоняются. По SIGTERM
+/// или Ctrl+C текущий прогон или пачка команд доделывается, после чего демон выходит.
+async fn run_daemon(
+    orchestrator: &mut Orchestrator<GitHubArtifactsClient, TmdbClient, TelegramDispatcher>,
+    schedule: &Schedule,
+    commands: bool,
+) -> Result<(), AppError> {
+    let mut shutdown = shutdown_signal()?;
+
+    loop {
+        let next_run = schedule
+            .next_after(Utc::now())
+            .ok_or(AppError::EmptySchedule)?;
+        println!("Следующий прогон: {next_run}");
+
+        while Utc::now() < next_run {
+            if *shutdown.borrow() {
+                println!("Получен сигнал завершения, демон остановлен");
+                return Ok(());
+            }
+            let remaining = (next_run - Utc::now()).to_std().unwrap_or_default();
+            if commands && remaining.as_secs() > 0 {
+                let timeout = remaining.as_secs().min(POLL_TIMEOUT_SECS);
+                let cancel = async {
+                    if shutdown.changed().await.is_err() {
+                        std::future::pending::<()>().await;
+                    }
+                };
+                if let Err(err) = orchestrator
+                    .process_updates_until(Utc::now(), timeout, cancel)
+                    .await
+                {
+                    eprintln!(
+                        "WARN: ошибка обработки обновлений Telegram, повтор через паузу: {err}"
+                    );
+                    tokio::select! {
+                        _ = shutdown.changed() => {}
+                        _ = tokio::time::sleep(POLL_ERROR_BACKOFF.min(remaining)) => {}
+                    }
+                }
+            } else {
+                tokio::select! {
+                    _ = shutdown.changed() => {}
+                    _ = tokio::time::sleep(remaining) => {}
+                }
+            }
+        }
+
+        if *shutdown.borrow() {
+            println!("Получен сигнал завершения, демон остановлен");
+            return Ok(());
+        }
+        match orchestrator.run(Utc::now()).await {
+            Ok(summary) => println!("{}", summary.render_markdown()),
+            Err(err) => eprintln!("WARN: прогон завершился ошибкой, жду следующего: {err}"),
+        }
+    }
+}
+
+/// Подписывается на SIGTERM и Ctrl+C сразу, чтобы сигнал во время прогона
+/// не завершил процесс, а лишь выставил флаг остановки.
+fn shutdown_signal() -> Result<tokio::sync::watch::Receiver<bool>, AppError> {
+    let (sender, receiver) = tokio::sync::watch::channel(false);
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut terminate = signal(SignalKind::terminate()).map_err(AppError::Signal)?;
+        let mut interrupt = signal(SignalKind::interrupt()).map_err(AppError::Signal)?;
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = interrupt.recv() => {}
+            }
+            let _ = sender.send(true);
+        });
+    }
+    #[cfg(not(unix))]
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = sender.send(true);
+        }
+    });
+
+    Ok(receiver)
+}
+
+/// HTTP-приёмник вебхуков Telegram; работает до SIGTERM или Ctrl+C.
+async fn serve_webhook(
+    orchestrator: &mut Orchestrator<GitHubArtifactsClient, TmdbClient, TelegramDispatcher>,
+) -> Result<(), AppError> {
+    let address = env::var("WEBHOOK_LISTEN").unwrap_or_else(|_| DEFAULT_WEBHOOK_LISTEN.to_owned());
+    let config = WebhookConfig {
+        path: env::var("WEBHOOK_PATH").unwrap_or_else(|_| DEFAULT_WEBHOOK_PATH.to_owned()),
+        secret: required_env("WEBHOOK_SECRET")?,
+    };
+    let listener = tokio::net::TcpListener::bind(&address)
+        .await
+        .map_err(|source| AppError::WebhookBind { address, source })?;
+
+    let mut shutdown = shutdown_signal()?;
+    let shutdown = async move {
+        if shutdown.wait_for(|stop| *stop).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    webhook::serve(&listener, &config, orchestrator, shutdown).await?;
+    Ok(())
+}
+
+/// Печатает или сохраняет сообщения, которые отправил бы прогон, ничего не отправляя.
+async fn preview(
+    orchestrator: &mut Orchestrator<GitHubArtifactsClient, TmdbClient, TelegramDispatcher>,
+) -> Result<(), AppError> {
+    let format: PreviewFormat = env::var("PREVIEW_FORMAT").unwrap_or_default().parse()?;
+    let chats = orchestrator.preview(Utc::now()).await?;
+    let rendered = preview::render(&chats, format);
+
+    match env::var("PREVIEW_OUTPUT") {
+        Ok(path) if !path.trim().is_empty() => {
+            std::fs::write(&path, rendered).map_err(|source| AppError::PreviewOutput {
+                path: path.clone(),
+                source,
+            })?;
+            println!("Предпросмотр сохранён в {path}");
+        }
+        _ => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunMode {
+    /// Один прогон рассылки (по умолчанию).
+    Run,
+    /// Только обработка команд через `getUpdates`.
+    Poll,
+    /// Приём обновлений через вебхук.
+    Webhook,
+    /// Прогоны по расписанию `BOT_SCHEDULE` в одном процессе.
+    Daemon,
+    /// Регистрация вебхука (`setWebhook`) и выход.
+    SetWebhook,
+    /// Снятие вебхука (`deleteWebhook`) и выход.
+    DeleteWebhook,
+    /// Полный прогон без отправки и сохранения: только вывод сообщений.
+    Preview,
+}
+
 #[derive(Debug, Clone)]
 struct AppConfig {
     tmdb_api_key: String,
     telegram_token: String,
     telegram_chats: Vec<i64>,
+    chat_regions: HashMap<i64, Vec<String>>,
+    chat_collections: HashMap<i64, Vec<u64>>,
+    chat_cadence: HashMap<i64, Cadence>,
+    chat_timezones: HashMap<i64, Tz>,
+    chat_quiet_hours: HashMap<i64, QuietHours>,
+    chat_topics: HashMap<i64, ForumTopics>,
+    message_options: HashMap<i64, ChatMessageOptions>,
+    backlog: BacklogConfig,
+    window: WindowPolicy,
+    scoring: ScoringConfig,
+    corrections: CorrectionPolicy,
     github_repo: String,
     github_token: String,
+    change_feed: bool,
+    mode: RunMode,
+    commands: bool,
+    /// Расписание прогонов; разбирается только для режима `daemon`.
+    schedule: Option<Schedule>,
 }
 
 impl AppConfig {
@@ -70,26 +374,107 @@ impl AppConfig {
         let tmdb_api_key = required_env("TMDB_API_KEY")?;
         let telegram_token = required_env("TELEGRAM_BOT_TOKEN")?;
         let telegram_chats = parse_chat_ids(&required_env("TELEGRAM_CHAT_ID")?)?;
+        let chat_regions = match env::var("TELEGRAM_CHAT_REGIONS") {
+            Ok(raw) => parse_chat_regions(&raw)?,
+            Err(_) => HashMap::new(),
+        };
+        let chat_collections = match env::var("TELEGRAM_CHAT_COLLECTIONS") {
+            Ok(raw) => parse_chat_collections(&raw)?,
+            Err(_) => HashMap::new(),
+        };
+        let chat_cadence = match env::var("TELEGRAM_CHAT_CADENCE") {
+            Ok(raw) => parse_chat_cadence(&raw)?,
+            Err(_) => HashMap::new(),
+        };
+        let chat_timezones = match env::var("TELEGRAM_CHAT_TIMEZONE") {
+            Ok(raw) => parse_chat_timezones(&raw)?,
+            Err(_) => HashMap::new(),
+        };
+        let chat_quiet_hours = match env::var("TELEGRAM_CHAT_QUIET_HOURS") {
+            Ok(raw) => parse_chat_quiet_hours(&raw)?,
+            Err(_) => HashMap::new(),
+        };
+        let chat_topics = match env::var("TELEGRAM_CHAT_TOPICS") {
+            Ok(raw) => parse_chat_topics(&raw)?,
+            Err(_) => HashMap::new(),
+        };
+        let message_options = match env::var("TELEGRAM_MESSAGE_OPTIONS") {
+            Ok(raw) => parse_message_options(&raw)?,
+            Err(_) => HashMap::new(),
+        };
+        let backlog = backlog_config_from_env()?;
+        let window = window_policy_from_env()?;
+        let scoring = scoring_config_from_env()?;
+        let corrections = correction_policy_from_env()?;
         let github_repo = required_env("GITHUB_REPOSITORY")?;
         let github_token = required_env("GITHUB_TOKEN")?;
+        let change_feed = flag_env("TMDB_CHANGE_FEED");
+        let mode = match env::var("BOT_MODE").as_deref().map(str::trim) {
+            Err(_) | Ok("") | Ok("run") => RunMode::Run,
+            Ok("poll") => RunMode::Poll,
+            Ok("webhook") => RunMode::Webhook,
+            Ok("daemon") => RunMode::Daemon,
+            Ok("set-webhook") => RunMode::SetWebhook,
+            Ok("delete-webhook") => RunMode::DeleteWebhook,
+            Ok("preview") => RunMode::Preview,
+            Ok(other) => return Err(AppError::InvalidMode(other.to_owned())),
+        };
+        let commands = flag_env("TELEGRAM_COMMANDS");
+        let schedule = match mode {
+            RunMode::Daemon => Some(
+                env::var("BOT_SCHEDULE")
+                    .unwrap_or_else(|_| DEFAULT_SCHEDULE.to_owned())
+                    .parse()?,
+            ),
+            _ => None,
+        };
 
         Ok(Self {
             tmdb_api_key,
             telegram_token,
             telegram_chats,
+            chat_regions,
+            chat_collections,
+            chat_cadence,
+            chat_timezones,
+            chat_quiet_hours,
+            chat_topics,
+            message_options,
+            backlog,
+            window,
+            scoring,
+            corrections,
             github_repo,
             github_token,
+            change_feed,
+            mode,
+            commands,
+            schedule,
         })
     }
 
+    fn dispatcher(&self) -> TelegramDispatcher {
+        TelegramDispatcher::new(self.telegram_token.clone(), self.telegram_chats.clone())
+    }
+
     fn build_orchestrator(
         self,
     ) -> Result<Orchestrator<GitHubArtifactsClient, TmdbClient, TelegramDispatcher>, AppError> {
         let creds = github_credentials_from_env(&self.github_repo, &self.github_token)?;
+        let dispatcher = self.dispatcher();
         let (history_file, history_artifact) = history_config_from_env();
         let (tv_history_file, tv_history_artifact) = tv_history_config_from_env();
+        let (chat_history_file, chat_history_artifact) = chat_history_config_from_env();
+        let (state_file, state_artifact) = bot_state_config_from_env();
         let history = SentHistory::new(history_file, history_artifact, creds.clone())?;
-        let tv_history = SentEventHistory::new(tv_history_file, tv_history_artifact, creds)?;
+        let tv_history =
+            SentEventHistory::new(tv_history_file, tv_history_artifact, creds.clone())?;
+        let chat_history =
+            ChatHistoryStore::new(chat_history_file, chat_history_artifact, creds.clone())?;
+        let state_store = BotStateStore::new(state_file, state_artifact, creds.clone())?;
+        let reports_dir =
+            env::var("REPORTS_DIR").unwrap_or_else(|_| DEFAULT_REPORTS_DIR.to_owned());
+        let report_archive = ReportArchive::new(reports_dir, creds)?;
 
         let telegram_config = TelegramConfig {
             chats: self
@@ -99,12 +484,36 @@ impl AppConfig {
                 .map(|chat_id| ChatConfig {
                     chat_id,
                     locales: Vec::new(),
+                    regions: self.chat_regions.get(&chat_id).cloned().unwrap_or_default(),
+                    collections: self
+                        .chat_collections
+                        .get(&chat_id)
+                        .cloned()
+                        .unwrap_or_default(),
+                    cadence: self.chat_cadence.get(&chat_id).copied().unwrap_or_default(),
+                    timezone: self
+                        .chat_timezones
+                        .get(&chat_id)
+                        .copied()
+                        .unwrap_or_default(),
+                    quiet_hours: self.chat_quiet_hours.get(&chat_id).copied(),
+                    topics: self.chat_topics.get(&chat_id).cloned().unwrap_or_default(),
+                    message_options: self
+                        .message_options
+                        .get(&chat_id)
+                        .cloned()
+                        .unwrap_or_default(),
                 })
                 .collect(),
         };
 
-        let tmdb_client = TmdbClient::new(self.tmdb_api_key);
-        let dispatcher = TelegramDispatcher::new(self.telegram_token, self.telegram_chats.clone());
+        let tmdb_client = TmdbClient::new(self.tmdb_api_key).with_followed_collections(
+            telegram_config
+                .chats
+                .iter()
+                .flat_map(|chat| chat.collections.iter().copied()),
+        );
+        let update_channel = dispatcher.clone();
 
         Ok(Orchestrator::new(
             history,
@@ -112,7 +521,18 @@ impl AppConfig {
             tmdb_client,
             dispatcher,
             telegram_config,
-        ))
+        )
+        .with_settings(OrchestratorSettings {
+            change_feed: self.change_feed,
+            backlog: self.backlog,
+            window: self.window,
+            scoring: self.scoring,
+            corrections: self.corrections,
+        })
+        .with_state_store(state_store)
+        .with_chat_history_store(chat_history)
+        .with_report_archive(report_archive)
+        .with_update_channel(update_channel))
     }
 }
 
@@ -140,6 +560,143 @@ fn tv_history_config_from_env() -> (String, String) {
     (file_path, artifact_name)
 }
 
+fn chat_history_config_from_env() -> (String, String) {
+    let file_path = env::var("CHAT_HISTORY_FILE_PATH")
+        .unwrap_or_else(|_| DEFAULT_CHAT_HISTORY_FILE_PATH.to_owned());
+    let artifact_name = env::var("CHAT_HISTORY_ARTIFACT_NAME")
+        .unwrap_or_else(|_| DEFAULT_CHAT_HISTORY_ARTIFACT_NAME.to_owned());
+    (file_path, artifact_name)
+}
+
+fn bot_state_config_from_env() -> (String, String) {
+    let file_path =
+        env::var("BOT_STATE_FILE_PATH").unwrap_or_else(|_| DEFAULT_BOT_STATE_FILE_PATH.to_owned());
+    let artifact_name = env::var("BOT_STATE_ARTIFACT_NAME")
+        .unwrap_or_else(|_| DEFAULT_BOT_STATE_ARTIFACT_NAME.to_owned());
+    (file_path, artifact_name)
+}
+
+fn backlog_config_from_env() -> Result<BacklogConfig, AppError> {
+    let mut default = BacklogPolicy::default();
+    if let Ok(raw) = env::var("MAX_RELEASES_PER_RUN") {
+        default.max_releases_per_run = match raw.trim().parse() {
+            Ok(limit) if limit > 0 => limit,
+            _ => return Err(AppError::InvalidMaxReleases(raw)),
+        };
+    }
+    if let Ok(raw) = env::var("BACKLOG_MAX_AGE_DAYS") {
+        default.max_age_days = match raw.trim().parse() {
+            Ok(days) if days >= 0 => days,
+            _ => return Err(AppError::InvalidBacklogAge(raw)),
+        };
+    }
+    let chats = match env::var("TELEGRAM_CHAT_BACKLOG") {
+        Ok(raw) => parse_chat_backlog(&raw, default)?,
+        Err(_) => HashMap::new(),
+    };
+    Ok(BacklogConfig { default, chats })
+}
+
+fn window_policy_from_env() -> Result<WindowPolicy, AppError> {
+    let mut policy = WindowPolicy::default();
+    if let Ok(raw) = env::var("RELEASE_WINDOW_OVERLAP_HOURS") {
+        policy.overlap_hours = match raw.trim().parse() {
+            Ok(hours) if hours >= 0 => hours,
+            _ => return Err(AppError::InvalidWindowOverlap(raw)),
+        };
+    }
+    if let Ok(raw) = env::var("RELEASE_WINDOW_MAX_CATCH_UP_DAYS") {
+        policy.max_catch_up_days = match raw.trim().parse() {
+            Ok(days) if days > 0 => days,
+            _ => return Err(AppError::InvalidCatchUp(raw)),
+        };
+    }
+    Ok(policy)
+}
+
+fn scoring_config_from_env() -> Result<ScoringConfig, AppError> {
+    let mut scoring = ScoringConfig::default();
+    if let Ok(raw) = env::var("SCORING_WEIGHTS") {
+        scoring.weights = parse_scoring_weights(&raw)?;
+    }
+    if let Ok(raw) = env::var("HIGHLIGHT_TOP_N") {
+        scoring.highlight_top = raw
+            .trim()
+            .parse()
+            .map_err(|_| AppError::InvalidHighlightTop(raw.clone()))?;
+    }
+    if let Ok(raw) = env::var("SILENT_SCORE_BELOW") {
+        scoring.silent_below = match raw.trim().parse::<f64>() {
+            Ok(threshold) if threshold.is_finite() => Some(threshold),
+            _ => return Err(AppError::InvalidSilentScore(raw)),
+        };
+    }
+    Ok(scoring)
+}
+
+/// Разбирает `SCORING_WEIGHTS` вида `popularity=2,votes=3,follow=5`; не указанные
+/// веса остаются по умолчанию.
+fn parse_scoring_weights(raw: &str) -> Result<ScoringWeights, AppError> {
+    let mut weights = ScoringWeights::default();
+    for entry in raw
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let invalid = || AppError::InvalidScoringWeights(entry.to_owned());
+        let (name, value) = entry.split_once('=').ok_or_else(invalid)?;
+        let value: f64 = value.trim().parse().map_err(|_| invalid())?;
+        if !value.is_finite() {
+            return Err(invalid());
+        }
+        let slot = match name.trim() {
+            "popularity" => &mut weights.popularity,
+            "votes" => &mut weights.vote_average,
+            "vote_count" => &mut weights.vote_count,
+            "recency" => &mut weights.recency,
+            "providers" => &mut weights.providers,
+            "follow" => &mut weights.follow,
+            "movie" => &mut weights.movie,
+            "tv_premiere" => &mut weights.tv_premiere,
+            "tv_season" => &mut weights.tv_season,
+            _ => return Err(invalid()),
+        };
+        *slot = value;
+    }
+    Ok(weights)
+}
+
+fn correction_policy_from_env() -> Result<CorrectionPolicy, AppError> {
+    let mut policy = CorrectionPolicy::default();
+    if let Ok(raw) = env::var("CORRECTION_RECHECK_DAYS") {
+        policy.recheck_days = match raw.trim().parse() {
+            Ok(days) if days >= 0 => days,
+            _ => return Err(AppError::InvalidRecheckDays(raw)),
+        };
+    }
+    if let Ok(raw) = env::var("CORRECTION_MAX_RECHECKS") {
+        policy.max_rechecks = match raw.trim().parse() {
+            Ok(limit) if limit > 0 => limit,
+            _ => return Err(AppError::InvalidMaxRechecks(raw)),
+        };
+    }
+    if let Ok(raw) = env::var("CORRECTION_ACTION") {
+        policy.action = match raw.trim().to_lowercase().as_str() {
+            "" | "post" => CorrectionAction::Post,
+            "edit" => CorrectionAction::Edit,
+            "delete" => CorrectionAction::Delete,
+            _ => return Err(AppError::InvalidCorrectionAction(raw)),
+        };
+    }
+    Ok(policy)
+}
+
+fn flag_env(name: &str) -> bool {
+    env::var(name)
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
 fn parse_chat_ids(raw: &str) -> Result<Vec<i64>, AppError> {
     let mut ids = Vec::new();
     for value in raw.split(',') {
@@ -160,3 +717,271 @@ fn parse_chat_ids(raw: &str) -> Result<Vec<i64>, AppError> {
 
     Ok(ids)
 }
+
+/// Разбирает `TELEGRAM_CHAT_REGIONS` вида `-100123:US,GB;-100456:DE`.
+fn parse_chat_regions(raw: &str) -> Result<HashMap<i64, Vec<String>>, AppError> {
+    let lists = parse_per_chat_lists(raw).map_err(AppError::InvalidChatRegions)?;
+    let mut regions = HashMap::new();
+    for (chat_id, codes) in lists {
+        let codes: Vec<String> = codes.iter().map(|code| code.to_uppercase()).collect();
+        if codes
+            .iter()
+            .any(|code| code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()))
+        {
+            return Err(AppError::InvalidChatRegions(codes.join(",")));
+        }
+        regions.insert(chat_id, codes);
+    }
+
+    Ok(regions)
+}
+
+/// Разбирает `TELEGRAM_CHAT_COLLECTIONS` вида `-100123:10,1241;-100456:86311`.
+fn parse_chat_collections(raw: &str) -> Result<HashMap<i64, Vec<u64>>, AppError> {
+    let lists = parse_per_chat_lists(raw).map_err(AppError::InvalidChatCollections)?;
+    let mut collections = HashMap::new();
+    for (chat_id, ids) in lists {
+        let ids = ids
+            .iter()
+            .map(|id| {
+                id.parse()
+                    .map_err(|_| AppError::InvalidChatCollections(id.clone()))
+            })
+            .collect::<Result<Vec<u64>, _>>()?;
+        collections.insert(chat_id, ids);
+    }
+
+    Ok(collections)
+}
+
+/// Разбирает `TELEGRAM_CHAT_CADENCE` вида `-100123:daily,9;-100456:weekly,mon,10`.
+fn parse_chat_cadence(raw: &str) -> Result<HashMap<i64, Cadence>, AppError> {
+    let lists = parse_per_chat_lists(raw).map_err(AppError::InvalidChatCadence)?;
+    let mut cadence = HashMap::new();
+    for (chat_id, values) in lists {
+        let invalid = || AppError::InvalidChatCadence(values.join(","));
+        let hour = |raw: &str| match raw.parse::<u32>() {
+            Ok(hour) if hour < 24 => Ok(hour),
+            _ => Err(invalid()),
+        };
+        let parsed = match values.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["instant"] => Cadence::Instant,
+            ["daily", raw_hour] => Cadence::Daily {
+                hour: hour(raw_hour)?,
+            },
+            ["weekly", weekday, raw_hour] => Cadence::Weekly {
+                weekday: weekday.parse().map_err(|_| invalid())?,
+                hour: hour(raw_hour)?,
+            },
+            _ => return Err(invalid()),
+        };
+        cadence.insert(chat_id, parsed);
+    }
+
+    Ok(cadence)
+}
+
+/// Разбирает `TELEGRAM_CHAT_TOPICS` вида
+/// `-100123:movies=12,tv_premieres=14,tv_seasons=15,digest=16,genre:horror=20`.
+fn parse_chat_topics(raw: &str) -> Result<HashMap<i64, ForumTopics>, AppError> {
+    let lists = parse_per_chat_lists(raw).map_err(AppError::InvalidChatTopics)?;
+    let mut topics = HashMap::new();
+    for (chat_id, values) in lists {
+        let mut chat_topics = ForumTopics::default();
+        for value in &values {
+            let invalid = || AppError::InvalidChatTopics(value.clone());
+            let (route, thread_id) = value.split_once('=').ok_or_else(invalid)?;
+            let thread_id: i64 = thread_id.trim().parse().map_err(|_| invalid())?;
+            match route.trim() {
+                "movies" => chat_topics.movies = Some(thread_id),
+                "tv_premieres" => chat_topics.tv_premieres = Some(thread_id),
+                "tv_seasons" => chat_topics.tv_seasons = Some(thread_id),
+                "digest" => chat_topics.digest = Some(thread_id),
+                route => {
+                    let genre = route
+                        .strip_prefix("genre:")
+                        .filter(|genre| !genre.trim().is_empty())
+                        .ok_or_else(invalid)?;
+                    chat_topics
+                        .genres
+                        .insert(genre.trim().to_lowercase(), thread_id);
+                }
+            }
+        }
+        topics.insert(chat_id, chat_topics);
+    }
+
+    Ok(topics)
+}
+
+/// Разбирает `TELEGRAM_MESSAGE_OPTIONS` вида
+/// `-100123:preview=large,protect,digest.silent,movies.reply_to=42`. Флаг без
+/// префикса задаёт параметр всем сообщениям чата, с префиксом `movies.`,
+/// `tv_premieres.`, `tv_seasons.` или `digest.` — только этому типу.
+fn parse_message_options(raw: &str) -> Result<HashMap<i64, ChatMessageOptions>, AppError> {
+    let lists = parse_per_chat_lists(raw).map_err(AppError::InvalidMessageOptions)?;
+    let mut options = HashMap::new();
+    for (chat_id, values) in lists {
+        let mut chat_options = ChatMessageOptions::default();
+        let mut by_kind = Vec::new();
+        for value in &values {
+            let kind = match value.split_once('.') {
+                Some(("movies", flag)) => Some((MessageKind::Movie, flag)),
+                Some(("tv_premieres", flag)) => Some((MessageKind::TvPremiere, flag)),
+                Some(("tv_seasons", flag)) => Some((MessageKind::TvSeason, flag)),
+                Some(("digest", flag)) => Some((MessageKind::Digest, flag)),
+                _ => None,
+            };
+            match kind {
+                Some(kind_flag) => by_kind.push(kind_flag),
+                None => apply_message_flag(&mut chat_options.default, value)?,
+            }
+        }
+        // Типы наследуют общие флаги чата независимо от порядка в строке.
+        for (kind, flag) in by_kind {
+            let kind_options = chat_options
+                .kinds
+                .entry(kind)
+                .or_insert_with(|| chat_options.default.clone());
+            apply_message_flag(kind_options, flag)?;
+        }
+        options.insert(chat_id, chat_options);
+    }
+
+    Ok(options)
+}
+
+fn apply_message_flag(options: &mut MessageOptions, flag: &str) -> Result<(), AppError> {
+    let invalid = || AppError::InvalidMessageOptions(flag.to_owned());
+    let preview = &mut options.link_preview;
+    match flag.split_once('=') {
+        None if flag == "silent" => options.disable_notification = true,
+        None if flag == "protect" => options.protect_content = true,
+        Some(("preview", "off")) => preview.is_disabled = true,
+        Some(("preview", "on")) => preview.is_disabled = false,
+        Some(("preview", "large")) => {
+            preview.is_disabled = false;
+            preview.prefer_large_media = true;
+        }
+        Some(("preview", "above")) => {
+            preview.is_disabled = false;
+            preview.show_above_text = true;
+        }
+        Some(("preview_url", url)) if !url.trim().is_empty() => {
+            preview.is_disabled = false;
+            preview.url = Some(url.trim().to_owned());
+        }
+        Some(("parse_mode", "html")) => options.parse_mode = Some(ParseMode::Html),
+        Some(("parse_mode", "markdown")) => options.parse_mode = Some(ParseMode::MarkdownV2),
+        Some(("reply_to", message_id)) => {
+            options.reply_to_message_id = Some(message_id.trim().parse().map_err(|_| invalid())?);
+        }
+        _ => return Err(invalid()),
+    }
+    Ok(())
+}
+
+/// Разбирает `TELEGRAM_CHAT_TIMEZONE` вида `-100123:Europe/Moscow;-100456:America/New_York`
+/// в часовые пояса IANA.
+fn parse_chat_timezones(raw: &str) -> Result<HashMap<i64, Tz>, AppError> {
+    let lists = parse_per_chat_lists(raw).map_err(AppError::InvalidChatTimezone)?;
+    let mut timezones = HashMap::new();
+    for (chat_id, values) in lists {
+        let [name] = values.as_slice() else {
+            return Err(AppError::InvalidChatTimezone(values.join(",")));
+        };
+        let timezone = name
+            .trim()
+            .parse::<Tz>()
+            .map_err(|_| AppError::InvalidChatTimezone(name.clone()))?;
+        timezones.insert(chat_id, timezone);
+    }
+
+    Ok(timezones)
+}
+
+/// Разбирает `TELEGRAM_CHAT_QUIET_HOURS` вида `-100123:23-8;-100456:22-7,silent`.
+fn parse_chat_quiet_hours(raw: &str) -> Result<HashMap<i64, QuietHours>, AppError> {
+    let lists = parse_per_chat_lists(raw).map_err(AppError::InvalidChatQuietHours)?;
+    let mut quiet_hours = HashMap::new();
+    for (chat_id, values) in lists {
+        let invalid = || AppError::InvalidChatQuietHours(values.join(","));
+        let (range, mode) = match values.as_slice() {
+            [range] => (range, QuietMode::Defer),
+            [range, mode] if mode == "defer" => (range, QuietMode::Defer),
+            [range, mode] if mode == "silent" => (range, QuietMode::Silent),
+            _ => return Err(invalid()),
+        };
+        let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+        let hour = |raw: &str| match raw.trim().parse::<u32>() {
+            Ok(hour) if hour < 24 => Ok(hour),
+            _ => Err(invalid()),
+        };
+        let (start, end) = (hour(start)?, hour(end)?);
+        if start == end {
+            return Err(invalid());
+        }
+        quiet_hours.insert(chat_id, QuietHours { start, end, mode });
+    }
+
+    Ok(quiet_hours)
+}
+
+/// Разбирает `TELEGRAM_CHAT_BACKLOG` вида `-100123:5,14;-100456:20`: лимит релизов
+/// за прогон и, необязательно, срок жизни очереди в днях.
+fn parse_chat_backlog(
+    raw: &str,
+    default: BacklogPolicy,
+) -> Result<HashMap<i64, BacklogPolicy>, AppError> {
+    let lists = parse_per_chat_lists(raw).map_err(AppError::InvalidChatBacklog)?;
+    let mut policies = HashMap::new();
+    for (chat_id, values) in lists {
+        let invalid = || AppError::InvalidChatBacklog(values.join(","));
+        let (limit, max_age) = match values.as_slice() {
+            [limit] => (limit, None),
+            [limit, max_age] => (limit, Some(max_age)),
+            _ => return Err(invalid()),
+        };
+        let max_releases_per_run = match limit.parse() {
+            Ok(limit) if limit > 0 => limit,
+            _ => return Err(invalid()),
+        };
+        let max_age_days = match max_age.map(|days| days.parse()) {
+            None => default.max_age_days,
+            Some(Ok(days)) if days >= 0 => days,
+            Some(_) => return Err(invalid()),
+        };
+        policies.insert(
+            chat_id,
+            BacklogPolicy {
+                max_releases_per_run,
+                max_age_days,
+            },
+        );
+    }
+
+    Ok(policies)
+}
+
+/// Общий разбор списков вида `chat_id:a,b;chat_id:c`; ошибка содержит некорректную запись.
+fn parse_per_chat_lists(raw: &str) -> Result<Vec<(i64, Vec<String>)>, String> {
+    let mut lists = Vec::new();
+    for entry in raw.split(';') {
+        let trimmed = entry.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let (chat_id, values) = trimmed.split_once(':').ok_or_else(|| trimmed.to_owned())?;
+        let chat_id: i64 = chat_id.trim().parse().map_err(|_| trimmed.to_owned())?;
+        let values = values
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_owned)
+            .collect();
+        lists.push((chat_id, values));
+    }
+
+    Ok(lists)
+}
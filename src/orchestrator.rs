@@ -1,30 +1,321 @@
 use async_trait::async_trait;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use thiserror::Error;
-use tracing::info;
+use tracing::{debug, info, warn};
 
-use crate::config::TelegramConfig;
-use crate::formatter::{DigitalRelease, ReleaseKind, build_messages, sort_releases_by_priority};
-use crate::state::{SentEventHistory, SentHistory, StateError};
-use crate::telegram::TelegramDispatcher;
-use crate::tmdb::{MovieRelease, ReleaseWindow, TmdbClient, TvEvent, TvEventKind};
+use crate::commands::{
+    BotCommand, HELP_TEXT, apply_command, deactivate_chat, effective_config, migrate_chat,
+    parse_command,
+};
+use crate::config::{
+    BacklogConfig, Cadence, ChatConfig, CorrectionAction, CorrectionPolicy, ForumTopics,
+    MessageKind, MessageOptions, ParseMode, QuietMode, ScoringConfig, TelegramConfig, WindowPolicy,
+};
+use crate::formatter::{
+    ChatPayload, ChatRelease, DigitalRelease, ReleaseKind, build_chat_messages, build_messages,
+    escape_markup, group_releases_by_chat, rank_chat_releases, release_line, release_targets_chat,
+    sort_releases_by_priority,
+};
+use crate::preview::ChatPreview;
+use crate::state::{
+    Announcement, BotState, BotStateStore, ChatHistory, ChatHistoryStore, OutboxEntry,
+    OutboxStatus, PossibleDuplicateRecord, ReportArchive, SentEventHistory, SentHistory,
+    StateError,
+};
+use crate::telegram::{
+    IncomingMessage, PossibleDuplicate, TelegramDispatcher, TelegramError, Update,
+};
+use crate::tmdb::{
+    DigitalReleaseDate, FilterReport, MovieRelease, ReleaseWindow, TmdbClient, TvEvent, TvEventKind,
+};
 
 pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
 
+/// Ошибка отправки в чат вместе с `message_id` сообщений, ушедших до неё.
+#[derive(Debug, Error)]
+#[error("{error}")]
+pub struct SendFailure {
+    pub sent: Vec<Option<i64>>,
+    pub error: BoxError,
+}
+
+impl From<BoxError> for SendFailure {
+    fn from(error: BoxError) -> Self {
+        Self {
+            sent: Vec::new(),
+            error,
+        }
+    }
+}
+
 #[async_trait]
-pub trait ReleaseProvider {
+pub trait ReleaseProvider: Sync {
     async fn fetch_releases(&self, window: ReleaseWindow) -> Result<ReleaseBatch, BoxError>;
+
+    /// Релизы из ленты изменений с момента `since`; по умолчанию лента не поддерживается.
+    async fn fetch_changed_releases(
+        &self,
+        _since: DateTime<Utc>,
+        _window: ReleaseWindow,
+    ) -> Result<ReleaseBatch, BoxError> {
+        Ok(ReleaseBatch::default())
+    }
+
+    /// Проверяет, что сбои тайтлов в отчёте прогона (discover и лента изменений
+    /// вместе) укладываются в бюджет ошибок. По умолчанию бюджета нет.
+    fn check_error_budget(&self, _report: &FilterReport) -> Result<(), BoxError> {
+        Ok(())
+    }
+
+    /// Текущие цифровые даты фильма: приоритетных регионов и по всем регионам
+    /// (`None`, если TMDB их убрал); нужны для поправок к анонсам. По умолчанию
+    /// перепроверка не поддерживается.
+    async fn digital_release(
+        &self,
+        _movie_id: u64,
+        _today: NaiveDate,
+    ) -> Result<Option<DigitalReleaseDate>, BoxError> {
+        Err("перепроверка цифровых дат не поддерживается".into())
+    }
+}
+
+#[async_trait]
+pub trait MessageDispatcher: Sync {
+    /// Отправляет сообщения и возвращает их `message_id` в том же порядке
+    /// (`None`, если id неизвестен — такое сообщение потом не правится).
+    /// При ошибке [`SendFailure::sent`] содержит id сообщений, ушедших до неё.
+    ///
+    /// `thread_id` — тема форума в супергруппе (`None` — общий поток), `options` —
+    /// превью ссылок, тихая отправка, защита от пересылки и ответ на сообщение.
+    async fn send_messages(
+        &self,
+        chat_id: i64,
+        thread_id: Option<i64>,
+        messages: Vec<String>,
+        options: &MessageOptions,
+    ) -> Result<Vec<Option<i64>>, SendFailure>;
+
+    /// Заменяет текст ранее отправленного сообщения, сохраняя его превью ссылок
+    /// и разметку.
+    async fn edit_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        text: String,
+        options: &MessageOptions,
+    ) -> Result<(), BoxError>;
+
+    async fn delete_message(&self, chat_id: i64, message_id: i64) -> Result<(), BoxError>;
+
+    /// Разрешает рассылку в чат, подписавшийся через команды бота.
+    fn allow_chat(&self, _chat_id: i64) {}
+
+    /// Сообщения, доставленные повтором после попытки, которая тоже могла
+    /// дойти; по умолчанию диспетчер дублей не отслеживает.
+    fn take_possible_duplicates(&self) -> Vec<PossibleDuplicate> {
+        Vec::new()
+    }
 }
 
+/// Входящий канал бота: `getUpdates` и ответы на команды.
 #[async_trait]
-pub trait MessageDispatcher {
-    async fn send_messages(&self, chat_id: i64, messages: Vec<String>) -> Result<(), BoxError>;
+pub trait UpdateChannel: Send + Sync {
+    async fn fetch_updates(
+        &self,
+        offset: Option<i64>,
+        timeout_secs: u64,
+    ) -> Result<Vec<Update>, BoxError>;
+
+    async fn reply(&self, chat_id: i64, text: String) -> Result<(), BoxError>;
+
+    async fn is_chat_admin(&self, chat_id: i64, user_id: i64) -> Result<bool, BoxError>;
+
+    /// Имя бота (`getMe`): команды вида `/command@OtherBot` адресованы не ему.
+    async fn bot_username(&self) -> Result<String, BoxError>;
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct ReleaseBatch {
     pub movies: Vec<MovieRelease>,
     pub tv_events: Vec<TvEvent>,
+    pub report: FilterReport,
+    /// До какого момента прочитана лента изменений (`None` — до конца окна).
+    /// Курсор ленты дальше него не сдвигается.
+    pub synced_until: Option<DateTime<Utc>>,
+}
+
+impl ReleaseBatch {
+    /// Добавляет релизы из другого батча, пропуская уже известные; возвращает число добавленных.
+    pub fn merge(&mut self, other: ReleaseBatch) -> usize {
+        let mut movie_ids: std::collections::HashSet<u64> =
+            self.movies.iter().map(|movie| movie.id).collect();
+        let mut tv_keys: std::collections::HashSet<String> =
+            self.tv_events.iter().map(TvEvent::event_key).collect();
+        let before = self.movies.len() + self.tv_events.len();
+        self.report.extend(other.report);
+
+        self.movies.extend(
+            other
+                .movies
+                .into_iter()
+                .filter(|movie| movie_ids.insert(movie.id)),
+        );
+        self.tv_events.extend(
+            other
+                .tv_events
+                .into_iter()
+                .filter(|event| tv_keys.insert(event.event_key())),
+        );
+
+        self.movies.len() + self.tv_events.len() - before
+    }
+}
+
+/// Отобранные для прогона релизы и счётчики отбора.
+struct RunPlan {
+    window: ReleaseWindow,
+    uncovered_since: Option<DateTime<Utc>>,
+    fetched: usize,
+    change_feed_releases: usize,
+    filter_rejected: usize,
+    tmdb_failures: usize,
+    duplicates: usize,
+    candidate_count: usize,
+    deferred: usize,
+    backlog: usize,
+    quiet_deferred: usize,
+    /// Чаты в тихих часах, которым рассылка уходит без звука.
+    silent: std::collections::HashSet<i64>,
+    report: FilterReport,
+    payloads: Vec<ChatPayload>,
+    /// Свежие данные всех найденных релизов по чатам, включая уже отправленные.
+    refreshed: Vec<ChatPayload>,
+}
+
+/// Рассылка в одну тему чата с общими для её сообщений параметрами отправки.
+struct ThreadPayload {
+    thread_id: Option<i64>,
+    options: MessageOptions,
+    payload: ChatPayload,
+}
+
+/// Раскладывает рассылки чатов по темам форума и параметрам отправки: дайджест
+/// целиком уходит в тему дайджестов, мгновенные релизы — каждый в тему своего
+/// жанра или типа, а внутри темы делятся по параметрам своего типа.
+/// Порядок релизов внутри рассылки сохраняется.
+fn thread_payloads(payloads: &[ChatPayload], config: &TelegramConfig) -> Vec<ThreadPayload> {
+    let mut threaded = Vec::new();
+    for payload in payloads {
+        let chat = config
+            .chats
+            .iter()
+            .find(|chat| chat.chat_id == payload.chat_id)
+            .cloned()
+            .unwrap_or_else(|| ChatConfig::new(payload.chat_id));
+        if chat.cadence.is_digest() {
+            threaded.push(ThreadPayload {
+                thread_id: chat.topics.digest,
+                options: chat.message_options.for_kind(MessageKind::Digest).clone(),
+                payload: payload.clone(),
+            });
+            continue;
+        }
+        let mut by_thread: std::collections::BTreeMap<
+            Option<i64>,
+            Vec<(MessageOptions, Vec<ChatRelease>)>,
+        > = std::collections::BTreeMap::new();
+        for release in &payload.releases {
+            let thread_id = release_topic(&chat.topics, release);
+            let options = chat.message_options.for_kind(message_kind(&release.kind));
+            let groups = by_thread.entry(thread_id).or_default();
+            match groups.iter_mut().find(|(known, _)| known == options) {
+                Some((_, releases)) => releases.push(release.clone()),
+                None => groups.push((options.clone(), vec![release.clone()])),
+            }
+        }
+        for (thread_id, groups) in by_thread {
+            threaded.extend(groups.into_iter().map(|(options, releases)| ThreadPayload {
+                thread_id,
+                options,
+                payload: ChatPayload {
+                    chat_id: payload.chat_id,
+                    releases,
+                },
+            }));
+        }
+    }
+    threaded
+}
+
+fn message_kind(kind: &ReleaseKind) -> MessageKind {
+    match kind {
+        ReleaseKind::Movie => MessageKind::Movie,
+        ReleaseKind::TvPremiere => MessageKind::TvPremiere,
+        ReleaseKind::TvSeason { .. } => MessageKind::TvSeason,
+    }
+}
+
+fn release_topic(topics: &ForumTopics, release: &ChatRelease) -> Option<i64> {
+    let by_kind = match release.kind {
+        ReleaseKind::Movie => topics.movies,
+        ReleaseKind::TvPremiere => topics.tv_premieres,
+        ReleaseKind::TvSeason { .. } => topics.tv_seasons,
+    };
+    topics.for_genres(&release.genres, by_kind)
+}
+
+/// Тексты сообщений рассылки в порядке отправки, экранированные под
+/// `parse_mode`; чатам с дайджестом — дайджест.
+fn payload_messages(
+    payload: &ChatPayload,
+    config: &TelegramConfig,
+    parse_mode: Option<ParseMode>,
+) -> Vec<String> {
+    let digest_title = cadence_of(config, payload.chat_id).digest_title();
+    build_chat_messages(payload.clone(), digest_title, parse_mode)
+        .into_iter()
+        .map(|message| message.text)
+        .collect()
+}
+
+/// Релизы, строки которых (экранированные под `parse_mode`) есть хотя бы
+/// в одном из `messages`.
+fn releases_in_messages(
+    releases: &[ChatRelease],
+    messages: &[String],
+    parse_mode: Option<ParseMode>,
+) -> Vec<ChatRelease> {
+    releases
+        .iter()
+        .filter(|release| {
+            let line = escape_markup(&release_line(release), parse_mode);
+            messages.iter().any(|text| text.contains(&line))
+        })
+        .cloned()
+        .collect()
+}
+
+fn cadence_of(config: &TelegramConfig, chat_id: i64) -> Cadence {
+    config
+        .chats
+        .iter()
+        .find(|chat| chat.chat_id == chat_id)
+        .map(|chat| chat.cadence)
+        .unwrap_or_default()
+}
+
+/// Настройки прогона, не относящиеся к конкретным чатам.
+#[derive(Debug, Clone, Default)]
+pub struct OrchestratorSettings {
+    /// Дополнять discover инкрементальной лентой TMDB `/changes`.
+    pub change_feed: bool,
+    /// Лимиты релизов за прогон и срок жизни отложенных, по чатам.
+    pub backlog: BacklogConfig,
+    pub window: WindowPolicy,
+    /// Оценка релизов: порядок, отбор под лимит, 🔥 и тихая отправка.
+    pub scoring: ScoringConfig,
+    /// Перепроверка цифровых дат анонсированных фильмов.
+    pub corrections: CorrectionPolicy,
 }
 
 pub struct Orchestrator<C: crate::github::artifacts::ArtifactStore, P, D>
@@ -37,9 +328,23 @@ where
     release_provider: P,
     dispatcher: D,
     telegram_config: TelegramConfig,
+    settings: OrchestratorSettings,
+    state_store: Option<BotStateStore<C>>,
+    state: BotState,
+    state_restored: bool,
+    history_restored: bool,
+    chat_history: ChatHistory,
+    chat_history_store: Option<ChatHistoryStore<C>>,
+    report_archive: Option<ReportArchive<C>>,
+    update_channel: Option<Box<dyn UpdateChannel>>,
+    bot_username: Option<String>,
 }
 
-const MAX_RELEASES_PER_RUN: usize = 10;
+const UPCOMING_WINDOW_DAYS: i64 = 14;
+/// Сколько дней отправленные сообщения сверяются с TMDB и правятся.
+const ANNOUNCEMENT_RETENTION_DAYS: i64 = 30;
+const FILTER_REPORT_ARTIFACT_NAME: &str = "filter-report";
+const FILTER_REPORT_CSV_ARTIFACT_NAME: &str = "filter-report-csv";
 
 impl<C: crate::github::artifacts::ArtifactStore, P, D> Orchestrator<C, P, D>
 where
@@ -59,36 +364,292 @@ where
             release_provider,
             dispatcher,
             telegram_config,
+            settings: OrchestratorSettings::default(),
+            state_store: None,
+            state: BotState::default(),
+            state_restored: false,
+            history_restored: false,
+            chat_history: ChatHistory::default(),
+            chat_history_store: None,
+            report_archive: None,
+            update_channel: None,
+            bot_username: None,
         }
     }
 
+    pub fn with_settings(mut self, settings: OrchestratorSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Подключает хранилище сводного состояния; без него состояние живёт только в памяти.
+    pub fn with_state_store(mut self, store: BotStateStore<C>) -> Self {
+        self.state_store = Some(store);
+        self
+    }
+
+    /// Подключает хранилище историй по чатам; без него они живут только в памяти
+    /// и при каждом запуске заново переносятся из общих историй.
+    pub fn with_chat_history_store(mut self, store: ChatHistoryStore<C>) -> Self {
+        self.chat_history_store = Some(store);
+        self
+    }
+
+    /// Подключает архив, в который выгружается отчёт фильтрации каждого прогона.
+    pub fn with_report_archive(mut self, archive: ReportArchive<C>) -> Self {
+        self.report_archive = Some(archive);
+        self
+    }
+
+    /// Подключает входящий канал для обработки команд бота.
+    pub fn with_update_channel(mut self, channel: impl UpdateChannel + 'static) -> Self {
+        self.update_channel = Some(Box::new(channel));
+        self
+    }
+
+    pub fn state(&self) -> &BotState {
+        &self.state
+    }
+
     pub fn release_window(now: DateTime<Utc>) -> ReleaseWindow {
         let start = now - Duration::days(7);
         ReleaseWindow { start, end: now }
     }
 
+    /// Окно от прошлого успешного прогона с перекрытием, но не короче обычного
+    /// [`Self::release_window`] и не длиннее допустимого догона. Вторым значением
+    /// возвращается момент прошлого прогона, если простой оказался длиннее и часть
+    /// релизов останется непросканированной.
+    pub fn catch_up_window(
+        now: DateTime<Utc>,
+        last_success: Option<DateTime<Utc>>,
+        policy: WindowPolicy,
+    ) -> (ReleaseWindow, Option<DateTime<Utc>>) {
+        let Some(last_success) = last_success else {
+            return (Self::release_window(now), None);
+        };
+        let start = last_success - Duration::hours(policy.overlap_hours);
+        let earliest = now - Duration::days(policy.max_catch_up_days);
+        if start < earliest {
+            return (
+                ReleaseWindow {
+                    start: earliest,
+                    end: now,
+                },
+                Some(last_success),
+            );
+        }
+        // TMDB проставляет цифровые даты задним числом, поэтому частые прогоны
+        // всё равно смотрят на обычное окно назад.
+        let start = start.min(Self::release_window(now).start).max(earliest);
+        (ReleaseWindow { start, end: now }, None)
+    }
+
     pub async fn run(&mut self, now: DateTime<Utc>) -> Result<RunSummary, OrchestratorError> {
-        if let Err(err) = self.movie_history.restore() {
-            eprintln!(
-                "WARN: не удалось восстановить историю фильмов, продолжаю с пустой историей: {err}"
+        self.restore_history();
+        self.restore_state();
+        let telegram_config = effective_config(&self.telegram_config, &self.state.subscriptions);
+        self.migrate_chat_history(&telegram_config);
+        for chat in &telegram_config.chats {
+            self.dispatcher.allow_chat(chat.chat_id);
+        }
+
+        // Сначала — то, что прошлый прогон запланировал, но не успел разослать.
+        self.reconcile_outbox();
+        let resumed = self.dispatch_outbox(now, false).await?;
+        let resumed_messages = resumed.iter().map(|chat| chat.messages).sum();
+        if !resumed.is_empty() {
+            info!(
+                target: "orchestrator",
+                chats = resumed.len(),
+                messages = resumed_messages,
+                "Дослан outbox прошлого прогона"
             );
         }
-        if let Err(err) = self.tv_history.restore() {
-            eprintln!(
-                "WARN: не удалось восстановить историю сериалов, продолжаю с пустой историей: {err}"
+
+        let plan = match self.plan(now, &telegram_config).await {
+            Ok(plan) => plan,
+            // Отчёт прогона, превысившего бюджет ошибок, нужнее всего: по нему разбирают сбои.
+            Err(OrchestratorError::ErrorBudget { report, source }) => {
+                self.publish_filter_report(&report);
+                return Err(OrchestratorError::ErrorBudget { report, source });
+            }
+            Err(err) => return Err(err),
+        };
+        self.publish_filter_report(&plan.report);
+        let RunPlan {
+            window,
+            uncovered_since,
+            fetched,
+            change_feed_releases,
+            filter_rejected,
+            tmdb_failures,
+            duplicates,
+            candidate_count,
+            deferred,
+            backlog,
+            quiet_deferred,
+            silent,
+            payloads,
+            refreshed,
+            ..
+        } = plan;
+
+        for ThreadPayload {
+            thread_id,
+            options,
+            payload,
+        } in thread_payloads(&payloads, &telegram_config)
+        {
+            self.state.outbox.push(OutboxEntry {
+                chat_id: payload.chat_id,
+                thread_id,
+                messages: payload_messages(&payload, &telegram_config, options.parse_mode),
+                options,
+                event_keys: payload
+                    .releases
+                    .iter()
+                    .map(|release| release.event_key.clone())
+                    .collect(),
+                status: OutboxStatus::Pending,
+                message_ids: Vec::new(),
+                silent: silent.contains(&payload.chat_id),
+                releases: payload.releases,
+            });
+        }
+        if !payloads.is_empty() {
+            self.save_outbox()?;
+        }
+
+        let chats = self.dispatch_outbox(now, true).await?;
+        self.settle_digests(&chats, now);
+        let messages_sent = chats.iter().map(|chat| chat.messages).sum();
+        let corrections = self.correct_announcements(now).await;
+        let edited_messages = self.refresh_announcements(&refreshed, now).await;
+        let possible_duplicates = self.record_possible_duplicates(now);
+
+        // Общие истории остаются сводным журналом всего, что дошло хотя бы до одного чата.
+        let delivered_keys: std::collections::HashSet<&str> = self
+            .state
+            .outbox
+            .iter()
+            .filter(|entry| entry.status == OutboxStatus::Sent)
+            .flat_map(|entry| entry.event_keys.iter().map(String::as_str))
+            .collect();
+        let sent_releases = delivered_keys.len();
+        let movie_ids: Vec<u64> = delivered_keys
+            .iter()
+            .filter_map(|key| key.strip_prefix("movie:")?.parse().ok())
+            .collect();
+        let tv_keys: Vec<String> = delivered_keys
+            .iter()
+            .filter(|key| key.starts_with("tv:"))
+            .map(|key| key.to_string())
+            .collect();
+
+        let movie_inserted = self.movie_history.append(&movie_ids);
+        let tv_inserted = self.tv_history.append(&tv_keys);
+
+        if movie_inserted > 0
+            && let Err(err) = self.movie_history.persist()
+        {
+            warn!(
+                target: "orchestrator",
+                error = %err,
+                "Не удалось сохранить историю фильмов, продолжаю без ошибки"
+            );
+        }
+        if tv_inserted > 0
+            && let Err(err) = self.tv_history.persist()
+        {
+            warn!(
+                target: "orchestrator",
+                error = %err,
+                "Не удалось сохранить историю сериалов, продолжаю без ошибки"
             );
         }
+        // Доставленные записи уходят из outbox, только когда история чатов надёжно
+        // сохранена; иначе следующий прогон восстановит её из outbox.
+        if self.persist_chat_history() {
+            self.state
+                .outbox
+                .retain(|entry| entry.status != OutboxStatus::Sent);
+        }
+        // Прогон с недоставленными чатами не сдвигает окно: их релизы найдутся снова.
+        if !chats
+            .iter()
+            .any(|chat| matches!(chat.outcome, DeliveryOutcome::Failed { .. }))
+        {
+            self.state.last_run.succeeded_at = Some(window.end);
+        }
+        self.persist_state();
 
-        let window = Self::release_window(now);
-        let batch = self
+        Ok(RunSummary {
+            window_start: window.start,
+            uncovered_since,
+            fetched,
+            change_feed_releases,
+            filter_rejected,
+            tmdb_failures,
+            new_releases: candidate_count,
+            sent_releases,
+            duplicates,
+            messages_sent,
+            movie_history_appended: movie_inserted,
+            tv_history_appended: tv_inserted,
+            truncated: deferred,
+            backlog,
+            quiet_deferred,
+            resumed_messages,
+            edited_messages,
+            corrections,
+            possible_duplicates,
+            chats,
+        })
+    }
+
+    /// Отбирает релизы прогона и раскладывает их по чатам с учётом истории и очереди.
+    /// Ничего не отправляет и не сохраняет; изменения состояния остаются в памяти.
+    async fn plan(
+        &mut self,
+        now: DateTime<Utc>,
+        telegram_config: &TelegramConfig,
+    ) -> Result<RunPlan, OrchestratorError> {
+        let (window, uncovered_since) =
+            Self::catch_up_window(now, self.state.last_run.succeeded_at, self.settings.window);
+        if let Some(since) = uncovered_since {
+            warn!(
+                target: "orchestrator",
+                last_success = %since,
+                window_start = %window.start,
+                max_catch_up_days = self.settings.window.max_catch_up_days,
+                "Простой дольше допустимого догона: релизы до начала окна не просканированы"
+            );
+        }
+        let mut batch = self
             .release_provider
             .fetch_releases(window)
             .await
             .map_err(OrchestratorError::Releases)?;
+        let change_feed_releases = self.fetch_change_feed(&mut batch, window).await;
+        if let Err(source) = self.release_provider.check_error_budget(&batch.report) {
+            return Err(OrchestratorError::ErrorBudget {
+                report: Box::new(std::mem::take(&mut batch.report)),
+                source,
+            });
+        }
+        let filter_rejected = batch.report.rejected();
+        let tmdb_failures = batch.report.failures.len();
+        let report = std::mem::take(&mut batch.report);
 
         let fetched = batch.movies.len() + batch.tv_events.len();
-        let (movie_releases, movie_duplicates) = self.filter_new_movies(batch.movies);
-        let (tv_events, tv_duplicates) = self.filter_new_tv_events(batch.tv_events);
+        let mut everything = Self::convert_movies(&batch.movies);
+        everything.extend(Self::convert_tv_events(&batch.tv_events));
+        let refreshed = group_releases_by_chat(&everything, telegram_config);
+        let (movie_releases, movie_duplicates) =
+            self.filter_new_movies(batch.movies, telegram_config);
+        let (tv_events, tv_duplicates) =
+            self.filter_new_tv_events(batch.tv_events, telegram_config);
         let duplicates = movie_duplicates + tv_duplicates;
 
         let mut combined = Vec::new();
@@ -97,102 +658,1396 @@ where
 
         sort_releases_by_priority(&mut combined);
         let candidate_count = combined.len();
-        if combined.len() > MAX_RELEASES_PER_RUN {
-            combined.truncate(MAX_RELEASES_PER_RUN);
-        }
+
+        // Каждый чат получает только то, чего ещё нет в его собственной истории.
+        let fresh: Vec<ChatPayload> = group_releases_by_chat(&combined, telegram_config)
+            .into_iter()
+            .filter_map(|mut payload| {
+                payload.releases.retain(|release| {
+                    !self
+                        .chat_history
+                        .contains(payload.chat_id, &release.event_key)
+                });
+                (!payload.releases.is_empty()).then_some(payload)
+            })
+            .collect();
+        let (fresh_digest, fresh_instant): (Vec<ChatPayload>, Vec<ChatPayload>) = fresh
+            .into_iter()
+            .partition(|payload| cadence_of(telegram_config, payload.chat_id).is_digest());
+        let (mut payloads, deferred) = self.apply_backlog(fresh_instant, telegram_config, now);
+        payloads.extend(self.collect_digests(fresh_digest, telegram_config, now));
+        let (payloads, quiet_deferred, mut silent) =
+            self.apply_quiet_hours(payloads, telegram_config, now);
+        self.silence_low_scores(&payloads, &mut silent);
+        let backlog: usize = self.state.backlog.values().map(Vec::len).sum();
 
         info!(
             target: "orchestrator",
             fetched,
             after_history = candidate_count,
             duplicates,
-            sent = combined.len(),
+            deferred,
+            backlog,
             "Отфильтрованы релизы после истории"
         );
 
-        if combined.is_empty() {
-            return Ok(RunSummary {
-                fetched,
-                new_releases: candidate_count,
-                sent_releases: 0,
-                duplicates,
-                messages_sent: 0,
-                movie_history_appended: 0,
-                tv_history_appended: 0,
-                truncated: candidate_count,
+        Ok(RunPlan {
+            window,
+            uncovered_since,
+            fetched,
+            change_feed_releases,
+            filter_rejected,
+            tmdb_failures,
+            duplicates,
+            candidate_count,
+            deferred,
+            backlog,
+            quiet_deferred,
+            silent,
+            report,
+            payloads,
+            refreshed,
+        })
+    }
+
+    /// Сообщения, которые прогон отправил бы сейчас, без отправки и сохранения.
+    pub async fn preview(
+        &mut self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ChatPreview>, OrchestratorError> {
+        self.restore_history();
+        self.restore_state();
+        let telegram_config = effective_config(&self.telegram_config, &self.state.subscriptions);
+        self.migrate_chat_history(&telegram_config);
+
+        let plan = self.plan(now, &telegram_config).await?;
+        Ok(thread_payloads(&plan.payloads, &telegram_config)
+            .into_iter()
+            .map(
+                |ThreadPayload {
+                     thread_id,
+                     options,
+                     payload,
+                 }| ChatPreview {
+                    chat_id: payload.chat_id,
+                    thread_id,
+                    messages: payload_messages(&payload, &telegram_config, options.parse_mode),
+                    scores: payload
+                        .releases
+                        .iter()
+                        .map(|release| {
+                            format!("{}: {}", release.event_key, release.score.explain())
+                        })
+                        .collect(),
+                    event_keys: payload
+                        .releases
+                        .into_iter()
+                        .map(|release| release.event_key)
+                        .collect(),
+                },
+            )
+            .collect())
+    }
+
+    /// Объединяет свежие релизы чатов с их очередью и оставляет в рассылке не больше
+    /// лимита чата. Остаток возвращается в очередь, устаревшие и уже полученные
+    /// чатом релизы из неё выбрасываются. Возвращает рассылки и число отложенных
+    /// в этом прогоне релизов.
+    fn apply_backlog(
+        &mut self,
+        fresh: Vec<ChatPayload>,
+        config: &TelegramConfig,
+        now: DateTime<Utc>,
+    ) -> (Vec<ChatPayload>, usize) {
+        let mut fresh: std::collections::HashMap<i64, Vec<ChatRelease>> = fresh
+            .into_iter()
+            .map(|payload| (payload.chat_id, payload.releases))
+            .collect();
+        let mut backlog = std::mem::take(&mut self.state.backlog);
+        let today = now.date_naive();
+        let mut payloads = Vec::new();
+        let mut deferred = 0;
+
+        for chat in &config.chats {
+            // Очередь чата с дайджестом забирает сам дайджест.
+            if chat.cadence.is_digest() {
+                if let Some(queued) = backlog.remove(&chat.chat_id) {
+                    self.state.backlog.insert(chat.chat_id, queued);
+                }
+                continue;
+            }
+            let policy = self.settings.backlog.policy_for(chat.chat_id);
+            let oldest = today - Duration::days(policy.max_age_days);
+            let new_releases = fresh.remove(&chat.chat_id).unwrap_or_default();
+            let new_keys: std::collections::HashSet<String> = new_releases
+                .iter()
+                .map(|release| release.event_key.clone())
+                .collect();
+
+            let queued_releases = backlog.remove(&chat.chat_id).unwrap_or_default();
+            let queued_keys: std::collections::HashSet<String> = queued_releases
+                .iter()
+                .map(|release| release.event_key.clone())
+                .collect();
+            let mut releases: Vec<ChatRelease> = queued_releases
+                .into_iter()
+                .filter(|release| {
+                    release.event_date >= oldest
+                        && !new_keys.contains(&release.event_key)
+                        && !self.chat_history.contains(chat.chat_id, &release.event_key)
+                })
+                .collect();
+            let queued = releases.len();
+            releases.extend(new_releases);
+            rank_chat_releases(&mut releases, &self.settings.scoring, today);
+
+            let overflow = if releases.len() > policy.max_releases_per_run {
+                releases.split_off(policy.max_releases_per_run)
+            } else {
+                Vec::new()
+            };
+            deferred += overflow
+                .iter()
+                .filter(|release| !queued_keys.contains(&release.event_key))
+                .count();
+            if !overflow.is_empty() {
+                info!(
+                    target: "orchestrator",
+                    chat_id = chat.chat_id,
+                    queued,
+                    deferred = overflow.len(),
+                    limit = policy.max_releases_per_run,
+                    "Релизы сверх лимита чата отложены в очередь"
+                );
+                self.state.backlog.insert(chat.chat_id, overflow);
+            }
+            if !releases.is_empty() {
+                payloads.push(ChatPayload {
+                    chat_id: chat.chat_id,
+                    releases,
+                });
+            }
+        }
+
+        (payloads, deferred)
+    }
+
+    /// Копит релизы чатов с дайджестом и возвращает дайджесты, чей слот подошёл.
+    /// Пустой дайджест в свой слот считается отправленным, чтобы следующий релиз
+    /// ждал следующего слота.
+    fn collect_digests(
+        &mut self,
+        fresh: Vec<ChatPayload>,
+        config: &TelegramConfig,
+        now: DateTime<Utc>,
+    ) -> Vec<ChatPayload> {
+        let mut fresh: std::collections::HashMap<i64, Vec<ChatRelease>> = fresh
+            .into_iter()
+            .map(|payload| (payload.chat_id, payload.releases))
+            .collect();
+        let mut payloads = Vec::new();
+
+        for chat in config.chats.iter().filter(|chat| chat.cadence.is_digest()) {
+            let digest = self.state.digests.entry(chat.chat_id).or_default();
+            let incoming = fresh
+                .remove(&chat.chat_id)
+                .unwrap_or_default()
+                .into_iter()
+                .chain(self.state.backlog.remove(&chat.chat_id).unwrap_or_default());
+            for release in incoming {
+                if !digest
+                    .releases
+                    .iter()
+                    .any(|queued| queued.event_key == release.event_key)
+                {
+                    digest.releases.push(release);
+                }
+            }
+            digest
+                .releases
+                .retain(|release| !self.chat_history.contains(chat.chat_id, &release.event_key));
+
+            // Новый дайджест отсчитывается от первого прогона, а не от вчерашнего слота.
+            let last_sent = *digest.last_sent.get_or_insert(now);
+            if !chat.cadence.is_due(Some(last_sent), now, chat.timezone) {
+                continue;
+            }
+            if digest.releases.is_empty() {
+                digest.last_sent = Some(now);
+                continue;
+            }
+            let mut releases = digest.releases.clone();
+            rank_chat_releases(&mut releases, &self.settings.scoring, now.date_naive());
+            payloads.push(ChatPayload {
+                chat_id: chat.chat_id,
+                releases,
             });
         }
 
-        let messages = build_messages(&combined, &self.telegram_config);
+        payloads
+    }
 
-        let mut grouped: std::collections::HashMap<i64, Vec<String>> =
-            std::collections::HashMap::new();
-        for message in messages {
-            grouped
-                .entry(message.chat_id)
-                .or_default()
-                .push(message.text);
+    /// Рассылки, где все релизы оценены ниже порога, уходят без звука.
+    fn silence_low_scores(
+        &self,
+        payloads: &[ChatPayload],
+        silent: &mut std::collections::HashSet<i64>,
+    ) {
+        for payload in payloads {
+            for release in &payload.releases {
+                debug!(
+                    target: "orchestrator",
+                    chat_id = payload.chat_id,
+                    event_key = %release.event_key,
+                    score = %release.score.explain(),
+                    "Оценка релиза"
+                );
+            }
+            let Some(threshold) = self.settings.scoring.silent_below else {
+                continue;
+            };
+            let low = payload
+                .releases
+                .iter()
+                .all(|release| release.score.total < threshold);
+            if low && silent.insert(payload.chat_id) {
+                info!(
+                    target: "orchestrator",
+                    chat_id = payload.chat_id,
+                    threshold,
+                    "Все релизы рассылки ниже порога оценки, отправка без звука"
+                );
+            }
         }
+    }
 
-        let mut messages_sent = 0;
-        for (chat_id, messages) in grouped {
-            messages_sent += messages.len();
-            self.dispatcher
-                .send_messages(chat_id, messages)
-                .await
-                .map_err(OrchestratorError::Dispatch)?;
+    /// Решает по каждому чату, отправлять сейчас или позже: в тихие часы рассылка
+    /// либо уходит без звука, либо откладывается в очередь чата до первого прогона
+    /// после них (дайджест просто остаётся ждать). Возвращает рассылки, число
+    /// отложенных релизов и чаты для тихой отправки.
+    fn apply_quiet_hours(
+        &mut self,
+        payloads: Vec<ChatPayload>,
+        config: &TelegramConfig,
+        now: DateTime<Utc>,
+    ) -> (Vec<ChatPayload>, usize, std::collections::HashSet<i64>) {
+        let mut ready = Vec::new();
+        let mut deferred = 0;
+        let mut silent = std::collections::HashSet::new();
+
+        for payload in payloads {
+            let Some(chat) = config
+                .chats
+                .iter()
+                .find(|chat| chat.chat_id == payload.chat_id)
+            else {
+                ready.push(payload);
+                continue;
+            };
+            match chat.quiet_at(now).map(|quiet| quiet.mode) {
+                None => ready.push(payload),
+                Some(QuietMode::Silent) => {
+                    silent.insert(payload.chat_id);
+                    ready.push(payload);
+                }
+                Some(QuietMode::Defer) => {
+                    deferred += payload.releases.len();
+                    info!(
+                        target: "orchestrator",
+                        chat_id = payload.chat_id,
+                        releases = payload.releases.len(),
+                        "Тихие часы чата, рассылка отложена"
+                    );
+                    if !chat.cadence.is_digest() {
+                        self.state
+                            .backlog
+                            .entry(payload.chat_id)
+                            .or_default()
+                            .extend(payload.releases);
+                    }
+                }
+            }
+        }
+
+        (ready, deferred, silent)
+    }
+
+    /// Закрывает доставленные дайджесты: отправленные релизы уходят из накопления.
+    fn settle_digests(&mut self, chats: &[ChatDelivery], now: DateTime<Utc>) {
+        for chat in chats {
+            let target = match chat.outcome {
+                DeliveryOutcome::Delivered => chat.chat_id,
+                DeliveryOutcome::Migrated { to } => to,
+                DeliveryOutcome::Forbidden
+                | DeliveryOutcome::Skipped { .. }
+                | DeliveryOutcome::Failed { .. } => continue,
+            };
+            if let Some(digest) = self.state.digests.get_mut(&target) {
+                digest.last_sent = Some(now);
+                digest
+                    .releases
+                    .retain(|release| !self.chat_history.contains(target, &release.event_key));
+            }
+        }
+    }
+
+    /// Возвращает в историю чатов уже доставленные сообщения outbox: прошлый
+    /// прогон мог упасть после отправки, но до сохранения истории.
+    fn reconcile_outbox(&mut self) {
+        for entry in &self.state.outbox {
+            let keys: Vec<String> = match entry.status {
+                OutboxStatus::Sent => entry.event_keys.clone(),
+                OutboxStatus::Pending => releases_in_messages(
+                    &entry.releases,
+                    &entry.messages[..entry.message_ids.len()],
+                    entry.options.parse_mode,
+                )
+                .into_iter()
+                .map(|release| release.event_key)
+                .collect(),
+            };
+            self.chat_history.append(entry.chat_id, keys);
+        }
+    }
+
+    /// Рассылает `Pending`-записи outbox с первого неотправленного сообщения,
+    /// сохраняя прогресс после каждого чата. Если прогресс не сохранился, рассылка
+    /// останавливается с ошибкой: иначе следующий прогон повторил бы уже
+    /// доставленные сообщения.
+    ///
+    /// Подтверждённые сообщения сразу попадают в историю чата. Запись, из которой
+    /// ничего не ушло, при сбое удаляется: её релизы будут запланированы заново
+    /// следующим прогоном. Частично отправленная запись при `retain_partial`
+    /// остаётся `Pending` и досылается в начале следующего прогона; иначе она
+    /// сводится к отправленной части, а остальные релизы планируются заново.
+    /// Неотправленные релизы пропущенного чата откладываются в его очередь.
+    async fn dispatch_outbox(
+        &mut self,
+        now: DateTime<Utc>,
+        retain_partial: bool,
+    ) -> Result<Vec<ChatDelivery>, OrchestratorError> {
+        let mut chats = Vec::new();
+        let mut index = 0;
+
+        while let Some(entry) = self.state.outbox.get(index) {
+            if entry.status != OutboxStatus::Pending {
+                index += 1;
+                continue;
+            }
+            let chat_id = entry.chat_id;
+            let thread_id = entry.thread_id;
+            // Тихие часы и низкая оценка приглушают рассылку поверх настроек чата.
+            let options = MessageOptions {
+                disable_notification: entry.options.disable_notification || entry.silent,
+                ..entry.options.clone()
+            };
+            let messages = entry.messages[entry.message_ids.len()..].to_vec();
+            let releases = entry.event_keys.len();
+
+            let (outcome, message_ids) = self.deliver(chat_id, thread_id, messages, &options).await;
+            let delivered = message_ids.len();
+            // Переезд мог случиться и в неудачной рассылке: подтверждённые
+            // сообщения тогда ушли уже на новый id.
+            let target = self
+                .state
+                .chat_migrations
+                .get(&chat_id)
+                .copied()
+                .unwrap_or(chat_id);
+            self.confirm_messages(index, target, message_ids, now);
+            if matches!(outcome, DeliveryOutcome::Skipped { .. }) {
+                self.queue_unsent(index, target);
+            }
+
+            let entry = &mut self.state.outbox[index];
+            match outcome {
+                DeliveryOutcome::Delivered | DeliveryOutcome::Migrated { .. } => {
+                    entry.status = OutboxStatus::Sent;
+                    self.chat_history
+                        .append(target, entry.event_keys.iter().cloned());
+                    index += 1;
+                }
+                _ if entry.message_ids.is_empty() => {
+                    self.state.outbox.remove(index);
+                }
+                DeliveryOutcome::Failed { .. } if retain_partial => index += 1,
+                _ => {
+                    entry.messages.truncate(entry.message_ids.len());
+                    entry.releases = releases_in_messages(
+                        &entry.releases,
+                        &entry.messages,
+                        entry.options.parse_mode,
+                    );
+                    entry.event_keys = entry
+                        .releases
+                        .iter()
+                        .map(|release| release.event_key.clone())
+                        .collect();
+                    entry.status = OutboxStatus::Sent;
+                    index += 1;
+                }
+            }
+            self.save_outbox()?;
+
+            chats.push(ChatDelivery {
+                chat_id,
+                thread_id,
+                releases,
+                messages: delivered,
+                outcome,
+            });
         }
 
-        let movie_ids: Vec<u64> = combined
+        Ok(chats)
+    }
+
+    /// Откладывает неотправленные релизы записи outbox `index` в очередь чата
+    /// `target`. Пропуск чата не мешает прогону сдвинуть окно, поэтому без очереди
+    /// эти релизы больше не попали бы в рассылку.
+    fn queue_unsent(&mut self, index: usize, target: i64) {
+        let unsent: Vec<ChatRelease> = self.state.outbox[index]
+            .releases
             .iter()
-            .filter_map(|release| match release.kind {
-                ReleaseKind::Movie => Some(release.id),
-                _ => None,
+            .filter(|release| !self.chat_history.contains(target, &release.event_key))
+            .cloned()
+            .collect();
+        if !unsent.is_empty() {
+            self.state.backlog.entry(target).or_default().extend(unsent);
+        }
+    }
+
+    /// Отмечает `message_ids` следующих неотправленных сообщений записи outbox
+    /// `index`: их релизы попадают в историю чата `target`, а сами сообщения —
+    /// в анонсы.
+    fn confirm_messages(
+        &mut self,
+        index: usize,
+        target: i64,
+        message_ids: Vec<Option<i64>>,
+        now: DateTime<Utc>,
+    ) {
+        let entry = &mut self.state.outbox[index];
+        entry.chat_id = target;
+        let first = entry.message_ids.len();
+        entry.message_ids.extend(message_ids);
+        let confirmed = &entry.messages[first..entry.message_ids.len()];
+        self.chat_history.append(
+            target,
+            releases_in_messages(&entry.releases, confirmed, entry.options.parse_mode)
+                .into_iter()
+                .map(|release| release.event_key),
+        );
+        self.record_announcements(index, first, now);
+    }
+
+    /// Запоминает сообщения записи outbox `index`, начиная с `first`, вместе
+    /// с релизами, попавшими в каждое.
+    fn record_announcements(&mut self, index: usize, first: usize, now: DateTime<Utc>) {
+        let entry = &self.state.outbox[index];
+        for (text, message_id) in entry.messages[first..]
+            .iter()
+            .zip(&entry.message_ids[first..])
+        {
+            let Some(message_id) = *message_id else {
+                continue;
+            };
+            let contained = releases_in_messages(
+                &entry.releases,
+                std::slice::from_ref(text),
+                entry.options.parse_mode,
+            );
+            if contained.is_empty() {
+                continue;
+            }
+            self.state.announcements.push(Announcement {
+                chat_id: entry.chat_id,
+                message_id,
+                thread_id: entry.thread_id,
+                options: entry.options.clone(),
+                text: text.clone(),
+                releases: contained,
+                sent_at: now,
+            });
+        }
+    }
+
+    /// Забирает у диспетчера сообщения, доставленные повтором после таймаута, и
+    /// сохраняет их в состоянии, чтобы дубли можно было найти и удалить после
+    /// прогона. Записи старше [`ANNOUNCEMENT_RETENTION_DAYS`] забываются.
+    fn record_possible_duplicates(&mut self, now: DateTime<Utc>) -> usize {
+        let oldest = now - Duration::days(ANNOUNCEMENT_RETENTION_DAYS);
+        self.state
+            .possible_duplicates
+            .retain(|record| record.detected_at >= oldest);
+        let duplicates = self.dispatcher.take_possible_duplicates();
+        for duplicate in &duplicates {
+            warn!(
+                target: "orchestrator",
+                chat_id = duplicate.chat_id,
+                message_id = duplicate.message_id,
+                "Сообщение могло уйти в чат дважды"
+            );
+            self.state
+                .possible_duplicates
+                .push(PossibleDuplicateRecord {
+                    chat_id: duplicate.chat_id,
+                    message_id: duplicate.message_id,
+                    detected_at: now,
+                });
+        }
+        duplicates.len()
+    }
+
+    /// Сверяет отправленные сообщения со свежими данными TMDB и правит те, где
+    /// строка релиза изменилась (дата, оценка, стриминг). Сообщения старше
+    /// [`ANNOUNCEMENT_RETENTION_DAYS`] забываются. Возвращает число правок.
+    async fn refresh_announcements(
+        &mut self,
+        refreshed: &[ChatPayload],
+        now: DateTime<Utc>,
+    ) -> usize {
+        let retention = ANNOUNCEMENT_RETENTION_DAYS.max(self.settings.corrections.recheck_days);
+        let oldest = now - Duration::days(retention);
+        self.state
+            .announcements
+            .retain(|announcement| announcement.sent_at >= oldest);
+        let fresh: std::collections::HashMap<(i64, &str), &ChatRelease> = refreshed
+            .iter()
+            .flat_map(|payload| {
+                payload
+                    .releases
+                    .iter()
+                    .map(move |release| ((payload.chat_id, release.event_key.as_str()), release))
             })
             .collect();
-        let tv_keys: Vec<String> = combined
+
+        let mut edited = 0;
+        for index in 0..self.state.announcements.len() {
+            let announcement = &self.state.announcements[index];
+            let parse_mode = announcement.options.parse_mode;
+            let mut text = announcement.text.clone();
+            let mut releases = announcement.releases.clone();
+            for release in &mut releases {
+                let Some(update) = fresh.get(&(announcement.chat_id, release.event_key.as_str()))
+                else {
+                    continue;
+                };
+                let updated = ChatRelease {
+                    highlighted: release.highlighted,
+                    score: release.score.clone(),
+                    ..(*update).clone()
+                };
+                let (before, after) = (
+                    escape_markup(&release_line(release), parse_mode),
+                    escape_markup(&release_line(&updated), parse_mode),
+                );
+                if before != after {
+                    text = text.replace(&before, &after);
+                    *release = updated;
+                }
+            }
+            if text == announcement.text {
+                continue;
+            }
+
+            let (chat_id, message_id) = (announcement.chat_id, announcement.message_id);
+            let options = announcement.options.clone();
+            match self
+                .dispatcher
+                .edit_message(chat_id, message_id, text.clone(), &options)
+                .await
+            {
+                Ok(()) => {
+                    info!(
+                        target: "orchestrator",
+                        chat_id,
+                        message_id,
+                        "Сообщение исправлено по свежим данным TMDB"
+                    );
+                    let announcement = &mut self.state.announcements[index];
+                    announcement.text = text;
+                    announcement.releases = releases;
+                    edited += 1;
+                }
+                Err(err) => warn!(
+                    target: "orchestrator",
+                    chat_id,
+                    message_id,
+                    error = %err,
+                    "Не удалось исправить сообщение, повторю в следующем прогоне"
+                ),
+            }
+        }
+
+        edited
+    }
+
+    /// Перепроверяет цифровые даты фильмов, анонсированных за последние
+    /// `recheck_days`, кроме анонсов этого прогона: их данные только что из TMDB.
+    /// За прогон проверяется не больше `max_rechecks` фильмов, по кругу начиная
+    /// с места, где остановился прошлый. Если дата ушла в будущее или пропала,
+    /// анонс исправляется по [`CorrectionAction`], а событие отзывается из истории
+    /// чата, чтобы фильм анонсировали снова в настоящую дату. Возвращает число
+    /// поправок.
+    async fn correct_announcements(&mut self, now: DateTime<Utc>) -> usize {
+        let policy = self.settings.corrections;
+        if policy.recheck_days <= 0 {
+            return 0;
+        }
+        let today = now.date_naive();
+        let since = now - Duration::days(policy.recheck_days);
+        let movie_ids: std::collections::BTreeSet<u64> = self
+            .state
+            .announcements
             .iter()
-            .filter_map(|release| match &release.kind {
-                ReleaseKind::TvPremiere | ReleaseKind::TvSeason { .. } => {
-                    Some(release.event_key.clone())
+            .filter(|announcement| announcement.sent_at >= since && announcement.sent_at < now)
+            .flat_map(|announcement| &announcement.releases)
+            .filter(|release| release.kind == ReleaseKind::Movie)
+            .map(|release| release.id)
+            .collect();
+        let (after, before): (Vec<u64>, Vec<u64>) = movie_ids.into_iter().partition(|id| {
+            self.state
+                .rechecks
+                .last_movie_id
+                .is_none_or(|last| *id > last)
+        });
+        let movie_ids: Vec<u64> = after
+            .into_iter()
+            .chain(before)
+            .take(policy.max_rechecks)
+            .collect();
+        if let Some(last) = movie_ids.last() {
+            self.state.rechecks.last_movie_id = Some(*last);
+        }
+
+        let mut current = std::collections::HashMap::new();
+        for movie_id in movie_ids {
+            match self.release_provider.digital_release(movie_id, today).await {
+                Ok(digital) => {
+                    current.insert(movie_id, digital);
                 }
-                _ => None,
+                Err(err) => warn!(
+                    target: "orchestrator",
+                    movie_id,
+                    error = %err,
+                    "Не удалось перепроверить цифровую дату, повторю в следующем прогоне"
+                ),
+            }
+        }
+        // Анонс сверяется с датой того региона, по которой он был отправлен.
+        let moved = |release: &ChatRelease| -> Option<Option<NaiveDate>> {
+            let digital = current.get(&release.id)?.as_ref();
+            let date = match &release.region {
+                Some(region) => digital.and_then(|digital| digital.regional_dates.get(region)),
+                None => digital.map(|digital| &digital.date),
+            };
+            date.is_none_or(|date| *date > today)
+                .then_some(date.copied())
+        };
+
+        let mut corrected = 0;
+        for index in 0..self.state.announcements.len() {
+            let announcement = &self.state.announcements[index];
+            if announcement.sent_at < since {
+                continue;
+            }
+            let (chat_id, message_id) = (announcement.chat_id, announcement.message_id);
+            let thread_id = announcement.thread_id;
+            // Поправка приходит ответом на исправляемый анонс.
+            let options = MessageOptions {
+                reply_to_message_id: Some(message_id),
+                ..announcement.options.clone()
+            };
+            let targets: Vec<(ChatRelease, Option<NaiveDate>)> = announcement
+                .releases
+                .iter()
+                .filter(|release| release.kind == ReleaseKind::Movie)
+                .filter_map(|release| Some((release.clone(), moved(release)?)))
+                .collect();
+
+            for (release, new_date) in targets {
+                let announcement = &self.state.announcements[index];
+                let reason = match new_date {
+                    Some(date) => format!("цифровой релиз перенесён на {date}"),
+                    None => "дата цифрового релиза отозвана".to_string(),
+                };
+                let delete =
+                    policy.action == CorrectionAction::Delete && announcement.releases.len() == 1;
+                let edited_text = announcement.text.replace(
+                    &escape_markup(&release_line(&release), options.parse_mode),
+                    &escape_markup(
+                        &format!("⚠️ {} — {reason}", release.title),
+                        options.parse_mode,
+                    ),
+                );
+                let result = match policy.action {
+                    CorrectionAction::Post => self
+                        .dispatcher
+                        .send_messages(
+                            chat_id,
+                            thread_id,
+                            vec![escape_markup(
+                                &format!(
+                                    "⚠️ Поправка: «{}» — {reason}. Анонсируем снова, когда фильм выйдет.",
+                                    release.title
+                                ),
+                                options.parse_mode,
+                            )],
+                            &options,
+                        )
+                        .await
+                        .map(|_| ())
+                        .map_err(|failure| failure.error),
+                    _ if delete => self.dispatcher.delete_message(chat_id, message_id).await,
+                    CorrectionAction::Edit | CorrectionAction::Delete => {
+                        self.dispatcher
+                            .edit_message(chat_id, message_id, edited_text.clone(), &options)
+                            .await
+                    }
+                };
+                if let Err(err) = result {
+                    warn!(
+                        target: "orchestrator",
+                        chat_id,
+                        message_id,
+                        error = %err,
+                        "Не удалось отправить поправку к анонсу, повторю в следующем прогоне"
+                    );
+                    continue;
+                }
+
+                info!(
+                    target: "orchestrator",
+                    chat_id,
+                    event_key = %release.event_key,
+                    new_date = ?new_date,
+                    "Анонс исправлен: цифровая дата перенесена или отозвана"
+                );
+                let announcement = &mut self.state.announcements[index];
+                announcement
+                    .releases
+                    .retain(|announced| announced.event_key != release.event_key);
+                if delete {
+                    announcement.releases.clear();
+                } else if policy.action != CorrectionAction::Post {
+                    announcement.text = edited_text;
+                }
+                self.chat_history.retract(chat_id, &release.event_key);
+                corrected += 1;
+            }
+        }
+        self.state
+            .announcements
+            .retain(|announcement| !announcement.releases.is_empty());
+
+        corrected
+    }
+
+    /// Сохраняет outbox до отправки и после каждого чата; без этого рассылать нельзя.
+    fn save_outbox(&self) -> Result<(), OrchestratorError> {
+        if let Some(store) = &self.state_store {
+            store.persist(&self.state)?;
+        }
+        Ok(())
+    }
+
+    /// Отправляет сообщения одного чата (в тему `thread_id`); ошибка чата не
+    /// прерывает рассылку остальным.
+    ///
+    /// При переезде группы недоставленные сообщения повторяются на новый id,
+    /// а настройки чата переносятся в состояние; при 403 чат помечается неактивным.
+    async fn deliver(
+        &mut self,
+        chat_id: i64,
+        thread_id: Option<i64>,
+        messages: Vec<String>,
+        options: &MessageOptions,
+    ) -> (DeliveryOutcome, Vec<Option<i64>>) {
+        let mut target = chat_id;
+        let mut migrated = None;
+        let mut delivered = Vec::new();
+
+        loop {
+            let sent = self
+                .dispatcher
+                .send_messages(
+                    target,
+                    thread_id,
+                    messages[delivered.len()..].to_vec(),
+                    options,
+                )
+                .await;
+            let err = match sent {
+                Ok(message_ids) => {
+                    delivered.extend(message_ids);
+                    let outcome = match migrated {
+                        Some(to) => DeliveryOutcome::Migrated { to },
+                        None => DeliveryOutcome::Delivered,
+                    };
+                    return (outcome, delivered);
+                }
+                Err(SendFailure { sent, error }) => {
+                    delivered.extend(sent);
+                    error
+                }
+            };
+
+            match err.downcast_ref::<TelegramError>() {
+                Some(TelegramError::ChatMigrated {
+                    migrate_to_chat_id, ..
+                }) if migrated.is_none() => {
+                    let to = *migrate_to_chat_id;
+                    warn!(
+                        target: "orchestrator",
+                        from = target,
+                        to,
+                        "Чат перенесён в супергруппу, повторяю отправку"
+                    );
+                    migrate_chat(
+                        target,
+                        to,
+                        &self.telegram_config,
+                        &mut self.state.subscriptions,
+                    );
+                    self.state.chat_migrations.insert(target, to);
+                    self.rename_configured_chat(target, to);
+                    self.chat_history.rename_chat(target, to);
+                    if let Some(queued) = self.state.backlog.remove(&target) {
+                        self.state.backlog.entry(to).or_default().extend(queued);
+                    }
+                    if let Some(digest) = self.state.digests.remove(&target) {
+                        self.state.digests.insert(to, digest);
+                    }
+                    self.dispatcher.allow_chat(to);
+                    target = to;
+                    migrated = Some(to);
+                }
+                Some(TelegramError::Forbidden { description, .. }) => {
+                    warn!(
+                        target: "orchestrator",
+                        chat_id = target,
+                        description = %description,
+                        "Бот не может писать в чат, чат отключён"
+                    );
+                    deactivate_chat(target, &self.telegram_config, &mut self.state.subscriptions);
+                    return (DeliveryOutcome::Forbidden, delivered);
+                }
+                // Нет прав, чат не найден или тема закрыта: чат остаётся в рассылке,
+                // но в этом прогоне пропускается, не помечая прогон неудачным.
+                Some(
+                    error @ (TelegramError::ChatNotFound { .. }
+                    | TelegramError::NotEnoughRights { .. }
+                    | TelegramError::TopicClosed { .. }
+                    | TelegramError::ChatSkipped { .. }),
+                ) => {
+                    warn!(
+                        target: "orchestrator",
+                        chat_id = target,
+                        error = %error,
+                        "Бот не может писать в чат, чат пропущен"
+                    );
+                    let outcome = DeliveryOutcome::Skipped {
+                        reason: error.to_string(),
+                    };
+                    return (outcome, delivered);
+                }
+                _ => {
+                    warn!(
+                        target: "orchestrator",
+                        chat_id = target,
+                        error = %err,
+                        "Не удалось доставить сообщения в чат, продолжаю с остальными"
+                    );
+                    let outcome = DeliveryOutcome::Failed {
+                        error: err.to_string(),
+                    };
+                    return (outcome, delivered);
+                }
+            }
+        }
+    }
+
+    /// Забирает накопившиеся обновления Telegram и выполняет команды чатов.
+    ///
+    /// Смещение `getUpdates` сохраняется в состоянии после каждой пачки, поэтому
+    /// команда не выполняется повторно даже после перезапуска. Возвращает число команд.
+    pub async fn process_updates(
+        &mut self,
+        now: DateTime<Utc>,
+        timeout_secs: u64,
+    ) -> Result<usize, OrchestratorError> {
+        self.process_updates_until(now, timeout_secs, std::future::pending())
+            .await
+    }
+
+    /// То же, что [`Self::process_updates`], но ожидание `getUpdates` прерывается,
+    /// как только завершится `cancel`. Уже полученная пачка доделывается целиком.
+    pub async fn process_updates_until<F>(
+        &mut self,
+        now: DateTime<Utc>,
+        timeout_secs: u64,
+        cancel: F,
+    ) -> Result<usize, OrchestratorError>
+    where
+        F: std::future::Future<Output = ()>,
+    {
+        self.restore_state();
+        let Some(channel) = self.update_channel.take() else {
+            return Ok(0);
+        };
+
+        let result = self
+            .handle_updates(channel.as_ref(), now, timeout_secs, cancel)
+            .await;
+        self.update_channel = Some(channel);
+        result
+    }
+
+    async fn handle_updates<F>(
+        &mut self,
+        channel: &dyn UpdateChannel,
+        now: DateTime<Utc>,
+        timeout_secs: u64,
+        cancel: F,
+    ) -> Result<usize, OrchestratorError>
+    where
+        F: std::future::Future<Output = ()>,
+    {
+        let updates = tokio::select! {
+            updates = channel.fetch_updates(self.state.updates.offset, timeout_secs) => {
+                updates.map_err(OrchestratorError::Updates)?
+            }
+            () = cancel => return Ok(0),
+        };
+        if updates.is_empty() {
+            return Ok(0);
+        }
+        let bot_username = self.resolve_bot_username(channel).await?;
+
+        let mut handled = 0usize;
+        for update in updates {
+            if self
+                .handle_update(channel, update, &bot_username, now)
+                .await
+            {
+                handled += 1;
+            }
+        }
+
+        self.persist_state();
+        info!(target: "orchestrator", handled, "Обработаны команды бота");
+        Ok(handled)
+    }
+
+    /// Обрабатывает пачку обновлений, пришедших вебхуком, и возвращает число команд.
+    ///
+    /// Повторно доставленные обновления (с `update_id` меньше сохранённого смещения)
+    /// пропускаются, так что вебхук и polling делят одну защиту от дублей.
+    /// Состояние сохраняется один раз на пачку.
+    pub async fn process_webhook_updates(
+        &mut self,
+        updates: Vec<Update>,
+        now: DateTime<Utc>,
+    ) -> Result<usize, OrchestratorError> {
+        self.restore_state();
+        let Some(channel) = self.update_channel.take() else {
+            return Ok(0);
+        };
+
+        let result = match self.resolve_bot_username(channel.as_ref()).await {
+            Ok(bot_username) => {
+                let mut handled = 0usize;
+                for update in updates {
+                    if self
+                        .handle_update(channel.as_ref(), update, &bot_username, now)
+                        .await
+                    {
+                        handled += 1;
+                    }
+                }
+                Ok(handled)
+            }
+            Err(err) => Err(err),
+        };
+        self.update_channel = Some(channel);
+        let handled = result?;
+        self.persist_state();
+        Ok(handled)
+    }
+
+    /// Имя бота запрашивается один раз; до ответа `getMe` обновления не
+    /// обрабатываются, чтобы смещение не ушло дальше непрочитанных команд.
+    async fn resolve_bot_username(
+        &mut self,
+        channel: &dyn UpdateChannel,
+    ) -> Result<String, OrchestratorError> {
+        if let Some(username) = &self.bot_username {
+            return Ok(username.clone());
+        }
+        let username = channel
+            .bot_username()
+            .await
+            .map_err(OrchestratorError::Updates)?;
+        self.bot_username = Some(username.clone());
+        Ok(username)
+    }
+
+    async fn handle_update(
+        &mut self,
+        channel: &dyn UpdateChannel,
+        update: Update,
+        bot_username: &str,
+        now: DateTime<Utc>,
+    ) -> bool {
+        if self
+            .state
+            .updates
+            .offset
+            .is_some_and(|offset| update.update_id < offset)
+        {
+            return false;
+        }
+        self.state.updates.offset = Some(update.update_id + 1);
+        let Some(message) = update.message else {
+            return false;
+        };
+        let Some(command) = message
+            .text
+            .as_deref()
+            .and_then(|text| parse_command(text, bot_username))
+        else {
+            return false;
+        };
+
+        let reply = match command {
+            Ok(command) => self.execute_command(channel, &message, command, now).await,
+            Err(err) => format!("{err}\n\n{HELP_TEXT}"),
+        };
+        if let Err(err) = channel.reply(message.chat.id, reply).await {
+            warn!(
+                target: "orchestrator",
+                chat_id = message.chat.id,
+                error = %err,
+                "Не удалось ответить на команду"
+            );
+        }
+        true
+    }
+
+    async fn execute_command(
+        &mut self,
+        channel: &dyn UpdateChannel,
+        message: &IncomingMessage,
+        command: BotCommand,
+        now: DateTime<Utc>,
+    ) -> String {
+        let chat_id = message.chat.id;
+        if command.changes_settings() && !message.chat.is_private() {
+            let is_admin = match &message.from {
+                Some(user) => channel
+                    .is_chat_admin(chat_id, user.id)
+                    .await
+                    .unwrap_or_else(|err| {
+                        warn!(
+                            target: "orchestrator",
+                            chat_id,
+                            error = %err,
+                            "Не удалось проверить права в чате, команда отклонена"
+                        );
+                        false
+                    }),
+                None => false,
+            };
+            if !is_admin {
+                return "Менять подписку чата могут только его администраторы.".to_string();
+            }
+        }
+
+        match apply_command(
+            &command,
+            chat_id,
+            &self.telegram_config,
+            &mut self.state.subscriptions,
+        ) {
+            Some(reply) => reply,
+            None => self.render_upcoming(chat_id, now).await,
+        }
+    }
+
+    /// Релизы ближайших недель, отобранные по фильтрам чата (без учёта истории).
+    async fn render_upcoming(&self, chat_id: i64, now: DateTime<Utc>) -> String {
+        let window = ReleaseWindow {
+            start: now,
+            end: now + Duration::days(UPCOMING_WINDOW_DAYS),
+        };
+        let batch = match self.release_provider.fetch_releases(window).await {
+            Ok(batch) => batch,
+            Err(err) => {
+                warn!(
+                    target: "orchestrator",
+                    error = %err,
+                    "Не удалось загрузить релизы для /upcoming"
+                );
+                return "Не удалось получить список релизов, попробуйте позже.".to_string();
+            }
+        };
+
+        let chat = effective_config(&self.telegram_config, &self.state.subscriptions)
+            .chats
+            .into_iter()
+            .find(|chat| chat.chat_id == chat_id)
+            .unwrap_or_else(|| ChatConfig::new(chat_id));
+        let mut releases = Self::convert_movies(&batch.movies);
+        releases.extend(Self::convert_tv_events(&batch.tv_events));
+        let texts: Vec<String> = build_messages(&releases, &TelegramConfig { chats: vec![chat] })
+            .into_iter()
+            .map(|message| message.text)
+            .collect();
+
+        if texts.is_empty() {
+            "В ближайшие две недели подходящих релизов не найдено.".to_string()
+        } else {
+            texts.join("\n")
+        }
+    }
+
+    /// Загружает историю отправок только перед первым прогоном: в режиме демона
+    /// она дальше живёт в памяти и лишь сохраняется после каждого прогона.
+    fn restore_history(&mut self) {
+        if self.history_restored {
+            return;
+        }
+        self.history_restored = true;
+        if let Err(err) = self.movie_history.restore() {
+            warn!(
+                target: "orchestrator",
+                error = %err,
+                "Не удалось восстановить историю фильмов, продолжаю с пустой историей"
+            );
+        }
+        if let Err(err) = self.tv_history.restore() {
+            warn!(
+                target: "orchestrator",
+                error = %err,
+                "Не удалось восстановить историю сериалов, продолжаю с пустой историей"
+            );
+        }
+        if let Some(store) = &self.chat_history_store {
+            match store.restore() {
+                Ok(history) => self.chat_history = history,
+                Err(err) => warn!(
+                    target: "orchestrator",
+                    error = %err,
+                    "Не удалось восстановить историю чатов, перенесу её из общих"
+                ),
+            }
+        }
+    }
+
+    /// Разовый переход на истории по чатам: текущие чаты считаются получившими
+    /// всё из общих историй, чтобы не разослать старые релизы повторно.
+    fn migrate_chat_history(&mut self, config: &TelegramConfig) {
+        if self.chat_history.migrated_from_global {
+            return;
+        }
+        let keys: Vec<String> = self
+            .movie_history
+            .iter()
+            .map(|id| format!("movie:{id}"))
+            .chain(self.tv_history.iter().cloned())
+            .collect();
+        let chat_ids: Vec<i64> = config.chats.iter().map(|chat| chat.chat_id).collect();
+        info!(
+            target: "orchestrator",
+            chats = chat_ids.len(),
+            keys = keys.len(),
+            "Общие истории перенесены в истории чатов"
+        );
+        self.chat_history.migrate_from_global(&chat_ids, &keys);
+    }
+
+    /// Сохраняет историю чатов; `false`, если сохранить не удалось.
+    fn persist_chat_history(&self) -> bool {
+        let Some(store) = &self.chat_history_store else {
+            return true;
+        };
+        match store.persist(&self.chat_history) {
+            Ok(()) => true,
+            Err(err) => {
+                warn!(
+                    target: "orchestrator",
+                    error = %err,
+                    "Не удалось сохранить историю чатов, доставленное останется в outbox"
+                );
+                false
+            }
+        }
+    }
+
+    /// Восстанавливает состояние один раз за жизнь оркестратора, чтобы команды
+    /// и прогоны в одном процессе работали с общей копией.
+    fn restore_state(&mut self) {
+        if self.state_restored {
+            return;
+        }
+        self.state_restored = true;
+        if let Some(store) = &self.state_store {
+            match store.restore() {
+                Ok(state) => self.state = state,
+                Err(err) => warn!(
+                    target: "orchestrator",
+                    error = %err,
+                    "Не удалось восстановить состояние бота, продолжаю с пустым состоянием"
+                ),
+            }
+        }
+        // Группа могла переезжать не раз: чат переводится на последний id цепочки.
+        let migrations = &self.state.chat_migrations;
+        let renames: Vec<(i64, i64)> = migrations
+            .keys()
+            .map(|from| {
+                let mut to = *from;
+                for _ in 0..migrations.len() {
+                    match migrations.get(&to) {
+                        Some(next) => to = *next,
+                        None => break,
+                    }
+                }
+                (*from, to)
             })
             .collect();
+        for (from, to) in renames {
+            self.rename_configured_chat(from, to);
+        }
+    }
 
-        let movie_inserted = self.movie_history.append(&movie_ids);
-        let tv_inserted = self.tv_history.append(&tv_keys);
+    /// Переводит чат из окружения на новый id после переезда в супергруппу:
+    /// иначе его настройки (лимиты, частота, темы) остались бы у старого id.
+    fn rename_configured_chat(&mut self, from: i64, to: i64) {
+        let mut renamed = false;
+        for chat in &mut self.telegram_config.chats {
+            if chat.chat_id == from {
+                chat.chat_id = to;
+                renamed = true;
+            }
+        }
+        if let Some(policy) = self.settings.backlog.chats.remove(&from) {
+            self.settings.backlog.chats.entry(to).or_insert(policy);
+        }
+        if renamed {
+            self.dispatcher.allow_chat(to);
+        }
+    }
 
-        if movie_inserted > 0
-            && let Err(err) = self.movie_history.persist()
-        {
-            eprintln!("WARN: не удалось сохранить историю фильмов, продолжаю без ошибки: {err}");
+    /// Дополняет батч лентой изменений и сдвигает курсор; ошибки ленты не прерывают прогон.
+    async fn fetch_change_feed(
+        &mut self,
+        batch: &mut ReleaseBatch,
+        window: ReleaseWindow,
+    ) -> usize {
+        if !self.settings.change_feed {
+            return 0;
         }
-        if tv_inserted > 0
-            && let Err(err) = self.tv_history.persist()
+
+        let since = self.state.change_feed.synced_until.unwrap_or(window.start);
+        match self
+            .release_provider
+            .fetch_changed_releases(since, window)
+            .await
         {
-            eprintln!("WARN: не удалось сохранить историю сериалов, продолжаю без ошибки: {err}");
+            Ok(changed) => {
+                let synced_until = changed.synced_until.unwrap_or(window.end);
+                let added = batch.merge(changed);
+                self.state.change_feed.synced_until = Some(synced_until);
+                info!(
+                    target: "orchestrator",
+                    since = %since,
+                    synced_until = %synced_until,
+                    added,
+                    "Учтена лента изменений TMDB"
+                );
+                added
+            }
+            Err(err) => {
+                warn!(
+                    target: "orchestrator",
+                    error = %err,
+                    "Не удалось загрузить ленту изменений TMDB, курсор не сдвигается"
+                );
+                0
+            }
         }
+    }
 
-        Ok(RunSummary {
-            fetched,
-            new_releases: candidate_count,
-            sent_releases: combined.len(),
-            duplicates,
-            messages_sent,
-            movie_history_appended: movie_inserted,
-            tv_history_appended: tv_inserted,
-            truncated: candidate_count.saturating_sub(combined.len()),
-        })
+    fn publish_filter_report(&self, report: &FilterReport) {
+        let Some(archive) = &self.report_archive else {
+            return;
+        };
+
+        let json = match report.to_json() {
+            Ok(json) => json,
+            Err(err) => {
+                warn!(
+                    target: "orchestrator",
+                    error = %err,
+                    "Не удалось сериализовать отчёт фильтрации"
+                );
+                return;
+            }
+        };
+        let published = archive
+            .publish(
+                FILTER_REPORT_ARTIFACT_NAME,
+                "filter_report.json",
+                json.as_bytes(),
+            )
+            .and_then(|_| {
+                archive.publish(
+                    FILTER_REPORT_CSV_ARTIFACT_NAME,
+                    "filter_report.csv",
+                    report.to_csv().as_bytes(),
+                )
+            });
+        if let Err(err) = published {
+            warn!(
+                target: "orchestrator",
+                error = %err,
+                "Не удалось выгрузить отчёт фильтрации, продолжаю без ошибки"
+            );
+        }
+    }
+
+    fn persist_state(&self) {
+        let Some(store) = &self.state_store else {
+            return;
+        };
+        if let Err(err) = store.persist(&self.state) {
+            warn!(
+                target: "orchestrator",
+                error = %err,
+                "Не удалось сохранить состояние бота, продолжаю без ошибки"
+            );
+        }
     }
 
-    fn filter_new_movies(&self, releases: Vec<MovieRelease>) -> (Vec<MovieRelease>, usize) {
+    /// Оставляет фильмы, которые ещё не дошли хотя бы до одного из чатов, которым
+    /// они адресованы; остальные считаются дубликатами.
+    fn filter_new_movies(
+        &self,
+        releases: Vec<MovieRelease>,
+        config: &TelegramConfig,
+    ) -> (Vec<MovieRelease>, usize) {
         let mut unique = Vec::new();
         let mut duplicates = 0usize;
         let mut seen = std::collections::HashSet::new();
 
         for release in releases.into_iter() {
-            if self.movie_history.contains(release.id) || !seen.insert(release.id) {
+            if !seen.insert(release.id) {
+                duplicates += 1;
+                continue;
+            }
+            let digital = Self::convert_movie(&release);
+            if !self.pending_for_any_chat(&digital, config) {
                 duplicates += 1;
                 continue;
             }
@@ -202,14 +2057,22 @@ where
         (unique, duplicates)
     }
 
-    fn filter_new_tv_events(&self, events: Vec<TvEvent>) -> (Vec<TvEvent>, usize) {
+    fn filter_new_tv_events(
+        &self,
+        events: Vec<TvEvent>,
+        config: &TelegramConfig,
+    ) -> (Vec<TvEvent>, usize) {
         let mut unique = Vec::new();
         let mut duplicates = 0usize;
         let mut seen = std::collections::HashSet::new();
 
         for event in events.into_iter() {
-            let key = event.event_key();
-            if self.tv_history.contains(&key) || !seen.insert(key.clone()) {
+            if !seen.insert(event.event_key()) {
+                duplicates += 1;
+                continue;
+            }
+            let digital = Self::convert_tv_event(&event);
+            if !self.pending_for_any_chat(&digital, config) {
                 duplicates += 1;
                 continue;
             }
@@ -219,44 +2082,64 @@ where
         (unique, duplicates)
     }
 
+    /// Есть ли чат, которому релиз адресован и который его ещё не получал.
+    fn pending_for_any_chat(&self, release: &DigitalRelease, config: &TelegramConfig) -> bool {
+        config.chats.iter().any(|chat| {
+            release_targets_chat(chat, release)
+                && !self.chat_history.contains(chat.chat_id, &release.event_key)
+        })
+    }
+
     fn convert_movies(releases: &[MovieRelease]) -> Vec<DigitalRelease> {
-        releases
-            .iter()
-            .map(|release| DigitalRelease {
-                id: release.id,
-                title: release.title.clone(),
-                event_date: release.digital_release_date,
-                locale: release.original_language.clone(),
-                kind: ReleaseKind::Movie,
-                vote_average: release.vote_average,
-                vote_count: release.vote_count,
-                event_key: format!("movie:{}", release.id),
-            })
-            .collect()
+        releases.iter().map(Self::convert_movie).collect()
+    }
+
+    fn convert_movie(release: &MovieRelease) -> DigitalRelease {
+        DigitalRelease {
+            id: release.id,
+            title: release.title.clone(),
+            event_date: release.digital_release_date,
+            locale: release.original_language.clone(),
+            kind: ReleaseKind::Movie,
+            vote_average: release.vote_average,
+            vote_count: release.vote_count,
+            popularity: Some(release.popularity),
+            has_providers: !release.watch_providers.is_empty(),
+            genres: release.genres.clone(),
+            event_key: format!("movie:{}", release.id),
+            regional_dates: release.regional_digital_dates.clone(),
+            only_for_regions: release.only_for_regions,
+            collection: release.collection.clone(),
+            only_for_followers: release.only_for_followers,
+        }
     }
 
     fn convert_tv_events(events: &[TvEvent]) -> Vec<DigitalRelease> {
-        events
-            .iter()
-            .map(|event| {
-                let kind = match event.kind {
-                    TvEventKind::Premiere => ReleaseKind::TvPremiere,
-                    TvEventKind::Season { season_number } => {
-                        ReleaseKind::TvSeason { season_number }
-                    }
-                };
-                DigitalRelease {
-                    id: event.show_id,
-                    title: event.show_name.clone(),
-                    event_date: event.event_date,
-                    locale: event.original_language.clone(),
-                    kind,
-                    vote_average: event.vote_average,
-                    vote_count: event.vote_count,
-                    event_key: event.event_key(),
-                }
-            })
-            .collect()
+        events.iter().map(Self::convert_tv_event).collect()
+    }
+
+    fn convert_tv_event(event: &TvEvent) -> DigitalRelease {
+        let kind = match event.kind {
+            TvEventKind::Premiere => ReleaseKind::TvPremiere,
+            TvEventKind::Season { season_number } => ReleaseKind::TvSeason { season_number },
+        };
+        DigitalRelease {
+            id: event.show_id,
+            title: event.show_name.clone(),
+            event_date: event.event_date,
+            locale: event.original_language.clone(),
+            kind,
+            vote_average: event.vote_average,
+            vote_count: event.vote_count,
+            popularity: event.popularity,
+            has_providers: false,
+            genres: Vec::new(),
+            event_key: event.event_key(),
+            regional_dates: Default::default(),
+            only_for_regions: false,
+            collection: None,
+            only_for_followers: false,
+        }
     }
 }
 
@@ -266,58 +2149,318 @@ pub enum OrchestratorError {
     State(#[from] StateError),
     #[error("ошибка загрузки релизов: {0}")]
     Releases(BoxError),
+    /// Слишком много тайтлов пропущено из-за ошибок TMDB; отчёт прогона прилагается.
+    #[error("ошибка загрузки релизов: {source}")]
+    ErrorBudget {
+        report: Box<FilterReport>,
+        source: BoxError,
+    },
     #[error("ошибка отправки уведомлений: {0}")]
     Dispatch(BoxError),
+    #[error("ошибка получения обновлений Telegram: {0}")]
+    Updates(BoxError),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RunSummary {
+    pub window_start: DateTime<Utc>,
+    /// Прошлый успешный прогон, если простой превысил допустимый догон.
+    pub uncovered_since: Option<DateTime<Utc>>,
     pub fetched: usize,
+    pub change_feed_releases: usize,
+    pub filter_rejected: usize,
+    pub tmdb_failures: usize,
     pub new_releases: usize,
     pub sent_releases: usize,
     pub duplicates: usize,
     pub messages_sent: usize,
     pub movie_history_appended: usize,
     pub tv_history_appended: usize,
+    /// Новые релизы, отложенные в очередь из-за лимита чата.
     pub truncated: usize,
+    /// Сколько релизов ждёт в очередях чатов после прогона.
+    pub backlog: usize,
+    /// Релизы, отложенные из-за тихих часов чатов.
+    pub quiet_deferred: usize,
+    /// Сообщения, досланные из outbox прервавшегося прошлого прогона.
+    pub resumed_messages: usize,
+    /// Ранее отправленные сообщения, исправленные по свежим данным TMDB.
+    pub edited_messages: usize,
+    /// Анонсы фильмов, чья цифровая дата перенесена в будущее или отозвана.
+    pub corrections: usize,
+    /// Сообщения, доставленные повтором после таймаута: в чате возможен дубль.
+    pub possible_duplicates: usize,
+    /// Результаты доставки по чатам в порядке `chat_id`.
+    pub chats: Vec<ChatDelivery>,
+}
+
+/// Результат доставки в один чат за прогон.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatDelivery {
+    pub chat_id: i64,
+    /// Тема форума; чат с темами получает по записи на каждую тему.
+    pub thread_id: Option<i64>,
+    /// Сколько релизов было адресовано чату.
+    pub releases: usize,
+    /// Сколько сообщений доставлено.
+    pub messages: usize,
+    pub outcome: DeliveryOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryOutcome {
+    Delivered,
+    /// Группа стала супергруппой, сообщения доставлены на новый id.
+    Migrated {
+        to: i64,
+    },
+    /// Бот удалён из чата или заблокирован; чат отключён.
+    Forbidden,
+    /// Нет прав писать, чат не найден или тема закрыта; чат пропущен.
+    Skipped {
+        reason: String,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl DeliveryOutcome {
+    fn describe(&self) -> String {
+        match self {
+            Self::Delivered => "доставлено".to_string(),
+            Self::Migrated { to } => format!("доставлено, чат перенесён в {to}"),
+            Self::Forbidden => "бот удалён или заблокирован, чат отключён".to_string(),
+            Self::Skipped { reason } => format!("пропущено: {reason}"),
+            Self::Failed { error } => format!("ошибка: {error}"),
+        }
+    }
 }
 
 impl RunSummary {
     pub fn render_markdown(&self) -> String {
-        format!(
-            "*Итоги прогона:*\\n- загружено релизов: {}\\n- новых релизов после истории: {}\\n- отправлено релизов: {}\\n- дубликатов: {}\\n- отправлено сообщений: {}\\n- добавлено в историю фильмов: {}\\n- добавлено в историю сериалов: {}\\n- отброшено из-за лимита: {}",
+        let summary = format!(
+            "*Итоги прогона:*\\n- загружено релизов: {}\\n- из ленты изменений: {}\\n- отклонено фильтрами: {}\\n- пропущено из-за ошибок TMDB: {}\\n- новых релизов после истории: {}\\n- отправлено релизов: {}\\n- дубликатов: {}\\n- отправлено сообщений: {}\\n- добавлено в историю фильмов: {}\\n- добавлено в историю сериалов: {}\\n- отложено из-за лимита: {}\\n- в очереди: {}\\n- отложено на тихие часы: {}\\n- перенесено чатов: {}\\n- отключено чатов: {}\\n- пропущено чатов без прав: {}\\n- дослано из outbox: {}\\n- исправлено сообщений: {}\\n- поправок к анонсам: {}\\n- возможных дублей сообщений: {}",
             self.fetched,
+            self.change_feed_releases,
+            self.filter_rejected,
+            self.tmdb_failures,
             self.new_releases,
             self.sent_releases,
             self.duplicates,
             self.messages_sent,
             self.movie_history_appended,
             self.tv_history_appended,
-            self.truncated
+            self.truncated,
+            self.backlog,
+            self.quiet_deferred,
+            self.migrated_chats().len(),
+            self.inactive_chats().len(),
+            self.skipped_chats().len(),
+            self.resumed_messages,
+            self.edited_messages,
+            self.corrections,
+            self.possible_duplicates
+        );
+        let chats: String = self
+            .chats
+            .iter()
+            .map(|chat| {
+                format!(
+                    "\\n  - чат {}{}: {} (релизов: {}, сообщений: {})",
+                    chat.chat_id,
+                    chat.thread_id
+                        .map(|thread_id| format!(", тема {thread_id}"))
+                        .unwrap_or_default(),
+                    chat.outcome.describe(),
+                    chat.releases,
+                    chat.messages
+                )
+            })
+            .collect();
+        let gap = self
+            .uncovered_since
+            .map(|since| {
+                format!(
+                    "\\n- ⚠️ простой с {}: релизы просканированы только с {}, более ранние пропущены",
+                    since.format("%Y-%m-%d"),
+                    self.window_start.format("%Y-%m-%d")
+                )
+            })
+            .unwrap_or_default();
+        format!(
+            "{summary}\\n- чатов с ошибкой доставки: {}{gap}{chats}",
+            self.failed_chats().len()
         )
     }
+
+    /// Группы, ставшие супергруппами: (старый id, новый id).
+    pub fn migrated_chats(&self) -> Vec<(i64, i64)> {
+        self.chats
+            .iter()
+            .filter_map(|chat| match chat.outcome {
+                DeliveryOutcome::Migrated { to } => Some((chat.chat_id, to)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Чаты, отключённые из-за 403 (бот удалён или заблокирован).
+    pub fn inactive_chats(&self) -> Vec<i64> {
+        self.chats_with(|outcome| *outcome == DeliveryOutcome::Forbidden)
+    }
+
+    /// Чаты, пропущенные из-за нехватки прав, закрытой темы или ненайденного чата.
+    pub fn skipped_chats(&self) -> Vec<i64> {
+        self.chats_with(|outcome| matches!(outcome, DeliveryOutcome::Skipped { .. }))
+    }
+
+    /// Чаты, доставка в которые завершилась ошибкой.
+    pub fn failed_chats(&self) -> Vec<i64> {
+        self.chats_with(|outcome| matches!(outcome, DeliveryOutcome::Failed { .. }))
+    }
+
+    fn chats_with(&self, predicate: impl Fn(&DeliveryOutcome) -> bool) -> Vec<i64> {
+        self.chats
+            .iter()
+            .filter(|chat| predicate(&chat.outcome))
+            .map(|chat| chat.chat_id)
+            .collect()
+    }
 }
 
 #[async_trait]
 impl ReleaseProvider for TmdbClient {
     async fn fetch_releases(&self, window: ReleaseWindow) -> Result<ReleaseBatch, BoxError> {
-        let movies = self
+        let (movies, mut report) = self
             .fetch_digital_releases(window)
             .await
             .map_err(|err| Box::new(err) as BoxError)?;
-        let tv_events = self
+        let (tv_events, tv_report) = self
             .fetch_tv_events(window)
             .await
             .map_err(|err| Box::new(err) as BoxError)?;
+        report.extend(tv_report);
+
+        Ok(ReleaseBatch {
+            movies,
+            tv_events,
+            report,
+            ..ReleaseBatch::default()
+        })
+    }
+
+    async fn fetch_changed_releases(
+        &self,
+        since: DateTime<Utc>,
+        window: ReleaseWindow,
+    ) -> Result<ReleaseBatch, BoxError> {
+        let (movies, mut report, movies_synced) = self
+            .fetch_changed_digital_releases(since, window)
+            .await
+            .map_err(|err| Box::new(err) as BoxError)?;
+        let (tv_events, tv_report, tv_synced) = self
+            .fetch_changed_tv_events(since, window)
+            .await
+            .map_err(|err| Box::new(err) as BoxError)?;
+        report.extend(tv_report);
 
-        Ok(ReleaseBatch { movies, tv_events })
+        Ok(ReleaseBatch {
+            movies,
+            tv_events,
+            report,
+            // Курсор общий: отстающая лента дочитывается, опережающая повторит сутки.
+            synced_until: Some(movies_synced.min(tv_synced)),
+        })
+    }
+
+    fn check_error_budget(&self, report: &FilterReport) -> Result<(), BoxError> {
+        TmdbClient::check_error_budget(self, report).map_err(|err| Box::new(err) as BoxError)
+    }
+
+    async fn digital_release(
+        &self,
+        movie_id: u64,
+        today: NaiveDate,
+    ) -> Result<Option<DigitalReleaseDate>, BoxError> {
+        self.fetch_digital_release(movie_id, today)
+            .await
+            .map_err(|err| Box::new(err) as BoxError)
     }
 }
 
 #[async_trait]
 impl MessageDispatcher for TelegramDispatcher {
-    async fn send_messages(&self, chat_id: i64, messages: Vec<String>) -> Result<(), BoxError> {
-        self.send_batch(chat_id, messages)
+    async fn send_messages(
+        &self,
+        chat_id: i64,
+        thread_id: Option<i64>,
+        messages: Vec<String>,
+        options: &MessageOptions,
+    ) -> Result<Vec<Option<i64>>, SendFailure> {
+        self.send_topic_batch_partial(chat_id, thread_id, messages, options)
+            .await
+            .map_err(|partial| SendFailure {
+                sent: partial.sent,
+                error: Box::new(partial.error),
+            })
+    }
+
+    async fn edit_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        text: String,
+        options: &MessageOptions,
+    ) -> Result<(), BoxError> {
+        self.edit_message_text(chat_id, message_id, text, options)
+            .await
+            .map_err(|err| Box::new(err) as BoxError)
+    }
+
+    async fn delete_message(&self, chat_id: i64, message_id: i64) -> Result<(), BoxError> {
+        TelegramDispatcher::delete_message(self, chat_id, message_id)
+            .await
+            .map_err(|err| Box::new(err) as BoxError)
+    }
+
+    fn allow_chat(&self, chat_id: i64) {
+        TelegramDispatcher::allow_chat(self, chat_id);
+    }
+
+    fn take_possible_duplicates(&self) -> Vec<PossibleDuplicate> {
+        TelegramDispatcher::take_possible_duplicates(self)
+    }
+}
+
+#[async_trait]
+impl UpdateChannel for TelegramDispatcher {
+    async fn fetch_updates(
+        &self,
+        offset: Option<i64>,
+        timeout_secs: u64,
+    ) -> Result<Vec<Update>, BoxError> {
+        self.get_updates(offset, timeout_secs)
+            .await
+            .map_err(|err| Box::new(err) as BoxError)
+    }
+
+    async fn reply(&self, chat_id: i64, text: String) -> Result<(), BoxError> {
+        self.send_reply(chat_id, text)
+            .await
+            .map_err(|err| Box::new(err) as BoxError)
+    }
+
+    async fn is_chat_admin(&self, chat_id: i64, user_id: i64) -> Result<bool, BoxError> {
+        let status = self
+            .chat_member_status(chat_id, user_id)
+            .await
+            .map_err(|err| Box::new(err) as BoxError)?;
+        Ok(matches!(status.as_str(), "creator" | "administrator"))
+    }
+
+    async fn bot_username(&self) -> Result<String, BoxError> {
+        TelegramDispatcher::bot_username(self)
             .await
             .map_err(|err| Box::new(err) as BoxError)
     }
@@ -339,4 +2482,39 @@ mod tests {
         assert_eq!(window.end, now);
         assert_eq!(window.start, now - Duration::days(7));
     }
+
+    #[test]
+    fn catch_up_window_covers_release_window_and_is_capped() {
+        type Runner = Orchestrator<
+            crate::github::artifacts::GitHubArtifactsClient,
+            TmdbClient,
+            TelegramDispatcher,
+        >;
+        let now = Utc::now();
+        let policy = WindowPolicy {
+            overlap_hours: 2,
+            max_catch_up_days: 10,
+        };
+
+        let (recent, gap) = Runner::catch_up_window(now, Some(now - Duration::hours(6)), policy);
+        assert_eq!(recent.start, now - Duration::days(7));
+        assert_eq!(gap, None);
+
+        let (outage, gap) = Runner::catch_up_window(now, Some(now - Duration::days(9)), policy);
+        assert_eq!(outage.start, now - Duration::days(9) - Duration::hours(2));
+        assert_eq!(gap, None);
+
+        let short = WindowPolicy {
+            max_catch_up_days: 3,
+            ..policy
+        };
+        let (short, gap) = Runner::catch_up_window(now, Some(now - Duration::hours(6)), short);
+        assert_eq!(short.start, now - Duration::days(3));
+        assert_eq!(gap, None);
+
+        let outage = now - Duration::days(20);
+        let (capped, gap) = Runner::catch_up_window(now, Some(outage), policy);
+        assert_eq!(capped.start, now - Duration::days(10));
+        assert_eq!(gap, Some(outage));
+    }
 }
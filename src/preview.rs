@@ -0,0 +1,169 @@
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use serde::Serialize;
+use thiserror::Error;
+
+/// Сообщения, которые прогон отправил бы в один чат.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChatPreview {
+    pub chat_id: i64,
+    /// Тема форума, в которую уйдут сообщения.
+    pub thread_id: Option<i64>,
+    pub messages: Vec<String>,
+    /// Ключи релизов рассылки (`movie:<id>`, `tv:<id>:premiere`, ...).
+    pub event_keys: Vec<String>,
+    /// Разбор оценки каждого релиза: `movie:1: 5.20 = оценка 2.25 + ...`.
+    pub scores: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewFormat {
+    Text,
+    Json,
+    /// Страница, имитирующая ленту чатов Telegram.
+    Html,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("неизвестный формат предпросмотра: {0} (ожидается text, json или html)")]
+pub struct PreviewFormatError(String);
+
+impl FromStr for PreviewFormat {
+    type Err = PreviewFormatError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.trim().to_lowercase().as_str() {
+            "" | "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "html" => Ok(Self::Html),
+            other => Err(PreviewFormatError(other.to_owned())),
+        }
+    }
+}
+
+/// Отрисовывает предпросмотр в выбранном формате.
+pub fn render(chats: &[ChatPreview], format: PreviewFormat) -> String {
+    match format {
+        PreviewFormat::Text => render_text(chats),
+        PreviewFormat::Json => {
+            serde_json::to_string_pretty(chats).expect("предпросмотр сериализуется в JSON")
+        }
+        PreviewFormat::Html => render_html(chats),
+    }
+}
+
+fn render_text(chats: &[ChatPreview]) -> String {
+    if chats.is_empty() {
+        return "Прогон ничего не отправит\n".to_string();
+    }
+    let mut out = String::new();
+    for chat in chats {
+        let _ = writeln!(
+            out,
+            "=== чат {}{} (релизов: {}, сообщений: {}) ===",
+            chat.chat_id,
+            topic_suffix(chat),
+            chat.event_keys.len(),
+            chat.messages.len()
+        );
+        for (index, message) in chat.messages.iter().enumerate() {
+            let _ = writeln!(out, "--- сообщение {} ---\n{message}", index + 1);
+        }
+        if !chat.scores.is_empty() {
+            out.push_str("--- оценки ---\n");
+            for score in &chat.scores {
+                let _ = writeln!(out, "{score}");
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn render_html(chats: &[ChatPreview]) -> String {
+    let mut out = String::from(concat!(
+        "<!DOCTYPE html>\n<html lang=\"ru\">\n<head>\n<meta charset=\"utf-8\">\n",
+        "<title>Предпросмотр рассылки</title>\n<style>\n",
+        "body{background:#8ba8c4;font-family:sans-serif;margin:0;padding:16px}\n",
+        ".chat{max-width:560px;margin:0 auto 24px}\n",
+        ".chat h2{color:#fff;font-size:15px;margin:0 0 8px}\n",
+        ".bubble{background:#fff;border-radius:12px;padding:8px 12px;margin:6px 0;",
+        "white-space:pre-wrap;font-size:14px;line-height:1.35;box-shadow:0 1px 1px rgba(0,0,0,.15)}\n",
+        ".empty{color:#fff;text-align:center}\n",
+        "</style>\n</head>\n<body>\n"
+    ));
+    if chats.is_empty() {
+        out.push_str("<p class=\"empty\">Прогон ничего не отправит</p>\n");
+    }
+    for chat in chats {
+        let _ = writeln!(
+            out,
+            "<section class=\"chat\">\n<h2>Чат {}{} · релизов: {}</h2>",
+            chat.chat_id,
+            topic_suffix(chat),
+            chat.event_keys.len()
+        );
+        for message in &chat.messages {
+            let _ = writeln!(out, "<div class=\"bubble\">{}</div>", escape_html(message));
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn topic_suffix(chat: &ChatPreview) -> String {
+    chat.thread_id
+        .map(|thread_id| format!(", тема {thread_id}"))
+        .unwrap_or_default()
+}
+
+fn escape_html(raw: &str) -> String {
+    let mut escaped = String::with_capacity(raw.len());
+    for ch in raw.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<ChatPreview> {
+        vec![ChatPreview {
+            chat_id: -100,
+            thread_id: None,
+            messages: vec!["🎬 Фильм <Тест> & Ко".to_string()],
+            event_keys: vec!["movie:1".to_string()],
+            scores: vec!["movie:1: 3.00 = свежесть 2.00 + тип 1.00".to_string()],
+        }]
+    }
+
+    #[test]
+    fn renders_every_format() {
+        let chats = sample();
+
+        let text = render(&chats, PreviewFormat::Text);
+        assert!(text.contains("=== чат -100 (релизов: 1, сообщений: 1) ==="));
+        assert!(text.contains("🎬 Фильм <Тест> & Ко"));
+        assert!(text.contains("--- оценки ---\nmovie:1: 3.00 = свежесть 2.00 + тип 1.00"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&chats, PreviewFormat::Json)).expect("валидный JSON");
+        assert_eq!(json[0]["event_keys"][0], "movie:1");
+
+        let html = render(&chats, PreviewFormat::Html);
+        assert!(html.contains("<div class=\"bubble\">🎬 Фильм &lt;Тест&gt; &amp; Ко</div>"));
+
+        assert_eq!("HTML".parse(), Ok(PreviewFormat::Html));
+        assert!("yaml".parse::<PreviewFormat>().is_err());
+    }
+}
@@ -0,0 +1,216 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use thiserror::Error;
+
+/// Сколько лет вперёд ищется ближайший запуск; защищает от расписаний вида `0 0 31 2 *`.
+const MAX_LOOKAHEAD_YEARS: i32 = 5;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScheduleError {
+    #[error("расписание должно состоять из 5 полей (минута час день месяц день_недели): {0}")]
+    FieldCount(String),
+    #[error("некорректное поле расписания {field}: {value}")]
+    InvalidField { field: &'static str, value: String },
+}
+
+/// Расписание в формате cron из пяти полей, время — UTC.
+///
+/// Поддерживаются `*`, числа, диапазоны `a-b`, шаги `*/n` и `a-b/n` и списки через запятую.
+/// Как и в cron, если заданы и день месяца, и день недели, подходит любой из них.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = raw.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(ScheduleError::FieldCount(raw.to_string()));
+        };
+
+        let mut days_of_week = parse_field("day_of_week", day_of_week, 0, 7)?;
+        // 7 — то же воскресенье, что и 0.
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+
+        Ok(Self {
+            minutes: parse_field("minute", minute, 0, 59)?,
+            hours: parse_field("hour", hour, 0, 23)?,
+            days_of_month: parse_field("day_of_month", day_of_month, 1, 31)?,
+            months: parse_field("month", month, 1, 12)?,
+            days_of_week,
+            day_of_month_any: day_of_month.starts_with('*'),
+            day_of_week_any: day_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl Schedule {
+    /// Ближайший момент запуска строго позже `after`, с точностью до минуты.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after.year() + MAX_LOOKAHEAD_YEARS;
+        let mut date = start.date_naive();
+
+        while date.year() <= limit {
+            if self.matches_date(date) {
+                let from = if date == start.date_naive() {
+                    (start.hour(), start.minute())
+                } else {
+                    (0, 0)
+                };
+                if let Some((hour, minute)) = self.first_time_from(from) {
+                    return Utc
+                        .with_ymd_and_hms(date.year(), date.month(), date.day(), hour, minute, 0)
+                        .single();
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !self.months[date.month() as usize] {
+            return false;
+        }
+        let day_of_month = self.days_of_month[date.day() as usize];
+        let day_of_week = self.days_of_week[date.weekday().num_days_from_sunday() as usize];
+        match (self.day_of_month_any, self.day_of_week_any) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+
+    fn first_time_from(&self, (from_hour, from_minute): (u32, u32)) -> Option<(u32, u32)> {
+        (from_hour..24)
+            .filter(|hour| self.hours[*hour as usize])
+            .find_map(|hour| {
+                let first_minute = if hour == from_hour { from_minute } else { 0 };
+                (first_minute..60)
+                    .find(|minute| self.minutes[*minute as usize])
+                    .map(|minute| (hour, minute))
+            })
+    }
+}
+
+fn parse_field(
+    field: &'static str,
+    raw: &str,
+    min: u32,
+    max: u32,
+) -> Result<Vec<bool>, ScheduleError> {
+    let invalid = || ScheduleError::InvalidField {
+        field,
+        value: raw.to_string(),
+    };
+    let mut allowed = vec![false; max as usize + 1];
+
+    for part in raw.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start.parse().map_err(|_| invalid())?,
+                end.parse().map_err(|_| invalid())?,
+            )
+        } else {
+            let value = range.parse().map_err(|_| invalid())?;
+            // `5/15` в cron означает «с 5-й каждые 15».
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+
+    Ok(allowed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("валидная дата")
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn finds_next_slot_for_steps_and_lists() {
+        let schedule: Schedule = "0 */6 * * *".parse().expect("расписание разбирается");
+        assert_eq!(
+            schedule.next_after(at("2026-03-01T05:59:30Z")),
+            Some(at("2026-03-01T06:00:00Z"))
+        );
+        assert_eq!(
+            schedule.next_after(at("2026-03-01T06:00:00Z")),
+            Some(at("2026-03-01T12:00:00Z"))
+        );
+
+        let weekdays: Schedule = "30 9,18 * * 1-5".parse().expect("расписание разбирается");
+        // 2026-03-06 — пятница, следующий будний день — понедельник 9 марта.
+        assert_eq!(
+            weekdays.next_after(at("2026-03-06T18:30:00Z")),
+            Some(at("2026-03-09T09:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn star_prefixed_day_field_requires_both_days() {
+        // Как в cron: поле дня с `*` не включает режим «любой из двух дней».
+        let schedule: Schedule = "0 0 */2 * 1".parse().expect("расписание разбирается");
+        // 2 марта — понедельник, но чётное число; 9 марта — нечётный понедельник.
+        assert_eq!(
+            schedule.next_after(at("2026-03-01T00:00:00Z")),
+            Some(at("2026-03-09T00:00:00Z"))
+        );
+        assert_eq!(
+            schedule.next_after(at("2026-03-09T00:00:00Z")),
+            Some(at("2026-03-23T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(matches!(
+            "0 */6 * *".parse::<Schedule>(),
+            Err(ScheduleError::FieldCount(_))
+        ));
+        assert!(matches!(
+            "61 * * * *".parse::<Schedule>(),
+            Err(ScheduleError::InvalidField {
+                field: "minute",
+                ..
+            })
+        ));
+        assert!(matches!(
+            "*/0 * * * *".parse::<Schedule>(),
+            Err(ScheduleError::InvalidField { .. })
+        ));
+        let impossible: Schedule = "0 0 31 2 *".parse().expect("расписание разбирается");
+        assert_eq!(impossible.next_after(at("2026-01-01T00:00:00Z")), None);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::JsonArtifactStore;
+use crate::config::MessageOptions;
+use crate::formatter::ChatRelease;
+use crate::github::artifacts::GitHubArtifactsClient;
+
+/// Сводное состояние бота, которое переживает прогоны (курсоры, служебные метки).
+///
+/// В отличие от историй отправок хранится одним JSON-документом, поэтому новые
+/// секции добавляются как поля с `#[serde(default)]` без миграции файла.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BotState {
+    #[serde(default)]
+    pub change_feed: ChangeFeedCursor,
+    #[serde(default)]
+    pub updates: UpdatesCursor,
+    #[serde(default)]
+    pub last_run: RunCursor,
+    /// Настройки чатов, заданные командами бота; перекрывают конфигурацию из окружения.
+    #[serde(default)]
+    pub subscriptions: BTreeMap<i64, ChatSubscription>,
+    /// Переезды групп в супергруппы: старый `chat_id` → новый.
+    #[serde(default)]
+    pub chat_migrations: BTreeMap<i64, i64>,
+    /// Сообщения прогона, сохранённые до рассылки; см. [`OutboxEntry`].
+    #[serde(default)]
+    pub outbox: Vec<OutboxEntry>,
+    /// Релизы, не вошедшие в лимит прогона чата; досылаются в порядке приоритета.
+    #[serde(default)]
+    pub backlog: BTreeMap<i64, Vec<ChatRelease>>,
+    /// Накопленные дайджесты чатов с ежедневной или еженедельной рассылкой.
+    #[serde(default)]
+    pub digests: BTreeMap<i64, DigestState>,
+    /// Отправленные сообщения с релизами, которые ещё можно поправить на месте.
+    #[serde(default)]
+    pub announcements: Vec<Announcement>,
+    /// Сообщения, доставленные повтором после таймаута: в чате они могли
+    /// оказаться дважды. Хранятся столько же, сколько анонсы.
+    #[serde(default)]
+    pub possible_duplicates: Vec<PossibleDuplicateRecord>,
+    #[serde(default)]
+    pub rechecks: RecheckCursor,
+}
+
+/// Курсор инкрементального обхода TMDB `/changes`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeFeedCursor {
+    /// Момент, до которого изменения уже обработаны успешным прогоном.
+    pub synced_until: Option<DateTime<Utc>>,
+}
+
+/// Смещение `getUpdates`: следующий ещё не обработанный `update_id`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdatesCursor {
+    pub offset: Option<i64>,
+}
+
+/// Где остановилась перепроверка цифровых дат: следующий прогон продолжает
+/// с фильмов, чей `id` больше, и дальше по кругу.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecheckCursor {
+    pub last_movie_id: Option<u64>,
+}
+
+/// Момент, по который релизы просканированы последним успешным прогоном.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunCursor {
+    pub succeeded_at: Option<DateTime<Utc>>,
+}
+
+/// Подписка чата, управляемая командами `/subscribe`, `/unsubscribe` и `/filters`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatSubscription {
+    pub active: bool,
+    #[serde(default)]
+    pub locales: Vec<String>,
+    #[serde(default)]
+    pub regions: Vec<String>,
+    #[serde(default)]
+    pub collections: Vec<u64>,
+}
+
+/// Релизы, ждущие слота дайджеста чата.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DigestState {
+    #[serde(default)]
+    pub releases: Vec<ChatRelease>,
+    /// Когда дайджест отправлен или пропущен за отсутствием релизов в последний раз.
+    pub last_sent: Option<DateTime<Utc>>,
+}
+
+/// Отправленное сообщение: его `message_id`, текущий текст и релизы в нём такими,
+/// какими они были объявлены. По ним прогон замечает изменения в TMDB и правит
+/// сообщение через `editMessageText`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Announcement {
+    pub chat_id: i64,
+    pub message_id: i64,
+    /// Тема форума, в которую ушло сообщение; поправки отправляются туда же.
+    #[serde(default)]
+    pub thread_id: Option<i64>,
+    /// Параметры отправки: правка сохраняет превью ссылок, поправка — остальное.
+    #[serde(default)]
+    pub options: MessageOptions,
+    pub text: String,
+    pub releases: Vec<ChatRelease>,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Сообщение, которое могло уйти в чат дважды, и когда это обнаружено.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PossibleDuplicateRecord {
+    pub chat_id: i64,
+    pub message_id: Option<i64>,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Запланированная рассылка в один чат. Запись попадает в состояние до отправки
+/// и удаляется только после того, как её релизы сохранены в истории чата, поэтому
+/// прогон после сбоя досылает `Pending` с первого неподтверждённого сообщения
+/// и не повторяет `Sent`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub chat_id: i64,
+    /// Тема форума (`message_thread_id`); `None` — общая лента чата.
+    #[serde(default)]
+    pub thread_id: Option<i64>,
+    /// Параметры отправки чата для типа релизов рассылки.
+    #[serde(default)]
+    pub options: MessageOptions,
+    pub messages: Vec<String>,
+    /// Ключи релизов рассылки (`movie:<id>`, `tv:<id>:premiere`, ...).
+    pub event_keys: Vec<String>,
+    pub status: OutboxStatus,
+    /// `message_id` уже доставленных сообщений: первые `message_ids.len()`
+    /// сообщений из `messages` подтверждены Telegram.
+    #[serde(default)]
+    pub message_ids: Vec<Option<i64>>,
+    /// Отправить без звука: рассылка запланирована в тихие часы чата.
+    #[serde(default)]
+    pub silent: bool,
+    /// Релизы рассылки; после отправки по ним заводятся [`Announcement`].
+    #[serde(default)]
+    pub releases: Vec<ChatRelease>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    Pending,
+    Sent,
+}
+
+/// Хранилище [`BotState`]: локальный JSON-файл плюс артефакт GitHub.
+pub type BotStateStore<C = GitHubArtifactsClient> = JsonArtifactStore<BotState, C>;
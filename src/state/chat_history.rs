@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::JsonArtifactStore;
+use crate::github::artifacts::GitHubArtifactsClient;
+
+/// История отправок по чатам: какие события (`movie:<id>`, `tv:<id>:premiere`, ...)
+/// уже дошли до какого чата.
+///
+/// Заменяет общие текстовые истории при решении, что отправлять: чат, в который
+/// доставка не удалась, или новый чат получают релиз, уже отправленный остальным.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatHistory {
+    /// Общие истории уже перенесены; перенос выполняется один раз.
+    #[serde(default)]
+    pub migrated_from_global: bool,
+    #[serde(default)]
+    pub chats: BTreeMap<i64, BTreeSet<String>>,
+}
+
+impl ChatHistory {
+    pub fn contains(&self, chat_id: i64, key: &str) -> bool {
+        self.chats
+            .get(&chat_id)
+            .is_some_and(|keys| keys.contains(key))
+    }
+
+    /// Добавляет ключи в историю чата, возвращая количество реально вставленных.
+    pub fn append<I>(&mut self, chat_id: i64, keys: I) -> usize
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let history = self.chats.entry(chat_id).or_default();
+        let mut inserted = 0;
+        for key in keys {
+            if history.insert(key) {
+                inserted += 1;
+            }
+        }
+        inserted
+    }
+
+    /// Отзывает анонс после поправки: событие уходит из истории чата, чтобы его
+    /// анонсировали заново в настоящую дату.
+    pub fn retract(&mut self, chat_id: i64, key: &str) {
+        if let Some(history) = self.chats.get_mut(&chat_id) {
+            history.remove(key);
+        }
+    }
+
+    /// Разовый перенос общих историй: каждый из `chat_ids` считается получившим
+    /// всё, что было отправлено до перехода на истории по чатам.
+    pub fn migrate_from_global(&mut self, chat_ids: &[i64], keys: &[String]) {
+        if self.migrated_from_global {
+            return;
+        }
+        for chat_id in chat_ids {
+            self.append(*chat_id, keys.iter().cloned());
+        }
+        self.migrated_from_global = true;
+    }
+
+    /// Переносит историю группы, ставшей супергруппой, на новый `chat_id`.
+    pub fn rename_chat(&mut self, from: i64, to: i64) {
+        if let Some(keys) = self.chats.remove(&from) {
+            self.chats.entry(to).or_default().extend(keys);
+        }
+    }
+}
+
+/// Хранилище [`ChatHistory`]: локальный JSON-файл плюс артефакт GitHub.
+/// Без обоих история пустая и ещё не перенесена из общих.
+pub type ChatHistoryStore<C = GitHubArtifactsClient> = JsonArtifactStore<ChatHistory, C>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migration_from_global_runs_once() {
+        let mut history = ChatHistory::default();
+        let global = vec!["movie:1".to_string(), "tv:2:premiere".to_string()];
+
+        history.migrate_from_global(&[10, 20], &global);
+        history.migrate_from_global(&[30], &global);
+        history.rename_chat(20, -100200);
+
+        assert!(history.migrated_from_global);
+        assert!(history.contains(10, "movie:1"));
+        assert!(history.contains(-100200, "tv:2:premiere"));
+        assert!(!history.contains(20, "movie:1"));
+        assert!(
+            !history.contains(30, "movie:1"),
+            "новый чат не получает общую историю"
+        );
+        assert_eq!(history.append(30, vec!["movie:1".to_string()]), 1);
+        assert_eq!(history.append(30, vec!["movie:1".to_string()]), 0);
+    }
+
+    #[test]
+    fn retracted_event_is_pending_until_announced_again() {
+        let mut history = ChatHistory::default();
+        history.append(10, vec!["movie:1".to_string()]);
+
+        history.retract(10, "movie:1");
+
+        assert!(!history.contains(10, "movie:1"));
+        assert_eq!(history.append(10, vec!["movie:1".to_string()]), 1);
+        assert!(history.contains(10, "movie:1"));
+    }
+}
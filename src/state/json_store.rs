@@ -0,0 +1,238 @@
+use std::fs;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use super::StateError;
+use crate::github::artifacts::{ArtifactStore, GitHubArtifactsClient, GitHubCredentials};
+
+/// Хранилище JSON-документа: локальный файл плюс артефакт GitHub.
+///
+/// Пустой или отсутствующий документ восстанавливается как `T::default()`, поэтому
+/// новые секции документа добавляются полями с `#[serde(default)]`.
+pub struct JsonArtifactStore<T, C: ArtifactStore = GitHubArtifactsClient> {
+    file_path: PathBuf,
+    artifact_name: String,
+    artifact_store: C,
+    document: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonArtifactStore<T, GitHubArtifactsClient> {
+    /// Создаёт продовую реализацию, работающую с GitHub Artifacts API.
+    pub fn new(
+        file_path: impl Into<PathBuf>,
+        artifact_name: impl Into<String>,
+        credentials: GitHubCredentials,
+    ) -> Result<Self, StateError> {
+        let client = GitHubArtifactsClient::new(credentials)?;
+        Ok(Self::with_store(file_path, artifact_name, client))
+    }
+}
+
+impl<T, C: ArtifactStore> JsonArtifactStore<T, C> {
+    /// Конструктор, позволяющий подменить источник артефактов (например, в тестах).
+    pub fn with_store(
+        file_path: impl Into<PathBuf>,
+        artifact_name: impl Into<String>,
+        artifact_store: C,
+    ) -> Self {
+        Self {
+            file_path: file_path.into(),
+            artifact_name: artifact_name.into(),
+            artifact_store,
+            document: PhantomData,
+        }
+    }
+}
+
+impl<T, C> JsonArtifactStore<T, C>
+where
+    T: Serialize + DeserializeOwned + Default,
+    C: ArtifactStore,
+{
+    /// Восстанавливает документ: артефакт приоритетнее локального файла,
+    /// при отсутствии обоих возвращается значение по умолчанию.
+    pub fn restore(&self) -> Result<T, StateError> {
+        match self.artifact_store.download_artifact(&self.artifact_name) {
+            Ok(Some(artifact_bytes)) => {
+                self.save_raw(&artifact_bytes)?;
+                Self::parse(&artifact_bytes)
+            }
+            Ok(None) => {
+                if self.file_path.exists() {
+                    eprintln!(
+                        "WARN: артефакт '{}' не найден, использую локальный файл",
+                        self.artifact_name
+                    );
+                    Self::parse(&fs::read(&self.file_path)?)
+                } else {
+                    eprintln!(
+                        "WARN: артефакт '{}' не найден, начинаю с пустого документа",
+                        self.artifact_name
+                    );
+                    Ok(T::default())
+                }
+            }
+            Err(err) => {
+                eprintln!(
+                    "WARN: не удалось скачать артефакт '{}' ({err}), использую локальные данные",
+                    self.artifact_name
+                );
+                if self.file_path.exists() {
+                    Self::parse(&fs::read(&self.file_path)?)
+                } else {
+                    Ok(T::default())
+                }
+            }
+        }
+    }
+
+    /// Сохраняет документ в файл и публикует его как артефакт.
+    pub fn persist(&self, document: &T) -> Result<(), StateError> {
+        let raw = serde_json::to_vec_pretty(document)?;
+        self.save_raw(&raw)?;
+        let file_name = self
+            .file_path
+            .file_name()
+            .ok_or_else(|| StateError::MissingFileName(self.file_path.clone()))?
+            .to_string_lossy()
+            .to_string();
+        self.artifact_store
+            .upload_artifact(&self.artifact_name, &file_name, &raw)?;
+        Ok(())
+    }
+
+    fn parse(data: &[u8]) -> Result<T, StateError> {
+        if data.iter().all(u8::is_ascii_whitespace) {
+            return Ok(T::default());
+        }
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    fn save_raw(&self, data: &[u8]) -> Result<(), StateError> {
+        if let Some(parent) = self.file_path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.file_path, data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    use chrono::{DateTime, Utc};
+    use tempfile::tempdir;
+
+    use crate::github::artifacts::ArtifactError;
+    use crate::state::{BotState, BotStateStore, ChatHistory, ChatHistoryStore};
+
+    #[derive(Default)]
+    struct MemoryStore {
+        downloaded: Option<Vec<u8>>,
+        uploads: RefCell<Vec<(String, String, Vec<u8>)>>,
+    }
+
+    impl ArtifactStore for MemoryStore {
+        fn download_artifact(
+            &self,
+            _artifact_name: &str,
+        ) -> Result<Option<Vec<u8>>, ArtifactError> {
+            Ok(self.downloaded.clone())
+        }
+
+        fn upload_artifact(
+            &self,
+            artifact_name: &str,
+            file_name: &str,
+            content: &[u8],
+        ) -> Result<(), ArtifactError> {
+            self.uploads.borrow_mut().push((
+                artifact_name.to_string(),
+                file_name.to_string(),
+                content.to_vec(),
+            ));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn missing_state_restores_default() {
+        let dir = tempdir().expect("временная директория должна создаваться");
+        let store = BotStateStore::with_store(
+            dir.path().join("state.json"),
+            "bot-state",
+            MemoryStore::default(),
+        );
+
+        assert_eq!(
+            store.restore().expect("состояние читается"),
+            BotState::default()
+        );
+    }
+
+    #[test]
+    fn persisted_state_roundtrips_through_artifact() {
+        let dir = tempdir().expect("временная директория должна создаваться");
+        let file_path = dir.path().join("state.json");
+        let store = BotStateStore::with_store(&file_path, "bot-state", MemoryStore::default());
+
+        let mut state = BotState::default();
+        state.change_feed.synced_until = Some(
+            DateTime::parse_from_rfc3339("2026-03-01T09:00:00Z")
+                .expect("валидная дата")
+                .with_timezone(&Utc),
+        );
+        store.persist(&state).expect("состояние сохраняется");
+
+        let uploaded = store.artifact_store.uploads.borrow()[0].2.clone();
+        let restored = BotStateStore::with_store(
+            &file_path,
+            "bot-state",
+            MemoryStore {
+                downloaded: Some(uploaded),
+                uploads: RefCell::new(Vec::new()),
+            },
+        )
+        .restore()
+        .expect("состояние читается");
+        assert_eq!(restored, state);
+    }
+
+    #[test]
+    fn unknown_sections_are_filled_with_defaults() {
+        let dir = tempdir().expect("временная директория должна создаваться");
+        let store = BotStateStore::with_store(
+            dir.path().join("state.json"),
+            "bot-state",
+            MemoryStore {
+                downloaded: Some(b"{}".to_vec()),
+                uploads: RefCell::new(Vec::new()),
+            },
+        );
+
+        assert_eq!(
+            store.restore().expect("состояние читается"),
+            BotState::default()
+        );
+    }
+
+    #[test]
+    fn chat_history_falls_back_to_local_file() {
+        let dir = tempdir().expect("временная директория должна создаваться");
+        let file_path = dir.path().join("chat_history.json");
+        let store =
+            ChatHistoryStore::with_store(&file_path, "chat-history", MemoryStore::default());
+        let mut history = ChatHistory::default();
+        history.append(10, vec!["movie:1".to_string()]);
+        store.persist(&history).expect("история сохраняется");
+
+        assert_eq!(store.restore().expect("история читается"), history);
+    }
+}
@@ -1,5 +1,10 @@
 #![allow(dead_code)]
 
+mod bot_state;
+mod chat_history;
+mod json_store;
+mod report_archive;
+
 use std::collections::BTreeSet;
 use std::fs;
 use std::io;
@@ -12,6 +17,14 @@ use crate::github::artifacts::{
     ArtifactError, ArtifactStore, GitHubArtifactsClient, GitHubCredentials,
 };
 
+pub use bot_state::{
+    Announcement, BotState, BotStateStore, ChangeFeedCursor, ChatSubscription, DigestState,
+    OutboxEntry, OutboxStatus, PossibleDuplicateRecord, RecheckCursor, RunCursor, UpdatesCursor,
+};
+pub use chat_history::{ChatHistory, ChatHistoryStore};
+pub use json_store::JsonArtifactStore;
+pub use report_archive::ReportArchive;
+
 /// TMDB идентификатор фильма.
 pub type MovieId = u64;
 
@@ -213,6 +226,11 @@ impl<C: ArtifactStore> SentEventHistory<C> {
         self.keys.contains(key)
     }
 
+    /// Возвращает итератор по всем известным ключам в отсортированном порядке.
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.keys.iter()
+    }
+
     /// Добавляет список новых ключей, возвращая количество реально вставленных значений.
     pub fn append(&mut self, new_keys: &[String]) -> usize {
         let mut inserted = 0;
@@ -336,6 +354,8 @@ pub enum StateError {
     },
     #[error("путь {0:?} не содержит имени файла")]
     MissingFileName(PathBuf),
+    #[error("некорректный JSON состояния: {0}")]
+    Json(#[from] serde_json::Error),
 }
 
 impl From<StateError> for io::Error {
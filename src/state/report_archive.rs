@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::StateError;
+use crate::github::artifacts::{ArtifactStore, GitHubArtifactsClient, GitHubCredentials};
+
+/// Архив отчётов прогона: файлы пишутся в локальный каталог и публикуются как артефакты.
+///
+/// В отличие от историй отчёты не восстанавливаются — каждый прогон перезаписывает их.
+pub struct ReportArchive<C: ArtifactStore = GitHubArtifactsClient> {
+    dir: PathBuf,
+    artifact_store: C,
+}
+
+impl ReportArchive<GitHubArtifactsClient> {
+    /// Создаёт продовую реализацию, работающую с GitHub Artifacts API.
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        credentials: GitHubCredentials,
+    ) -> Result<Self, StateError> {
+        let client = GitHubArtifactsClient::new(credentials)?;
+        Ok(Self::with_store(dir, client))
+    }
+}
+
+impl<C: ArtifactStore> ReportArchive<C> {
+    /// Конструктор, позволяющий подменить источник артефактов (например, в тестах).
+    pub fn with_store(dir: impl Into<PathBuf>, artifact_store: C) -> Self {
+        Self {
+            dir: dir.into(),
+            artifact_store,
+        }
+    }
+
+    /// Сохраняет файл отчёта в каталог и загружает его артефактом `artifact_name`.
+    pub fn publish(
+        &self,
+        artifact_name: &str,
+        file_name: &str,
+        content: &[u8],
+    ) -> Result<(), StateError> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join(file_name), content)?;
+        self.artifact_store
+            .upload_artifact(artifact_name, file_name, content)?;
+        Ok(())
+    }
+}
@@ -1,24 +1,88 @@
-use std::{collections::HashSet, env, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use thiserror::Error;
 use tokio::time::sleep;
 use tracing::warn;
 
+use crate::config::{LinkPreview, MessageOptions, ParseMode};
+
+mod rate_limit;
+mod updates;
+
+pub use rate_limit::{RateLimiter, RateLimits};
+use updates::{ApiResponse, ChatMember};
+pub use updates::{Chat, IncomingMessage, Update, User};
+
 const TELEGRAM_BASE_URL: &str = "https://api.telegram.org";
 const DEFAULT_MAX_RETRIES: usize = 3;
 const DEFAULT_RETRY_DELAYS: &[u64] = &[5, 15, 30];
+/// Лимит длины текста `sendMessage` в символах.
+const MESSAGE_LIMIT: usize = 4096;
+/// Таймаут одного запроса к Bot API (`sendMessage`, `editMessageText`, ...).
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Запас сверх `timeout` long polling, за который Telegram успевает ответить.
+const LONG_POLL_MARGIN: Duration = Duration::from_secs(10);
 
 #[derive(Clone)]
 pub struct TelegramDispatcher {
     transport: Arc<dyn TelegramTransport>,
-    chat_ids: HashSet<i64>,
+    chat_ids: Arc<RwLock<HashSet<i64>>>,
     token: String,
     api_host: String,
     retry_delays: Vec<Duration>,
     max_retries: usize,
+    rate_limiter: Arc<RateLimiter>,
+    possible_duplicates: Arc<Mutex<Vec<PossibleDuplicate>>>,
+    /// Чаты, куда бот не может писать (нет прав, чат не найден), с причиной.
+    /// Отправка в них пропускается до следующего [`Self::allow_chat`].
+    flagged_chats: Arc<RwLock<HashMap<i64, String>>>,
+}
+
+/// Сообщение, доставленное повтором после попытки, которая тоже могла дойти
+/// до Telegram: в чате оно может оказаться дважды.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PossibleDuplicate {
+    pub chat_id: i64,
+    pub message_id: Option<i64>,
+}
+
+/// Чем грозит повтор запроса после ошибки транспорта.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportFailure {
+    /// Запрос не ушёл: соединение не установлено (DNS, TLS, прокси) или
+    /// оборвалось до ответа без таймаута. Повтор безопасен.
+    NotSent,
+    /// Таймаут или сбой при чтении ответа: запрос уже записан, и Telegram
+    /// мог принять сообщение.
+    MaybeDelivered,
+    /// Ошибка сборки запроса, редиректы и т.п. — повтор не поможет.
+    Fatal,
+}
+
+impl TransportFailure {
+    pub fn classify(err: &reqwest::Error) -> Self {
+        // Таймаут подключения помечен и как `is_connect`, и как `is_timeout`:
+        // до отправки запроса он не дошёл.
+        if err.is_connect() {
+            Self::NotSent
+        } else if err.is_timeout() || err.is_body() || err.is_decode() {
+            Self::MaybeDelivered
+        } else if err.is_request() {
+            Self::NotSent
+        } else {
+            Self::Fatal
+        }
+    }
 }
 
 impl TelegramDispatcher {
@@ -30,41 +94,441 @@ impl TelegramDispatcher {
         TelegramDispatcherBuilder::new(token.into(), chat_ids)
     }
 
-    pub async fn send_batch<S, I>(&self, chat_id: i64, messages: I) -> Result<(), TelegramError>
+    /// Отправляет сообщения по порядку и возвращает их `message_id` (`None`, если
+    /// сообщение пустое и пропущено или ответ Telegram без `message_id`).
+    pub async fn send_batch<S, I>(
+        &self,
+        chat_id: i64,
+        messages: I,
+    ) -> Result<Vec<Option<i64>>, TelegramError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.send_topic_batch(chat_id, None, messages, &MessageOptions::default())
+            .await
+    }
+
+    /// Как [`Self::send_batch`], но с `disable_notification`: сообщения приходят без звука.
+    pub async fn send_silent_batch<S, I>(
+        &self,
+        chat_id: i64,
+        messages: I,
+    ) -> Result<Vec<Option<i64>>, TelegramError>
     where
         I: IntoIterator<Item = S>,
         S: Into<String>,
     {
-        if !self.chat_ids.contains(&chat_id) {
-            return Err(TelegramError::UnknownChat(chat_id));
+        let options = MessageOptions {
+            disable_notification: true,
+            ..MessageOptions::default()
+        };
+        self.send_topic_batch(chat_id, None, messages, &options)
+            .await
+    }
+
+    /// Отправляет сообщения в тему форума (`message_thread_id`; `None` — в
+    /// общий поток чата) с параметрами `options`.
+    pub async fn send_topic_batch<S, I>(
+        &self,
+        chat_id: i64,
+        thread_id: Option<i64>,
+        messages: I,
+        options: &MessageOptions,
+    ) -> Result<Vec<Option<i64>>, TelegramError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.send_topic_batch_partial(chat_id, thread_id, messages, options)
+            .await
+            .map_err(|partial| partial.error)
+    }
+
+    /// Как [`Self::send_topic_batch`], но при ошибке возвращает и `message_id`
+    /// сообщений, ушедших до неё, чтобы рассылку можно было продолжить с первого
+    /// неотправленного.
+    pub async fn send_topic_batch_partial<S, I>(
+        &self,
+        chat_id: i64,
+        thread_id: Option<i64>,
+        messages: I,
+        options: &MessageOptions,
+    ) -> Result<Vec<Option<i64>>, PartialSend>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut message_ids = Vec::new();
+        if !self.is_allowed(chat_id) {
+            return Err(PartialSend {
+                sent: message_ids,
+                error: TelegramError::UnknownChat(chat_id),
+            });
+        }
+        if let Some(reason) = self.flag_reason(chat_id) {
+            return Err(PartialSend {
+                sent: message_ids,
+                error: TelegramError::ChatSkipped { chat_id, reason },
+            });
         }
 
         for message in messages {
             let text = message.into();
             let text = text.trim().to_owned();
             if text.is_empty() {
+                message_ids.push(None);
                 continue;
             }
-            self.send_single(chat_id, text).await?;
+            match self
+                .send_remediated(chat_id, thread_id, text, options)
+                .await
+            {
+                Ok(message_id) => message_ids.push(message_id),
+                Err(error) => {
+                    return Err(PartialSend {
+                        sent: message_ids,
+                        error,
+                    });
+                }
+            }
         }
 
+        Ok(message_ids)
+    }
+
+    /// Отправляет сообщение, исправляя то, что Telegram отклонил: слишком длинный
+    /// текст делится пополам по строкам, текст с неразобранной разметкой уходит
+    /// без `parse_mode`, а чат, куда бот не может писать, помечается.
+    ///
+    /// У разделённого сообщения нет единого `message_id`, поэтому возвращается
+    /// `None`: такой анонс потом не правится. Рассылка заранее укладывает каждое
+    /// сообщение outbox в лимит, чтобы оно уходило одним запросом: части, ушедшие
+    /// до сбоя, здесь не подтверждаются и при досылке повторились бы.
+    async fn send_remediated(
+        &self,
+        chat_id: i64,
+        thread_id: Option<i64>,
+        text: String,
+        options: &MessageOptions,
+    ) -> Result<Option<i64>, TelegramError> {
+        let mut options = options.clone();
+        let mut pending = vec![text];
+        let mut message_id = None;
+        let mut split = false;
+
+        while let Some(text) = pending.pop() {
+            let request = SendMessageRequest::new(chat_id, thread_id, text.clone(), &options);
+            match self.send_single(request).await {
+                Ok(sent) => message_id = message_id.or(sent),
+                Err(TelegramError::MessageTooLong { .. }) if text.chars().count() > 1 => {
+                    warn!(
+                        target: "telegram_dispatcher",
+                        chat_id,
+                        length = text.chars().count(),
+                        "Сообщение длиннее лимита Telegram, делю его на части"
+                    );
+                    let (head, tail) = split_in_half(&text);
+                    pending.push(tail);
+                    pending.push(head);
+                    split = true;
+                }
+                Err(TelegramError::CantParseEntities { description, .. })
+                    if options.parse_mode.is_some() =>
+                {
+                    warn!(
+                        target: "telegram_dispatcher",
+                        chat_id,
+                        description = %description,
+                        "Telegram не разобрал разметку, отправляю простым текстом"
+                    );
+                    options.parse_mode = None;
+                    pending.push(text);
+                }
+                Err(err) => {
+                    if err.skips_chat() {
+                        self.flag_chat(chat_id, err.to_string());
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(if split { None } else { message_id })
+    }
+
+    /// Заменяет текст ранее отправленного сообщения (`editMessageText`).
+    ///
+    /// Ответ «message is not modified» считается успехом: текст уже такой. Подписи
+    /// (`editMessageCaption`) не нужны — бот отправляет только текстовые сообщения.
+    /// Превью ссылок и разметка передаются заново: без них Telegram включит превью
+    /// по умолчанию и покажет текст без разметки. Текст, чью разметку Telegram
+    /// не разобрал, переотправляется простым текстом.
+    pub async fn edit_message_text(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        text: impl Into<String>,
+        options: &MessageOptions,
+    ) -> Result<(), TelegramError> {
+        let mut payload = json!({
+            "chat_id": chat_id,
+            "message_id": message_id,
+            "text": text.into(),
+            "link_preview_options": LinkPreviewOptions::from(&options.link_preview),
+        });
+        if let Some(parse_mode) = options.parse_mode {
+            payload["parse_mode"] = json!(parse_mode);
+        }
+        loop {
+            self.rate_limiter.acquire(chat_id).await;
+            match self
+                .call_method::<serde_json::Value>("editMessageText", &payload)
+                .await
+            {
+                Ok(_) => return Ok(()),
+                Err(TelegramError::Api { description, .. })
+                    if description.contains("message is not modified") =>
+                {
+                    return Ok(());
+                }
+                Err(TelegramError::Api { description, .. })
+                    if payload.get("parse_mode").is_some()
+                        && description.to_lowercase().contains("can't parse entities") =>
+                {
+                    warn!(
+                        target: "telegram_dispatcher",
+                        chat_id,
+                        message_id,
+                        description = %description,
+                        "Telegram не разобрал разметку правки, отправляю простым текстом"
+                    );
+                    if let Some(payload) = payload.as_object_mut() {
+                        payload.remove("parse_mode");
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Удаляет ранее отправленное сообщение (`deleteMessage`).
+    pub async fn delete_message(&self, chat_id: i64, message_id: i64) -> Result<(), TelegramError> {
+        let payload = json!({ "chat_id": chat_id, "message_id": message_id });
+        self.rate_limiter.acquire(chat_id).await;
+        self.call_method::<bool>("deleteMessage", &payload).await?;
+        Ok(())
+    }
+
+    /// Забирает накопленные с прошлого вызова сообщения, которые могли задвоиться.
+    pub fn take_possible_duplicates(&self) -> Vec<PossibleDuplicate> {
+        std::mem::take(
+            &mut *self
+                .possible_duplicates
+                .lock()
+                .expect("список возможных дублей доступен"),
+        )
+    }
+
+    /// Разрешает рассылку в чат, подписавшийся через команды бота, и снимает с
+    /// него пометку о недоступности.
+    ///
+    /// Список разрешённых чатов общий для всех клонов диспетчера.
+    pub fn allow_chat(&self, chat_id: i64) {
+        self.chat_ids
+            .write()
+            .expect("список чатов доступен")
+            .insert(chat_id);
+        self.flagged_chats
+            .write()
+            .expect("список помеченных чатов доступен")
+            .remove(&chat_id);
+    }
+
+    /// Чаты, помеченные как недоступные для бота, с причиной.
+    pub fn flagged_chats(&self) -> HashMap<i64, String> {
+        self.flagged_chats
+            .read()
+            .expect("список помеченных чатов доступен")
+            .clone()
+    }
+
+    fn flag_chat(&self, chat_id: i64, reason: String) {
+        warn!(
+            target: "telegram_dispatcher",
+            chat_id,
+            reason = %reason,
+            "Бот не может писать в чат, дальнейшие сообщения в него пропускаются"
+        );
+        self.flagged_chats
+            .write()
+            .expect("список помеченных чатов доступен")
+            .insert(chat_id, reason);
+    }
+
+    fn flag_reason(&self, chat_id: i64) -> Option<String> {
+        self.flagged_chats
+            .read()
+            .expect("список помеченных чатов доступен")
+            .get(&chat_id)
+            .cloned()
+    }
+
+    fn is_allowed(&self, chat_id: i64) -> bool {
+        self.chat_ids
+            .read()
+            .expect("список чатов доступен")
+            .contains(&chat_id)
+    }
+
+    /// Ответ на команду: в отличие от [`Self::send_batch`] не требует, чтобы чат
+    /// был в списке рассылки — писать боту может любой чат.
+    pub async fn send_reply(
+        &self,
+        chat_id: i64,
+        text: impl Into<String>,
+    ) -> Result<(), TelegramError> {
+        let request =
+            SendMessageRequest::new(chat_id, None, text.into(), &MessageOptions::default());
+        self.send_single(request).await?;
         Ok(())
     }
 
+    /// Long polling `getUpdates`: ждёт новые сообщения до `timeout_secs` секунд.
+    pub async fn get_updates(
+        &self,
+        offset: Option<i64>,
+        timeout_secs: u64,
+    ) -> Result<Vec<Update>, TelegramError> {
+        let mut payload = json!({
+            "timeout": timeout_secs,
+            "allowed_updates": ["message"],
+        });
+        if let Some(offset) = offset {
+            payload["offset"] = json!(offset);
+        }
+        self.call_method("getUpdates", &payload).await
+    }
+
+    /// Регистрирует вебхук: Telegram начнёт присылать обновления POST-запросами на `url`
+    /// с заголовком `X-Telegram-Bot-Api-Secret-Token`, равным `secret_token`.
+    ///
+    /// Одно соединение за раз: обновления приходят по порядку, и защита от дублей
+    /// по смещению не отбрасывает те, что обогнали соседей.
+    pub async fn set_webhook(&self, url: &str, secret_token: &str) -> Result<(), TelegramError> {
+        let payload = json!({
+            "url": url,
+            "secret_token": secret_token,
+            "allowed_updates": ["message"],
+            "max_connections": 1,
+        });
+        self.call_method::<bool>("setWebhook", &payload).await?;
+        Ok(())
+    }
+
+    /// Снимает вебхук, после чего снова доступен `getUpdates`.
+    pub async fn delete_webhook(&self) -> Result<(), TelegramError> {
+        self.call_method::<bool>("deleteWebhook", &json!({}))
+            .await?;
+        Ok(())
+    }
+
+    /// Статус участника чата (`creator`, `administrator`, `member`, ...).
+    pub async fn chat_member_status(
+        &self,
+        chat_id: i64,
+        user_id: i64,
+    ) -> Result<String, TelegramError> {
+        let payload = json!({ "chat_id": chat_id, "user_id": user_id });
+        let member: ChatMember = self.call_method("getChatMember", &payload).await?;
+        Ok(member.status)
+    }
+
+    /// Имя бота из `getMe`.
+    pub async fn bot_username(&self) -> Result<String, TelegramError> {
+        let me: User = self.call_method("getMe", &json!({})).await?;
+        Ok(me.username.unwrap_or_default())
+    }
+
+    async fn call_method<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        payload: &serde_json::Value,
+    ) -> Result<T, TelegramError> {
+        let url = self.endpoint(method);
+        let response = self.transport.post_value(&url, payload).await?;
+        let status = response.status;
+        if !status.is_success() {
+            return Err(TelegramError::api(status, &response.body));
+        }
+
+        let envelope: ApiResponse<T> = serde_json::from_str(&response.body)?;
+        match envelope.result {
+            Some(result) if envelope.ok => Ok(result),
+            _ => Err(TelegramError::api(status, &response.body)),
+        }
+    }
+
     fn endpoint(&self, method: &str) -> String {
         format!("{}/bot{}/{}", self.api_host, self.token, method)
     }
 
-    async fn send_single(&self, chat_id: i64, text: String) -> Result<(), TelegramError> {
-        let payload = SendMessageRequest { chat_id, text };
+    async fn send_single(&self, payload: SendMessageRequest) -> Result<Option<i64>, TelegramError> {
+        let chat_id = payload.chat_id;
+        let thread_id = payload.message_thread_id;
         let url = self.endpoint("sendMessage");
         let mut retries = 0usize;
+        let mut maybe_delivered = false;
 
         loop {
-            let response = self.transport.post_json(&url, &payload).await?;
+            self.rate_limiter.acquire(chat_id).await;
+            let response = match self.transport.post_json(&url, &payload).await {
+                Ok(response) => response,
+                Err(err) => {
+                    let failure = TransportFailure::classify(&err);
+                    maybe_delivered |= failure == TransportFailure::MaybeDelivered;
+                    if failure != TransportFailure::Fatal && retries < self.max_retries {
+                        warn!(
+                            target: "telegram_dispatcher",
+                            chat_id,
+                            error = %err,
+                            failure = ?failure,
+                            "Ошибка соединения с Telegram API, повторяю запрос"
+                        );
+                        let delay = self.retry_delay_for(retries);
+                        retries += 1;
+                        sleep(delay).await;
+                        continue;
+                    }
+                    return Err(if maybe_delivered {
+                        TelegramError::DeliveryUnknown {
+                            chat_id,
+                            source: err,
+                        }
+                    } else {
+                        TelegramError::Transport(err)
+                    });
+                }
+            };
 
             if response.status.is_success() {
-                return Ok(());
+                let message_id = parse_message_id(&response.body);
+                if maybe_delivered {
+                    warn!(
+                        target: "telegram_dispatcher",
+                        chat_id,
+                        message_id,
+                        "Сообщение доставлено повтором после таймаута, возможен дубль"
+                    );
+                    self.possible_duplicates
+                        .lock()
+                        .expect("список возможных дублей доступен")
+                        .push(PossibleDuplicate {
+                            chat_id,
+                            message_id,
+                        });
+                }
+                return Ok(message_id);
             }
 
             let status = response.status;
@@ -92,7 +556,9 @@ impl TelegramDispatcher {
                 continue;
             }
 
-            return Err(TelegramError::Api { status, body });
+            return Err(TelegramError::from_response(
+                chat_id, thread_id, status, &body,
+            ));
         }
     }
 
@@ -114,6 +580,8 @@ pub struct TelegramDispatcherBuilder {
     transport: Option<Arc<dyn TelegramTransport>>,
     retry_delays: Vec<Duration>,
     max_retries: usize,
+    rate_limits: RateLimits,
+    request_timeout: Duration,
 }
 
 #[allow(dead_code)]
@@ -131,6 +599,8 @@ impl TelegramDispatcherBuilder {
                 .map(Duration::from_secs)
                 .collect(),
             max_retries: DEFAULT_MAX_RETRIES,
+            rate_limits: RateLimits::default(),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
         }
     }
 
@@ -163,30 +633,179 @@ impl TelegramDispatcherBuilder {
         self
     }
 
+    /// Лимиты отправки; по умолчанию — документированные лимиты Telegram.
+    /// Нулевой лимит сообщений снимает соответствующее ограничение.
+    pub fn rate_limits(mut self, rate_limits: RateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Таймаут запроса к Bot API; `getUpdates` ждёт свой `timeout` плюс запас.
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
     pub fn build(self) -> TelegramDispatcher {
         let sanitized_base = self.base_url.trim_end_matches('/').to_owned();
         let transport = self.transport.unwrap_or_else(|| {
-            Arc::new(ReqwestTransport::new(self.client)) as Arc<dyn TelegramTransport>
+            Arc::new(ReqwestTransport::new(self.client, self.request_timeout))
+                as Arc<dyn TelegramTransport>
         });
         TelegramDispatcher {
             transport,
-            chat_ids: self.chat_ids.into_iter().collect(),
+            chat_ids: Arc::new(RwLock::new(self.chat_ids.into_iter().collect())),
             token: self.token,
             api_host: sanitized_base,
             retry_delays: self.retry_delays,
             max_retries: self.max_retries,
+            rate_limiter: Arc::new(RateLimiter::new(self.rate_limits)),
+            possible_duplicates: Arc::new(Mutex::new(Vec::new())),
+            flagged_chats: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
 
+/// Сбой пакетной отправки: `message_id` сообщений, ушедших до ошибки, и сама ошибка.
+#[derive(Debug, Error)]
+#[error("{error}")]
+pub struct PartialSend {
+    pub sent: Vec<Option<i64>>,
+    pub error: TelegramError,
+}
+
 #[derive(Debug, Error)]
 pub enum TelegramError {
     #[error("неизвестный чат {0}")]
     UnknownChat(i64),
     #[error("ошибка HTTP клиента: {0}")]
     Transport(#[from] reqwest::Error),
-    #[error("ошибка Telegram API: {status} — {body}")]
-    Api { status: StatusCode, body: String },
+    /// Повторы исчерпаны, но хотя бы одна попытка могла дойти до Telegram.
+    #[error("не удалось подтвердить доставку в чат {chat_id}, сообщение могло дойти: {source}")]
+    DeliveryUnknown {
+        chat_id: i64,
+        source: reqwest::Error,
+    },
+    /// Ошибка Bot API без отдельного варианта: `error_code` и `description`
+    /// из ответа (или его тело, если это не JSON).
+    #[error("ошибка Telegram API: {status} — {description}")]
+    Api {
+        status: StatusCode,
+        error_code: Option<i64>,
+        description: String,
+    },
+    #[error("некорректный ответ Telegram API: {0}")]
+    Decode(#[from] serde_json::Error),
+    /// Группа стала супергруппой: писать нужно в `migrate_to_chat_id`.
+    #[error("чат {chat_id} перенесён в {migrate_to_chat_id}")]
+    ChatMigrated {
+        chat_id: i64,
+        migrate_to_chat_id: i64,
+    },
+    /// Бот удалён из чата или заблокирован пользователем (403).
+    #[error("бот не может писать в чат {chat_id}: {description}")]
+    Forbidden { chat_id: i64, description: String },
+    #[error("чат {chat_id} не найден")]
+    ChatNotFound { chat_id: i64 },
+    /// Текст длиннее лимита Telegram.
+    #[error("сообщение в чат {chat_id} слишком длинное")]
+    MessageTooLong { chat_id: i64 },
+    /// Telegram не разобрал разметку `parse_mode`.
+    #[error("не удалось разобрать разметку сообщения в чат {chat_id}: {description}")]
+    CantParseEntities { chat_id: i64, description: String },
+    /// Бот в чате, но без права писать (например, не администратор канала).
+    #[error("у бота нет прав писать в чат {chat_id}: {description}")]
+    NotEnoughRights { chat_id: i64, description: String },
+    /// Тема форума закрыта или удалена.
+    #[error("тема {thread_id:?} чата {chat_id} закрыта: {description}")]
+    TopicClosed {
+        chat_id: i64,
+        thread_id: Option<i64>,
+        description: String,
+    },
+    /// Чат ранее помечен недоступным, отправка пропущена без запроса к API.
+    #[error("чат {chat_id} пропущен: {reason}")]
+    ChatSkipped { chat_id: i64, reason: String },
+}
+
+impl TelegramError {
+    /// Разбирает неуспешный ответ `sendMessage` в типизированную ошибку.
+    fn from_response(chat_id: i64, thread_id: Option<i64>, status: StatusCode, body: &str) -> Self {
+        let error = parse_error_response(body);
+        if let Some(migrate_to_chat_id) = error
+            .as_ref()
+            .and_then(|error| error.parameters.as_ref()?.migrate_to_chat_id)
+        {
+            return Self::ChatMigrated {
+                chat_id,
+                migrate_to_chat_id,
+            };
+        }
+
+        let error_code = error.as_ref().and_then(|error| error.error_code);
+        let description = error
+            .and_then(|error| error.description)
+            .unwrap_or_else(|| body.to_owned());
+        let lowered = description.to_lowercase();
+        if lowered.contains("message is too long") {
+            Self::MessageTooLong { chat_id }
+        } else if lowered.contains("can't parse entities") {
+            Self::CantParseEntities {
+                chat_id,
+                description,
+            }
+        } else if lowered.contains("chat not found") {
+            Self::ChatNotFound { chat_id }
+        } else if lowered.contains("topic_closed")
+            || lowered.contains("topic closed")
+            || lowered.contains("message thread not found")
+        {
+            Self::TopicClosed {
+                chat_id,
+                thread_id,
+                description,
+            }
+        } else if lowered.contains("not enough rights")
+            || lowered.contains("have no rights")
+            || lowered.contains("chat_write_forbidden")
+            || lowered.contains("need administrator rights")
+        {
+            Self::NotEnoughRights {
+                chat_id,
+                description,
+            }
+        } else if status == StatusCode::FORBIDDEN || error_code == Some(403) {
+            Self::Forbidden {
+                chat_id,
+                description,
+            }
+        } else {
+            Self::Api {
+                status,
+                error_code,
+                description,
+            }
+        }
+    }
+
+    fn api(status: StatusCode, body: &str) -> Self {
+        let error = parse_error_response(body);
+        Self::Api {
+            status,
+            error_code: error.as_ref().and_then(|error| error.error_code),
+            description: error
+                .and_then(|error| error.description)
+                .unwrap_or_else(|| body.to_owned()),
+        }
+    }
+
+    /// Бот не может писать в чат целиком: дальнейшие сообщения в него бессмысленны.
+    pub fn skips_chat(&self) -> bool {
+        matches!(
+            self,
+            Self::Forbidden { .. } | Self::ChatNotFound { .. } | Self::NotEnoughRights { .. }
+        )
+    }
 }
 
 #[async_trait]
@@ -196,6 +815,13 @@ pub trait TelegramTransport: Send + Sync {
         url: &str,
         payload: &SendMessageRequest,
     ) -> Result<TelegramTransportResponse, reqwest::Error>;
+
+    /// Вызов произвольного метода Bot API (`getUpdates`, `getChatMember`, ...).
+    async fn post_value(
+        &self,
+        url: &str,
+        payload: &serde_json::Value,
+    ) -> Result<TelegramTransportResponse, reqwest::Error>;
 }
 
 #[derive(Debug, Clone)]
@@ -206,11 +832,15 @@ pub struct TelegramTransportResponse {
 
 struct ReqwestTransport {
     client: Client,
+    request_timeout: Duration,
 }
 
 impl ReqwestTransport {
-    fn new(client: Client) -> Self {
-        Self { client }
+    fn new(client: Client, request_timeout: Duration) -> Self {
+        Self {
+            client,
+            request_timeout,
+        }
     }
 }
 
@@ -221,12 +851,44 @@ impl TelegramTransport for ReqwestTransport {
         url: &str,
         payload: &SendMessageRequest,
     ) -> Result<TelegramTransportResponse, reqwest::Error> {
-        let response = self.client.post(url).json(payload).send().await?;
+        self.post(url, payload, self.request_timeout).await
+    }
+
+    async fn post_value(
+        &self,
+        url: &str,
+        payload: &serde_json::Value,
+    ) -> Result<TelegramTransportResponse, reqwest::Error> {
+        // Long polling держит запрос открытым до `timeout` секунд.
+        let timeout = payload
+            .get("timeout")
+            .and_then(serde_json::Value::as_u64)
+            .map_or(self.request_timeout, |secs| {
+                self.request_timeout
+                    .max(Duration::from_secs(secs) + LONG_POLL_MARGIN)
+            });
+        self.post(url, payload, timeout).await
+    }
+}
+
+impl ReqwestTransport {
+    async fn post<P: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        payload: &P,
+        timeout: Duration,
+    ) -> Result<TelegramTransportResponse, reqwest::Error> {
+        let response = self
+            .client
+            .post(url)
+            .timeout(timeout)
+            .json(payload)
+            .send()
+            .await?;
         let status = response.status();
-        let body = response.text().await.unwrap_or_else(|err| {
-            warn!(?err, "Не удалось прочитать тело ответа Telegram API");
-            String::new()
-        });
+        // Ошибку чтения тела не глотаем: запрос уже принят, и от неё зависит,
+        // считать ли повтор возможным дублем.
+        let body = response.text().await?;
 
         Ok(TelegramTransportResponse { status, body })
     }
@@ -277,6 +939,10 @@ pub fn dispatcher_from_env(chat_ids: Vec<i64>) -> Result<TelegramDispatcher, Con
 
 #[derive(Debug, Deserialize)]
 struct TelegramErrorResponse {
+    #[serde(default)]
+    error_code: Option<i64>,
+    #[serde(default)]
+    description: Option<String>,
     parameters: Option<TelegramErrorParameters>,
 }
 
@@ -284,17 +950,119 @@ struct TelegramErrorResponse {
 struct TelegramErrorParameters {
     #[serde(rename = "retry_after")]
     retry_after: Option<u64>,
+    #[serde(default)]
+    migrate_to_chat_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SendMessageRequest {
     chat_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_thread_id: Option<i64>,
     text: String,
+    link_preview_options: LinkPreviewOptions,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    disable_notification: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    protect_content: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_parameters: Option<ReplyParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parse_mode: Option<ParseMode>,
+}
+
+impl SendMessageRequest {
+    pub fn new(
+        chat_id: i64,
+        thread_id: Option<i64>,
+        text: String,
+        options: &MessageOptions,
+    ) -> Self {
+        Self {
+            chat_id,
+            message_thread_id: thread_id,
+            text,
+            link_preview_options: LinkPreviewOptions::from(&options.link_preview),
+            disable_notification: options.disable_notification,
+            protect_content: options.protect_content,
+            reply_parameters: options
+                .reply_to_message_id
+                .map(|message_id| ReplyParameters {
+                    message_id,
+                    allow_sending_without_reply: true,
+                }),
+            parse_mode: options.parse_mode,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct LinkPreviewOptions {
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    is_disabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    prefer_large_media: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    show_above_text: bool,
+}
+
+impl From<&LinkPreview> for LinkPreviewOptions {
+    fn from(preview: &LinkPreview) -> Self {
+        Self {
+            is_disabled: preview.is_disabled,
+            url: preview.url.clone(),
+            prefer_large_media: preview.prefer_large_media,
+            show_above_text: preview.show_above_text,
+        }
+    }
+}
+
+/// Ответ на сообщение; если его удалили, сообщение уходит без ответа.
+#[derive(Debug, Serialize)]
+struct ReplyParameters {
+    message_id: i64,
+    allow_sending_without_reply: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SentMessage {
+    message_id: i64,
+}
+
+/// `message_id` из ответа `sendMessage`; ответ без него не считается ошибкой.
+fn parse_message_id(body: &str) -> Option<i64> {
+    serde_json::from_str::<ApiResponse<SentMessage>>(body)
+        .ok()?
+        .result
+        .map(|message| message.message_id)
+}
+
+/// Делит текст пополам по последнему переводу строки перед серединой (без него —
+/// по символам). Середина не дальше [`MESSAGE_LIMIT`], так что начало всегда
+/// укладывается в лимит.
+fn split_in_half(text: &str) -> (String, String) {
+    let middle = text
+        .char_indices()
+        .nth(text.chars().count().min(2 * MESSAGE_LIMIT) / 2)
+        .map_or(text.len(), |(index, _)| index);
+    let at = match text[..middle].rfind('\n') {
+        Some(newline) if newline > 0 => newline,
+        _ => middle,
+    };
+    (
+        text[..at].trim_end().to_owned(),
+        text[at..].trim_start().to_owned(),
+    )
+}
+
+fn parse_error_response(body: &str) -> Option<TelegramErrorResponse> {
+    serde_json::from_str(body).ok()
 }
 
 fn parse_retry_after(body: &str) -> Option<Duration> {
-    serde_json::from_str::<TelegramErrorResponse>(body)
-        .ok()
+    parse_error_response(body)
         .and_then(|resp| resp.parameters?.retry_after)
         .map(Duration::from_secs)
 }
@@ -0,0 +1,230 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::Duration,
+};
+
+use tokio::time::{Instant, sleep_until};
+
+const SECOND: Duration = Duration::from_secs(1);
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// Лимиты отправки Telegram Bot API. Группой считается любой чат с
+/// отрицательным id (группы, супергруппы и каналы). Нулевой лимит сообщений
+/// снимает соответствующее ограничение, как и `usize::MAX`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimits {
+    /// Сообщений в секунду на всех чатах вместе.
+    pub global_per_second: usize,
+    /// Минимальный интервал между сообщениями в один чат.
+    pub per_chat_interval: Duration,
+    /// Сообщений в минуту в одну группу.
+    pub group_per_minute: usize,
+}
+
+impl RateLimits {
+    /// Без ограничений: отправка сразу, как раньше.
+    pub fn unlimited() -> Self {
+        Self {
+            global_per_second: usize::MAX,
+            per_chat_interval: Duration::ZERO,
+            group_per_minute: usize::MAX,
+        }
+    }
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            global_per_second: 30,
+            per_chat_interval: SECOND,
+            group_per_minute: 20,
+        }
+    }
+}
+
+/// Ограничитель отправки: перед каждым сообщением ждёт, пока оно уложится во
+/// все лимиты. Общий для всех клонов диспетчера, поэтому учитывает и
+/// параллельные `send_batch`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: RateLimits,
+    sent: Mutex<SentLog>,
+}
+
+#[derive(Debug, Default)]
+struct SentLog {
+    /// Отправки за последнюю секунду.
+    global: VecDeque<Instant>,
+    /// Отправки в чат за последнюю минуту.
+    chats: HashMap<i64, VecDeque<Instant>>,
+    /// Когда из журнала последний раз убирались чаты без свежих отправок.
+    swept_at: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            sent: Mutex::new(SentLog::default()),
+        }
+    }
+
+    pub fn limits(&self) -> RateLimits {
+        self.limits
+    }
+
+    /// Дожидается слота для сообщения в `chat_id` и занимает его.
+    pub async fn acquire(&self, chat_id: i64) {
+        loop {
+            match self.try_reserve(chat_id, Instant::now()) {
+                Ok(()) => return,
+                Err(ready_at) => sleep_until(ready_at).await,
+            }
+        }
+    }
+
+    /// Занимает слот в момент `now` или возвращает, когда он освободится.
+    fn try_reserve(&self, chat_id: i64, now: Instant) -> Result<(), Instant> {
+        let mut guard = self.sent.lock().expect("журнал отправок доступен");
+        let sent = &mut *guard;
+        prune(&mut sent.global, now, SECOND);
+        // Раз в минуту чаты без свежих отправок забываются, чтобы журнал не рос
+        // вместе с числом чатов, а каждая отправка не обходила их все.
+        if sent
+            .swept_at
+            .is_none_or(|swept_at| swept_at + MINUTE <= now)
+        {
+            sent.chats.retain(|_, chat| {
+                prune(chat, now, MINUTE);
+                !chat.is_empty()
+            });
+            sent.swept_at = Some(now);
+        }
+        let chat = sent.chats.entry(chat_id).or_default();
+        prune(chat, now, MINUTE);
+
+        let group_ready_at = if chat_id < 0 {
+            window_ready_at(chat, self.limits.group_per_minute, MINUTE)
+        } else {
+            None
+        };
+        let ready_at = [
+            chat.back()
+                .map(|last| *last + self.limits.per_chat_interval),
+            group_ready_at,
+            window_ready_at(&sent.global, self.limits.global_per_second, SECOND),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(now, Instant::max);
+        if ready_at > now {
+            return Err(ready_at);
+        }
+
+        sent.global.push_back(now);
+        chat.push_back(now);
+        Ok(())
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimits::default())
+    }
+}
+
+fn prune(log: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while log.front().is_some_and(|sent| *sent + window <= now) {
+        log.pop_front();
+    }
+}
+
+/// Когда в окне `window` освободится место, если в нём уже `limit` отправок;
+/// `limit == 0` ничего не ограничивает.
+fn window_ready_at(log: &VecDeque<Instant>, limit: usize, window: Duration) -> Option<Instant> {
+    if limit == 0 {
+        return None;
+    }
+    let excess = log.len().checked_sub(limit)?;
+    log.get(excess).map(|sent| *sent + window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_per_chat_per_group_and_globally() {
+        let limiter = RateLimiter::new(RateLimits {
+            global_per_second: 3,
+            per_chat_interval: SECOND,
+            group_per_minute: 2,
+        });
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+
+        assert_eq!(limiter.try_reserve(1, at(0)), Ok(()));
+        assert_eq!(limiter.try_reserve(1, at(500)), Err(at(1000)));
+        assert_eq!(limiter.try_reserve(2, at(500)), Ok(()));
+        assert_eq!(limiter.try_reserve(-100, at(600)), Ok(()));
+        // Три отправки за секунду: четвёртый чат ждёт освобождения окна.
+        assert_eq!(limiter.try_reserve(3, at(700)), Err(at(1000)));
+        assert_eq!(limiter.try_reserve(-100, at(1600)), Ok(()));
+        // Третье сообщение в группу за минуту ждёт, пока первое выйдет из окна.
+        assert_eq!(limiter.try_reserve(-100, at(2600)), Err(at(60_600)));
+        assert_eq!(limiter.try_reserve(-100, at(60_600)), Ok(()));
+    }
+
+    #[test]
+    fn forgets_chats_without_recent_sends() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+
+        assert_eq!(limiter.try_reserve(1, at(0)), Ok(()));
+        assert_eq!(limiter.try_reserve(2, at(0)), Ok(()));
+        assert_eq!(limiter.try_reserve(3, at(60_000)), Ok(()));
+
+        let sent = limiter.sent.lock().expect("журнал отправок доступен");
+        assert_eq!(sent.chats.keys().copied().collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn idle_chats_are_swept_once_a_minute() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+
+        let chats = || {
+            let sent = limiter.sent.lock().expect("журнал отправок доступен");
+            let mut chats: Vec<i64> = sent.chats.keys().copied().collect();
+            chats.sort_unstable();
+            chats
+        };
+
+        assert_eq!(limiter.try_reserve(1, at(0)), Ok(()));
+        assert_eq!(limiter.try_reserve(2, at(30_000)), Ok(()));
+        assert_eq!(limiter.try_reserve(3, at(61_000)), Ok(()));
+        assert_eq!(chats(), vec![2, 3]);
+        // Окно чата 2 истекло, но до следующей уборки он остаётся в журнале.
+        assert_eq!(limiter.try_reserve(3, at(100_000)), Ok(()));
+        assert_eq!(chats(), vec![2, 3]);
+        assert_eq!(limiter.try_reserve(3, at(121_000)), Ok(()));
+        assert_eq!(chats(), vec![3]);
+    }
+
+    #[test]
+    fn zero_limits_do_not_block() {
+        let limiter = RateLimiter::new(RateLimits {
+            global_per_second: 0,
+            per_chat_interval: Duration::ZERO,
+            group_per_minute: 0,
+        });
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            assert_eq!(limiter.try_reserve(-100, now), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+/// Обновление из `getUpdates`; бот обрабатывает только текстовые сообщения.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Update {
+    pub update_id: i64,
+    #[serde(default)]
+    pub message: Option<IncomingMessage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IncomingMessage {
+    pub message_id: i64,
+    pub chat: Chat,
+    #[serde(default)]
+    pub from: Option<User>,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Chat {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+impl Chat {
+    pub fn is_private(&self) -> bool {
+        self.kind == "private"
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub id: i64,
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct ChatMember {
+    pub(super) status: String,
+}
+
+/// Конверт успешного ответа Bot API: `{"ok": true, "result": ...}`.
+#[derive(Debug, Deserialize)]
+pub(super) struct ApiResponse<T> {
+    pub(super) ok: bool,
+    pub(super) result: Option<T>,
+}
@@ -2,17 +2,22 @@
 // Модуль ещё не встроен в рабочий поток бота, поэтому временно подавляем
 // предупреждения о неиспользуемых элементах до его подключения.
 
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use reqwest::{Client, RequestBuilder, Response, StatusCode};
-use serde::Deserialize;
 use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::time::sleep;
-use tracing::info;
+use tracing::{info, warn};
+
+mod report;
+
+pub use report::{CandidateKind, FilterRecord, FilterReport, ItemFailure};
 
 const TMDB_BASE_URL: &str = "https://api.themoviedb.org/3";
 const DIGITAL_RELEASE_TYPE: &str = "4";
@@ -38,22 +43,71 @@ const MOVIE_DISCOVER_WINDOW_DAYS: i64 = 14;
 const DISCOVER_WINDOW_EXPAND_THRESHOLD_DAYS: i64 = 7;
 const MOVIE_DEBUG_CANDIDATES_LIMIT: usize = 25;
 const PRIORITY_REGIONS_ENV: &str = "TMDB_PRIORITY_REGIONS";
+/// Сколько тайтлов ленты изменений обрабатывается за прогон. Лента читается
+/// целыми сутками, и первые сутки обрабатываются всегда, даже сверх лимита.
+const MAX_CHANGED_TITLES: usize = 500;
+const MAX_CHANGES_WINDOW_DAYS: i64 = 14;
+const MOVIE_CHANGE_KEYS: [&str; 1] = ["release_dates"];
+const TV_CHANGE_KEYS: [&str; 2] = ["season", "seasons"];
+const ERROR_BUDGET_ENV: &str = "TMDB_ERROR_BUDGET";
+const DEFAULT_ERROR_BUDGET: usize = 10;
+const BODY_EXCERPT_CHARS: usize = 200;
 
 #[derive(Debug, Error)]
 pub enum TmdbError {
     #[error("некорректное окно релизов: начало позже конца")]
     InvalidWindow,
-    #[error("ошибка HTTP: {0}")]
-    Http(#[from] reqwest::Error),
-    #[error("неожиданный статус ответа: {0}")]
-    UnexpectedStatus(StatusCode),
+    #[error("ошибка HTTP при запросе {endpoint}: {source}")]
+    Http {
+        endpoint: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("неожиданный статус ответа {status} от {endpoint}: {body}")]
+    UnexpectedStatus {
+        endpoint: String,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("некорректный ответ {endpoint}: {source}; тело: {body}")]
+    Decode {
+        endpoint: String,
+        body: String,
+        #[source]
+        source: serde_json::Error,
+    },
     #[error("ошибка парсинга даты: {0}")]
     DateParse(#[from] chrono::ParseError),
-    #[error("предел повторных попыток исчерпан")]
-    RetryLimitExceeded,
+    #[error("предел повторных попыток исчерпан для {endpoint}")]
+    RetryLimitExceeded { endpoint: String },
+    #[error("ошибка обработки TMDB id {title_id}: {source}")]
+    Title {
+        title_id: u64,
+        #[source]
+        source: Box<TmdbError>,
+    },
+    #[error("превышен бюджет ошибок TMDB: {failures} сбоев при допустимых {budget}")]
+    ErrorBudgetExceeded { failures: usize, budget: usize },
 }
 
-#[derive(Debug, Clone, Copy)]
+impl TmdbError {
+    fn for_title(self, title_id: u64) -> Self {
+        Self::Title {
+            title_id,
+            source: Box::new(self),
+        }
+    }
+
+    /// Идентификатор тайтла, при обработке которого возникла ошибка.
+    pub fn title_id(&self) -> Option<u64> {
+        match self {
+            Self::Title { title_id, .. } => Some(*title_id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ReleaseWindow {
     pub start: DateTime<Utc>,
     pub end: DateTime<Utc>,
@@ -71,6 +125,36 @@ pub struct MovieRelease {
     pub vote_count: Option<u32>,
     pub homepage: Option<String>,
     pub watch_providers: Vec<String>,
+    /// Жанры TMDB (английские названия).
+    pub genres: Vec<String>,
+    /// Цифровые даты по регионам, попавшие в окно прогона.
+    pub regional_digital_dates: BTreeMap<String, NaiveDate>,
+    /// Дата приоритетных регионов (`digital_release_date`) вне окна прогона:
+    /// релиз получают только чаты с регионами из `regional_digital_dates`.
+    pub only_for_regions: bool,
+    pub collection: Option<CollectionEntry>,
+    /// Фильм не прошёл общие фильтры и попал в прогон только как часть
+    /// отслеживаемой коллекции: его получают лишь чаты, подписанные на неё.
+    pub only_for_followers: bool,
+}
+
+/// Коллекция (франшиза) TMDB, к которой относится фильм, и его место в ней.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionEntry {
+    pub id: u64,
+    pub name: String,
+    /// Порядковый номер фильма по дате выхода, если состав коллекции удалось загрузить.
+    pub part: Option<usize>,
+    pub total: Option<usize>,
+}
+
+/// Выбранная цифровая дата релиза и регион, из которого она взята.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigitalReleaseDate {
+    pub date: NaiveDate,
+    pub region: Option<String>,
+    /// Предпочтительная цифровая дата в каждом регионе, где она известна.
+    pub regional_dates: BTreeMap<String, NaiveDate>,
 }
 
 #[derive(Debug, Clone)]
@@ -107,6 +191,22 @@ pub struct TmdbClient {
     http: Client,
     api_key: String,
     priority_regions: Vec<String>,
+    error_budget: usize,
+    followed_collections: HashSet<u64>,
+    /// Кандидаты последнего окна: обход discover и лента изменений одного
+    /// прогона загружают их один раз.
+    candidates: Arc<Mutex<Option<Arc<MovieCandidates>>>>,
+}
+
+/// Кандидаты discover по приоритетным регионам и состав отслеживаемых коллекций
+/// для одного окна.
+#[derive(Debug)]
+struct MovieCandidates {
+    window: ReleaseWindow,
+    movies: Vec<DiscoverMovie>,
+    /// Приоритетные регионы, в выдаче discover которых нашёлся фильм.
+    movie_regions: HashMap<u64, BTreeSet<String>>,
+    followed_parts: HashMap<u64, Vec<CollectionPart>>,
 }
 
 impl TmdbClient {
@@ -123,18 +223,113 @@ impl TmdbClient {
             http,
             api_key: api_key.into(),
             priority_regions: resolve_priority_regions(),
+            error_budget: resolve_error_budget(),
+            followed_collections: HashSet::new(),
+            candidates: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Коллекции, новые части которых проходят в прогон в обход общих фильтров качества.
+    pub fn with_followed_collections<I>(mut self, collections: I) -> Self
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        self.followed_collections = collections.into_iter().collect();
+        self
+    }
+
+    /// Задаёт число сбоев по отдельным тайтлам, которое прогон переживает без ошибки.
+    pub fn with_error_budget(mut self, budget: usize) -> Self {
+        self.error_budget = budget;
+        self
+    }
+
+    /// Проверяет, что число пропущенных из-за ошибок тайтлов укладывается в бюджет.
+    pub fn check_error_budget(&self, report: &FilterReport) -> Result<(), TmdbError> {
+        check_error_budget(report.failures.len(), self.error_budget)
+    }
+
+    /// Цифровые релизы окна вместе с полным отчётом фильтрации по каждому кандидату.
     pub async fn fetch_digital_releases(
         &self,
         window: ReleaseWindow,
-    ) -> Result<Vec<MovieRelease>, TmdbError> {
+    ) -> Result<(Vec<MovieRelease>, FilterReport), TmdbError> {
         if window.start > window.end {
             return Err(TmdbError::InvalidWindow);
         }
         let movie_window = movie_discover_window(window);
+        let start = format_discover_date(movie_window.start);
+        let end = format_discover_date(movie_window.end);
+        let candidates = self.movie_candidates(window).await?;
+
+        let raw_movies = candidates.movies.len();
+        let (releases, stats, report) = self
+            .filter_movie_candidates(
+                candidates.movies.clone(),
+                &candidates.movie_regions,
+                HashMap::new(),
+                &candidates.followed_parts,
+                window,
+            )
+            .await?;
 
+        info!(
+            target: "tmdb",
+            fetched = releases.len(),
+            skipped_missing_date = stats.skipped_missing_date,
+            skipped_missing_original_date = stats.skipped_missing_original_date,
+            skipped_missing_digital_date = stats.skipped_missing_digital_date,
+            skipped_outside_window = stats.skipped_outside_window,
+            skipped_old = stats.skipped_old,
+            "Сформирован список цифровых релизов после фильтров"
+        );
+        info!(
+            target: "tmdb",
+            raw_movies,
+            after_basic_filter = stats.after_basic_filter,
+            skipped_by_country = stats.skipped_by_country,
+            skipped_by_genre = stats.skipped_by_genre,
+            skipped_by_quality = stats.skipped_by_quality,
+            skipped_by_runtime = stats.skipped_by_runtime,
+            skipped_by_imdb = stats.skipped_by_imdb,
+            final_movies = releases.len(),
+            "Диагностика фильтрации фильмов"
+        );
+        info!(
+            target: "tmdb",
+            raw_movies,
+            details_enriched = stats.details_enriched,
+            accepted_movies = releases.len(),
+            rejected_country = stats.skipped_by_country,
+            rejected_genre = stats.skipped_by_genre,
+            rejected_rating_vote_count = stats.skipped_by_quality,
+            rejected_runtime = stats.skipped_by_runtime,
+            rejected_imdb_missing = stats.skipped_by_imdb,
+            rejected_other = stats.skipped_by_other,
+            detailed_logged = stats.logged_candidates,
+            debug_limit = MOVIE_DEBUG_CANDIDATES_LIMIT,
+            discover_start = %start,
+            discover_end = %end,
+            "Сводка movie-пайплайна"
+        );
+
+        Ok((releases, report))
+    }
+
+    /// Кандидаты discover и состав отслеживаемых коллекций для окна. Загружаются
+    /// один раз: повторный вызов с тем же окном (лента изменений того же прогона)
+    /// берёт их из кеша.
+    async fn movie_candidates(
+        &self,
+        window: ReleaseWindow,
+    ) -> Result<Arc<MovieCandidates>, TmdbError> {
+        if let Some(candidates) = &*self.candidates.lock().expect("кеш кандидатов доступен")
+            && candidates.window == window
+        {
+            return Ok(Arc::clone(candidates));
+        }
+
+        let movie_window = movie_discover_window(window);
         let url = format!("{TMDB_BASE_URL}/discover/movie");
         let client = self.http.clone();
         let api_key = self.api_key.clone();
@@ -174,6 +369,13 @@ impl TmdbClient {
         if movies.len() > MAX_DISCOVER_RESULTS_TOTAL {
             movies.truncate(MAX_DISCOVER_RESULTS_TOTAL);
         }
+        // Части отслеживаемых коллекций берутся напрямую из их состава: discover
+        // отсекает их порогом голосов, а фильтр свежести — годом оригинального выхода.
+        let followed_parts = self.fetch_followed_collections().await;
+        let followed_candidates =
+            followed_collection_candidates(&followed_parts, &seen_ids, window.end.date_naive());
+        let followed_added = followed_candidates.len();
+        movies.extend(followed_candidates);
 
         info!(
             target: "tmdb",
@@ -183,26 +385,106 @@ impl TmdbClient {
             regional_candidates = ?regional_candidates,
             before_dedup,
             after_dedup,
+            followed_added,
             limited_total = movies.len(),
             "Собраны кандидаты discover по приоритетным регионам"
         );
 
-        let raw_movies = movies.len();
+        let candidates = Arc::new(MovieCandidates {
+            window,
+            movies,
+            movie_regions,
+            followed_parts,
+        });
+        *self.candidates.lock().expect("кеш кандидатов доступен") = Some(Arc::clone(&candidates));
+        Ok(candidates)
+    }
+
+    /// Инкрементальный поиск цифровых релизов через `/movie/changes`.
+    ///
+    /// Берёт фильмы, у которых с `since` менялись `release_dates`, и прогоняет их
+    /// через те же фильтры, что и [`Self::fetch_digital_releases`], с теми же
+    /// регионами discover и составом отслеживаемых коллекций. Кроме релизов
+    /// возвращает момент, до которого лента обработана: дальше него курсор не
+    /// сдвигается, и остаток ленты дочитывается следующими прогонами.
+    pub async fn fetch_changed_digital_releases(
+        &self,
+        since: DateTime<Utc>,
+        window: ReleaseWindow,
+    ) -> Result<(Vec<MovieRelease>, FilterReport, DateTime<Utc>), TmdbError> {
+        if window.start > window.end {
+            return Err(TmdbError::InvalidWindow);
+        }
+        let changes_window = changes_window(since, window.end);
+        let (changed_ids, synced_until) = self
+            .walk_change_feed(ChangeFeed::Movie, changes_window)
+            .await?;
+
+        let mut candidates = Vec::new();
+        let mut prefetched = HashMap::new();
+        let mut failures = FilterReport::default();
+        for movie_id in &changed_ids {
+            // Детали и изменения тайтла приходят одним запросом.
+            let payload = match self
+                .fetch_movie_with_changes(*movie_id, changes_window)
+                .await
+            {
+                Ok(payload) => payload,
+                Err(err) => {
+                    failures.record_failure(CandidateKind::Movie, *movie_id, &err);
+                    continue;
+                }
+            };
+            if !has_relevant_change(ChangeFeed::Movie, &payload.changes) {
+                continue;
+            }
+            let (movie, details) = movie_candidate(*movie_id, payload);
+            prefetched.insert(movie.id, details);
+            candidates.push(movie);
+        }
+
+        // Регионы discover и состав коллекций — те же, что у обхода discover.
+        let discovered = self.movie_candidates(window).await?;
+        let relevant = candidates.len();
+        let (releases, stats, mut report) = self
+            .filter_movie_candidates(
+                candidates,
+                &discovered.movie_regions,
+                prefetched,
+                &discovered.followed_parts,
+                window,
+            )
+            .await?;
+        report.extend(failures);
+
+        info!(
+            target: "tmdb",
+            changes_start = %format_discover_date(changes_window.start),
+            changes_end = %format_discover_date(changes_window.end),
+            synced_until = %synced_until,
+            changed = changed_ids.len(),
+            relevant,
+            accepted = releases.len(),
+            failed = report.failures.len(),
+            skipped_outside_window = stats.skipped_outside_window,
+            skipped_missing_digital_date = stats.skipped_missing_digital_date,
+            "Обработана лента изменений фильмов"
+        );
+
+        Ok((releases, report, synced_until))
+    }
+
+    async fn filter_movie_candidates(
+        &self,
+        movies: Vec<DiscoverMovie>,
+        movie_regions: &HashMap<u64, BTreeSet<String>>,
+        mut prefetched: HashMap<u64, MovieDetails>,
+        followed_parts: &HashMap<u64, Vec<CollectionPart>>,
+        window: ReleaseWindow,
+    ) -> Result<(Vec<MovieRelease>, MovieFilterStats, FilterReport), TmdbError> {
         let mut releases = Vec::new();
-        let mut details_enriched = 0usize;
-        let mut logged_candidates = 0usize;
-        let mut skipped_missing_date = 0usize;
-        let mut skipped_missing_original_date = 0usize;
-        let mut skipped_missing_digital_date = 0usize;
-        let mut skipped_outside_window = 0usize;
-        let mut skipped_old = 0usize;
-        let mut after_basic_filter = 0usize;
-        let mut skipped_by_imdb = 0usize;
-        let mut skipped_by_country = 0usize;
-        let mut skipped_by_genre = 0usize;
-        let mut skipped_by_quality = 0usize;
-        let mut skipped_by_runtime = 0usize;
-        let mut skipped_by_other = 0usize;
+        let mut stats = MovieFilterStats::default();
+        let mut report = FilterReport::default();
         let current_year = window.end.date_naive().year();
         for movie in movies.into_iter() {
             let discover_regions = movie_regions
@@ -215,9 +497,19 @@ impl TmdbClient {
                 movie.release_date.clone()
             };
             if movie.release_date.is_empty() {
-                skipped_missing_date += 1;
-                skipped_by_other += 1;
-                if logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
+                stats.skipped_missing_date += 1;
+                stats.skipped_by_other += 1;
+                report.push(FilterRecord::movie(
+                    &movie,
+                    &discover_regions,
+                    None,
+                    MovieFilterVerdict::rejected(
+                        MovieRejectionReason::Other,
+                        "missing_release_date",
+                    ),
+                    None,
+                ));
+                if stats.logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
                     info!(
                         target: "tmdb",
                         title = %movie.title,
@@ -237,16 +529,26 @@ impl TmdbClient {
                         reason = "missing_release_date",
                         "movie_candidate_diagnostic"
                     );
-                    logged_candidates += 1;
+                    stats.logged_candidates += 1;
                 }
                 continue;
             }
             let Some(original_release_date) =
                 parse_original_release_date(&movie.original_release_date)
             else {
-                skipped_missing_original_date += 1;
-                skipped_by_other += 1;
-                if logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
+                stats.skipped_missing_original_date += 1;
+                stats.skipped_by_other += 1;
+                report.push(FilterRecord::movie(
+                    &movie,
+                    &discover_regions,
+                    None,
+                    MovieFilterVerdict::rejected(
+                        MovieRejectionReason::Other,
+                        "missing_original_release_date",
+                    ),
+                    None,
+                ));
+                if stats.logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
                     info!(
                         target: "tmdb",
                         title = %movie.title,
@@ -266,13 +568,23 @@ impl TmdbClient {
                         reason = "missing_original_release_date",
                         "movie_candidate_diagnostic"
                     );
-                    logged_candidates += 1;
+                    stats.logged_candidates += 1;
                 }
                 continue;
             };
             let original_year = original_release_date.year();
-            if !is_recent_original_release(original_year, current_year) {
-                skipped_old += 1;
+            let followed_part = followed_parts
+                .values()
+                .any(|parts| parts.iter().any(|part| part.id == movie.id));
+            if !followed_part && !is_recent_original_release(original_year, current_year) {
+                stats.skipped_old += 1;
+                report.push(FilterRecord::movie(
+                    &movie,
+                    &discover_regions,
+                    None,
+                    MovieFilterVerdict::rejected(MovieRejectionReason::Other, "old_movie"),
+                    None,
+                ));
                 info!(
                     target: "tmdb",
                     title = %movie.title,
@@ -285,15 +597,39 @@ impl TmdbClient {
             let release_date = parse_release_date(&movie.release_date)
                 .ok()
                 .unwrap_or(original_release_date);
-            let details = self.fetch_movie_details(movie.id).await?;
-            details_enriched += 1;
+            let details = match prefetched.remove(&movie.id) {
+                Some(details) => details,
+                None => match self.fetch_movie_details(movie.id).await {
+                    Ok(details) => details,
+                    Err(err) => {
+                        report.record_failure(CandidateKind::Movie, movie.id, &err);
+                        continue;
+                    }
+                },
+            };
+            stats.details_enriched += 1;
             let today = window.end.date_naive();
-            let Some(digital_release_date) =
-                self.fetch_digital_release_date(movie.id, today).await?
-            else {
-                skipped_missing_digital_date += 1;
-                skipped_by_other += 1;
-                if logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
+            let digital_release = match self.fetch_digital_release(movie.id, today).await {
+                Ok(digital_release) => digital_release,
+                Err(err) => {
+                    report.record_failure(CandidateKind::Movie, movie.id, &err);
+                    continue;
+                }
+            };
+            let Some(digital_release) = digital_release else {
+                stats.skipped_missing_digital_date += 1;
+                stats.skipped_by_other += 1;
+                report.push(FilterRecord::movie(
+                    &movie,
+                    &discover_regions,
+                    Some(&details),
+                    MovieFilterVerdict::rejected(
+                        MovieRejectionReason::Other,
+                        "missing_digital_release_date",
+                    ),
+                    None,
+                ));
+                if stats.logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
                     log_movie_candidate_diagnostic(
                         &movie,
                         &discover_regions,
@@ -304,15 +640,34 @@ impl TmdbClient {
                             "missing_digital_release_date",
                         ),
                     );
-                    logged_candidates += 1;
+                    stats.logged_candidates += 1;
                 }
                 continue;
             };
 
-            if !date_in_window(digital_release_date, window) {
-                skipped_outside_window += 1;
-                skipped_by_other += 1;
-                if logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
+            let regional_digital_dates: BTreeMap<String, NaiveDate> = digital_release
+                .regional_dates
+                .iter()
+                .filter(|(_, date)| date_in_window(**date, window))
+                .map(|(region, date)| (region.clone(), *date))
+                .collect();
+            // Дата приоритетных регионов вне окна: релиз нужен только чатам с
+            // регионами, у которых цифровой выход попал в окно.
+            let only_for_regions = !date_in_window(digital_release.date, window);
+            if only_for_regions && regional_digital_dates.is_empty() {
+                stats.skipped_outside_window += 1;
+                stats.skipped_by_other += 1;
+                report.push(FilterRecord::movie(
+                    &movie,
+                    &discover_regions,
+                    Some(&details),
+                    MovieFilterVerdict::rejected(
+                        MovieRejectionReason::Other,
+                        "digital_release_outside_window",
+                    ),
+                    Some(&digital_release),
+                ));
+                if stats.logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
                     log_movie_candidate_diagnostic(
                         &movie,
                         &discover_regions,
@@ -323,14 +678,31 @@ impl TmdbClient {
                             "digital_release_outside_window",
                         ),
                     );
-                    logged_candidates += 1;
+                    stats.logged_candidates += 1;
                 }
                 continue;
             }
 
-            after_basic_filter += 1;
-            let verdict = movie_filter_verdict(&details);
-            if logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
+            stats.after_basic_filter += 1;
+            let base_verdict = movie_filter_verdict(&details);
+            let only_for_followers = !base_verdict.is_accepted()
+                && details
+                    .collection
+                    .as_ref()
+                    .is_some_and(|collection| self.followed_collections.contains(&collection.id));
+            let verdict = if only_for_followers {
+                MovieFilterVerdict::followed_collection()
+            } else {
+                base_verdict
+            };
+            report.push(FilterRecord::movie(
+                &movie,
+                &discover_regions,
+                Some(&details),
+                verdict,
+                Some(&digital_release),
+            ));
+            if stats.logged_candidates < MOVIE_DEBUG_CANDIDATES_LIMIT {
                 log_movie_candidate_diagnostic(
                     &movie,
                     &discover_regions,
@@ -338,92 +710,63 @@ impl TmdbClient {
                     &details,
                     verdict,
                 );
-                logged_candidates += 1;
+                stats.logged_candidates += 1;
             }
             if verdict.rejection_reason == Some(MovieRejectionReason::ImdbMissing) {
-                skipped_by_imdb += 1;
+                stats.skipped_by_imdb += 1;
                 continue;
             }
             if verdict.rejection_reason == Some(MovieRejectionReason::Country) {
-                skipped_by_country += 1;
+                stats.skipped_by_country += 1;
                 continue;
             }
             if verdict.rejection_reason == Some(MovieRejectionReason::Genre) {
-                skipped_by_genre += 1;
+                stats.skipped_by_genre += 1;
                 continue;
             }
             if verdict.rejection_reason == Some(MovieRejectionReason::RatingVoteCount) {
-                skipped_by_quality += 1;
+                stats.skipped_by_quality += 1;
                 continue;
             }
             if verdict.rejection_reason == Some(MovieRejectionReason::Runtime) {
-                skipped_by_runtime += 1;
+                stats.skipped_by_runtime += 1;
                 continue;
             }
 
+            let collection = match &details.collection {
+                Some(collection) => Some(
+                    self.collection_entry(movie.id, collection, followed_parts)
+                        .await,
+                ),
+                None => None,
+            };
             releases.push(MovieRelease {
                 id: movie.id,
                 title: movie.title,
                 release_date,
-                digital_release_date,
+                digital_release_date: digital_release.date,
                 original_language: movie.original_language,
                 popularity: movie.popularity,
                 vote_average: details.vote_average,
                 vote_count: details.vote_count,
                 homepage: details.homepage,
                 watch_providers: details.watch_providers,
+                genres: details.genres.into_iter().map(|genre| genre.name).collect(),
+                regional_digital_dates,
+                only_for_regions,
+                collection,
+                only_for_followers,
             });
         }
 
-        info!(
-            target: "tmdb",
-            fetched = releases.len(),
-            skipped_missing_date,
-            skipped_missing_original_date,
-            skipped_missing_digital_date,
-            skipped_outside_window,
-            skipped_old,
-            "Сформирован список цифровых релизов после фильтров"
-        );
-        info!(
-            target: "tmdb",
-            raw_movies,
-            after_basic_filter,
-            skipped_by_country,
-            skipped_by_genre,
-            skipped_by_quality,
-            skipped_by_runtime,
-            skipped_by_imdb,
-            final_movies = releases.len(),
-            "Диагностика фильтрации фильмов"
-        );
-        info!(
-            target: "tmdb",
-            raw_movies,
-            details_enriched,
-            accepted_movies = releases.len(),
-            rejected_country = skipped_by_country,
-            rejected_genre = skipped_by_genre,
-            rejected_rating_vote_count = skipped_by_quality,
-            rejected_runtime = skipped_by_runtime,
-            rejected_imdb_missing = skipped_by_imdb,
-            rejected_other = skipped_by_other,
-            detailed_logged = logged_candidates,
-            debug_limit = MOVIE_DEBUG_CANDIDATES_LIMIT,
-            discover_start = %start,
-            discover_end = %end,
-            "Сводка movie-пайплайна"
-        );
-
-        println!(
-            "movie_filter_summary raw={raw_movies} accepted={} missing_release_date={skipped_missing_date} missing_original_date={skipped_missing_original_date} missing_digital_date={skipped_missing_digital_date} outside_window={skipped_outside_window} old={skipped_old} no_imdb={skipped_by_imdb} country={skipped_by_country} genre={skipped_by_genre} quality={skipped_by_quality} runtime={skipped_by_runtime}",
-            releases.len()
-        );
-
-        Ok(releases)
+        Ok((releases, stats, report))
     }
 
-    pub async fn fetch_tv_events(&self, window: ReleaseWindow) -> Result<Vec<TvEvent>, TmdbError> {
+    /// События сериалов окна вместе с отчётом фильтрации по премьерам и сезонам.
+    pub async fn fetch_tv_events(
+        &self,
+        window: ReleaseWindow,
+    ) -> Result<(Vec<TvEvent>, FilterReport), TmdbError> {
         if window.start > window.end {
             return Err(TmdbError::InvalidWindow);
         }
@@ -437,122 +780,353 @@ impl TmdbClient {
         }
 
         let mut events = Vec::new();
-        let mut skipped_missing_date = 0usize;
-        let mut skipped_outside_window = 0usize;
-        let mut skipped_quality = 0usize;
-
+        let mut stats = TvFilterStats::default();
+        let mut report = FilterReport::default();
         for show_id in show_ids {
-            let details = self.fetch_tv_details(show_id).await?;
-            let mut has_premiere = false;
-
-            if let Some(date) = details
-                .first_air_date
-                .as_deref()
-                .and_then(parse_optional_release_date)
-            {
-                if date_in_window(date, window) {
-                    if passes_quality_filters(
-                        details.vote_average,
-                        details.vote_count,
-                        details.popularity,
-                    ) {
-                        events.push(TvEvent {
-                            show_id,
-                            show_name: details.name.clone(),
-                            original_language: details.original_language.clone(),
-                            event_date: date,
-                            kind: TvEventKind::Premiere,
-                            vote_average: details.vote_average,
-                            vote_count: details.vote_count,
-                            popularity: details.popularity,
-                        });
-                        has_premiere = true;
-                    } else {
-                        skipped_quality += 1;
-                    }
-                } else {
-                    skipped_outside_window += 1;
-                }
-            } else {
-                skipped_missing_date += 1;
-            }
-
-            for season in details.seasons.iter() {
-                let Some(date) = season
-                    .air_date
-                    .as_deref()
-                    .and_then(parse_optional_release_date)
-                else {
-                    skipped_missing_date += 1;
-                    continue;
-                };
-                if !date_in_window(date, window) {
-                    skipped_outside_window += 1;
+            let details = match self.fetch_tv_details(show_id).await {
+                Ok(details) => details,
+                Err(err) => {
+                    report.record_failure(CandidateKind::TvShow, show_id, &err);
                     continue;
                 }
-                if !passes_quality_filters(
-                    details.vote_average,
-                    details.vote_count,
-                    details.popularity,
-                ) {
-                    skipped_quality += 1;
-                    continue;
-                }
-                if has_premiere && season.season_number == 1 {
+            };
+            events.extend(collect_tv_events(
+                show_id,
+                &details,
+                window,
+                &mut stats,
+                &mut report,
+            ));
+        }
+
+        info!(
+            target: "tmdb",
+            fetched = events.len(),
+            failed = report.failures.len(),
+            skipped_missing_date = stats.skipped_missing_date,
+            skipped_outside_window = stats.skipped_outside_window,
+            skipped_quality = stats.skipped_quality,
+            "Сформирован список событий сериалов"
+        );
+
+        Ok((events, report))
+    }
+
+    /// Инкрементальный поиск событий сериалов через `/tv/changes`.
+    ///
+    /// Учитываются только сериалы, у которых с `since` менялись сезоны. Как и
+    /// [`Self::fetch_changed_digital_releases`], возвращает, до какого момента
+    /// обработана лента.
+    pub async fn fetch_changed_tv_events(
+        &self,
+        since: DateTime<Utc>,
+        window: ReleaseWindow,
+    ) -> Result<(Vec<TvEvent>, FilterReport, DateTime<Utc>), TmdbError> {
+        if window.start > window.end {
+            return Err(TmdbError::InvalidWindow);
+        }
+        let changes_window = changes_window(since, window.end);
+        let (changed_ids, synced_until) = self
+            .walk_change_feed(ChangeFeed::Tv, changes_window)
+            .await?;
+
+        let mut events = Vec::new();
+        let mut relevant = 0usize;
+        let mut stats = TvFilterStats::default();
+        let mut report = FilterReport::default();
+        for show_id in &changed_ids {
+            let payload = match self.fetch_tv_with_changes(*show_id, changes_window).await {
+                Ok(payload) => payload,
+                Err(err) => {
+                    report.record_failure(CandidateKind::TvShow, *show_id, &err);
                     continue;
                 }
-                events.push(TvEvent {
-                    show_id,
-                    show_name: details.name.clone(),
-                    original_language: details.original_language.clone(),
-                    event_date: date,
-                    kind: TvEventKind::Season {
-                        season_number: season.season_number,
-                    },
-                    vote_average: details.vote_average,
-                    vote_count: details.vote_count,
-                    popularity: details.popularity,
-                });
+            };
+            if !has_relevant_change(ChangeFeed::Tv, &payload.changes) {
+                continue;
             }
+            relevant += 1;
+            let details = TvShowDetails::from(payload);
+            events.extend(collect_tv_events(
+                *show_id,
+                &details,
+                window,
+                &mut stats,
+                &mut report,
+            ));
         }
 
         info!(
             target: "tmdb",
+            changes_start = %format_discover_date(changes_window.start),
+            changes_end = %format_discover_date(changes_window.end),
+            synced_until = %synced_until,
+            changed = changed_ids.len(),
+            relevant,
             fetched = events.len(),
-            skipped_missing_date,
-            skipped_outside_window,
-            skipped_quality,
-            "Сформирован список событий сериалов"
+            failed = report.failures.len(),
+            skipped_outside_window = stats.skipped_outside_window,
+            skipped_quality = stats.skipped_quality,
+            "Обработана лента изменений сериалов"
+        );
+
+        Ok((events, report, synced_until))
+    }
+
+    /// Читает ленту изменений посуточно, каждые сутки — всеми страницами.
+    ///
+    /// Следующие сутки не начинаются, если их тайтлы не укладываются в
+    /// [`MAX_CHANGED_TITLES`]. Возвращает id и момент, до которого лента прочитана.
+    async fn walk_change_feed(
+        &self,
+        feed: ChangeFeed,
+        window: ReleaseWindow,
+    ) -> Result<(Vec<u64>, DateTime<Utc>), TmdbError> {
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
+        let mut synced_until = window.start;
+        for day in change_days(window) {
+            let day_ids: Vec<u64> = self
+                .fetch_changed_ids(feed, day)
+                .await?
+                .into_iter()
+                .filter(|id| !seen.contains(id))
+                .collect();
+            if !ids.is_empty() && ids.len() + day_ids.len() > MAX_CHANGED_TITLES {
+                info!(
+                    target: "tmdb",
+                    feed = feed.path(),
+                    synced_until = %synced_until,
+                    processed = ids.len(),
+                    postponed = day_ids.len(),
+                    "Лимит ленты изменений исчерпан, остаток дочитает следующий прогон"
+                );
+                break;
+            }
+            seen.extend(day_ids.iter().copied());
+            ids.extend(day_ids);
+            synced_until = day.end;
+        }
+
+        Ok((ids, synced_until))
+    }
+
+    async fn fetch_changed_ids(
+        &self,
+        feed: ChangeFeed,
+        window: ReleaseWindow,
+    ) -> Result<Vec<u64>, TmdbError> {
+        let url = format!("{TMDB_BASE_URL}/{}/changes", feed.path());
+        let client = self.http.clone();
+        let api_key = self.api_key.clone();
+        let start = format_discover_date(window.start);
+        let end = format_discover_date(window.end);
+
+        let request_factory = |page| {
+            let client = client.clone();
+            let url = url.clone();
+            let api_key = api_key.clone();
+            let start = start.clone();
+            let end = end.clone();
+
+            move || {
+                changes_request(
+                    client.clone(),
+                    url.clone(),
+                    api_key.clone(),
+                    start.clone(),
+                    end.clone(),
+                    Some(page),
+                )
+            }
+        };
+
+        let response: ChangesResponse = self.fetch_json(request_factory(1)).await?;
+        let total_pages = response.total_pages.max(1);
+        let mut entries = response.results;
+        for page in 2..=total_pages {
+            let response: ChangesResponse = self.fetch_json(request_factory(page)).await?;
+            entries.extend(response.results);
+        }
+
+        let mut seen = HashSet::new();
+        let ids: Vec<u64> = entries
+            .into_iter()
+            .filter(|entry| !entry.adult.unwrap_or(false) && seen.insert(entry.id))
+            .map(|entry| entry.id)
+            .collect();
+
+        info!(
+            target: "tmdb",
+            feed = feed.path(),
+            start = %start,
+            end = %end,
+            total_pages,
+            changed = ids.len(),
+            "Получена лента изменений TMDB"
         );
 
-        Ok(events)
+        Ok(ids)
+    }
+
+    /// Детали фильма вместе с его изменениями за `window` (`append_to_response=changes`).
+    async fn fetch_movie_with_changes(
+        &self,
+        movie_id: u64,
+        window: ReleaseWindow,
+    ) -> Result<MovieDetailsResponse, TmdbError> {
+        let url = format!("{TMDB_BASE_URL}/movie/{movie_id}");
+        let client = self.http.clone();
+        let api_key = self.api_key.clone();
+        let start = format_discover_date(window.start);
+        let end = format_discover_date(window.end);
+
+        let request_factory = move || {
+            title_changes_request(
+                client.clone(),
+                url.clone(),
+                api_key.clone(),
+                "watch/providers,changes",
+                start.clone(),
+                end.clone(),
+            )
+        };
+
+        self.fetch_json(request_factory)
+            .await
+            .map_err(|err| err.for_title(movie_id))
+    }
+
+    /// Детали сериала вместе с его изменениями за `window`.
+    async fn fetch_tv_with_changes(
+        &self,
+        show_id: u64,
+        window: ReleaseWindow,
+    ) -> Result<TvShowDetailsResponse, TmdbError> {
+        let url = format!("{TMDB_BASE_URL}/tv/{show_id}");
+        let client = self.http.clone();
+        let api_key = self.api_key.clone();
+        let start = format_discover_date(window.start);
+        let end = format_discover_date(window.end);
+
+        let request_factory = move || {
+            title_changes_request(
+                client.clone(),
+                url.clone(),
+                api_key.clone(),
+                "changes",
+                start.clone(),
+                end.clone(),
+            )
+        };
+
+        self.fetch_json(request_factory)
+            .await
+            .map_err(|err| err.for_title(show_id))
     }
 
     pub async fn fetch_movie_details(&self, movie_id: u64) -> Result<MovieDetails, TmdbError> {
+        let payload = self.fetch_movie_details_response(movie_id).await?;
+        Ok(MovieDetails::from(payload))
+    }
+
+    async fn fetch_movie_details_response(
+        &self,
+        movie_id: u64,
+    ) -> Result<MovieDetailsResponse, TmdbError> {
         let url = format!("{TMDB_BASE_URL}/movie/{movie_id}");
         let client = self.http.clone();
         let api_key = self.api_key.clone();
 
         let request_factory = move || movie_request(client.clone(), url.clone(), api_key.clone());
 
-        let payload: MovieDetailsResponse = self.fetch_json(request_factory).await?;
+        self.fetch_json(request_factory)
+            .await
+            .map_err(|err| err.for_title(movie_id))
+    }
 
-        let watch_providers = payload
-            .watch_providers
-            .map(|providers| collect_providers(providers.results))
-            .unwrap_or_default();
+    /// Состав отслеживаемых коллекций; коллекции, которые не удалось загрузить, пропускаются.
+    async fn fetch_followed_collections(&self) -> HashMap<u64, Vec<CollectionPart>> {
+        let mut collections = HashMap::new();
+        for collection_id in &self.followed_collections {
+            match self.fetch_collection_parts(*collection_id).await {
+                Ok(parts) => {
+                    collections.insert(*collection_id, parts);
+                }
+                Err(err) => {
+                    warn!(
+                        target: "tmdb",
+                        collection_id,
+                        error = %err,
+                        "Не удалось загрузить состав отслеживаемой коллекции"
+                    );
+                }
+            }
+        }
+        collections
+    }
 
-        Ok(MovieDetails {
-            homepage: payload.homepage,
-            imdb_id: payload.imdb_id,
-            watch_providers,
-            production_countries: payload.production_countries,
-            vote_average: payload.vote_average,
-            vote_count: payload.vote_count,
-            genres: payload.genres,
-            runtime: payload.runtime,
-            popularity: payload.popularity,
-        })
+    async fn fetch_collection_parts(
+        &self,
+        collection_id: u64,
+    ) -> Result<Vec<CollectionPart>, TmdbError> {
+        let url = format!("{TMDB_BASE_URL}/collection/{collection_id}");
+        let client = self.http.clone();
+        let api_key = self.api_key.clone();
+
+        let request_factory =
+            move || collection_request(client.clone(), url.clone(), api_key.clone());
+
+        let payload: CollectionResponse = self.fetch_json(request_factory).await?;
+        Ok(payload.parts)
+    }
+
+    /// Коллекция фильма. Позиция в ней нужна только подписчикам коллекции, поэтому
+    /// состав загружается лишь для отслеживаемых коллекций.
+    async fn collection_entry(
+        &self,
+        movie_id: u64,
+        collection: &CollectionRef,
+        followed_parts: &HashMap<u64, Vec<CollectionPart>>,
+    ) -> CollectionEntry {
+        if !self.followed_collections.contains(&collection.id) {
+            return CollectionEntry {
+                id: collection.id,
+                name: collection.name.clone(),
+                part: None,
+                total: None,
+            };
+        }
+        if let Some(parts) = followed_parts.get(&collection.id) {
+            return collection_entry_from_parts(movie_id, collection, parts);
+        }
+        self.resolve_collection_entry(movie_id, collection).await
+    }
+
+    /// Позиция фильма в коллекции; при сбое загрузки состава возвращается коллекция без позиции.
+    async fn resolve_collection_entry(
+        &self,
+        movie_id: u64,
+        collection: &CollectionRef,
+    ) -> CollectionEntry {
+        match self.fetch_collection_parts(collection.id).await {
+            Ok(parts) => collection_entry_from_parts(movie_id, collection, &parts),
+            Err(err) => {
+                warn!(
+                    target: "tmdb",
+                    collection_id = collection.id,
+                    movie_id,
+                    error = %err,
+                    "Не удалось загрузить состав коллекции"
+                );
+                CollectionEntry {
+                    id: collection.id,
+                    name: collection.name.clone(),
+                    part: None,
+                    total: None,
+                }
+            }
+        }
     }
 
     async fn fetch_tv_details(&self, show_id: u64) -> Result<TvShowDetails, TmdbError> {
@@ -562,17 +1136,12 @@ impl TmdbClient {
 
         let request_factory = move || tv_request(client.clone(), url.clone(), api_key.clone());
 
-        let payload: TvShowDetailsResponse = self.fetch_json(request_factory).await?;
+        let payload: TvShowDetailsResponse = self
+            .fetch_json(request_factory)
+            .await
+            .map_err(|err| err.for_title(show_id))?;
 
-        Ok(TvShowDetails {
-            name: payload.name,
-            original_language: payload.original_language,
-            first_air_date: payload.first_air_date,
-            seasons: payload.seasons,
-            vote_average: payload.vote_average,
-            vote_count: payload.vote_count,
-            popularity: payload.popularity,
-        })
+        Ok(TvShowDetails::from(payload))
     }
 
     async fn fetch_tv_discover(
@@ -630,11 +1199,12 @@ impl TmdbClient {
         Ok(shows)
     }
 
-    pub async fn fetch_digital_release_date(
+    /// Цифровая дата приоритетных регионов, её регион и даты по всем регионам.
+    pub async fn fetch_digital_release(
         &self,
         movie_id: u64,
         today: NaiveDate,
-    ) -> Result<Option<NaiveDate>, TmdbError> {
+    ) -> Result<Option<DigitalReleaseDate>, TmdbError> {
         let url = format!("{TMDB_BASE_URL}/movie/{movie_id}/release_dates");
         let client = self.http.clone();
         let api_key = self.api_key.clone();
@@ -642,8 +1212,15 @@ impl TmdbClient {
         let request_factory =
             move || release_dates_request(client.clone(), url.clone(), api_key.clone());
 
-        let payload: ReleaseDatesResponse = self.fetch_json(request_factory).await?;
-        select_digital_release_date(&payload.results, today, &self.priority_regions)
+        let payload: ReleaseDatesResponse = self
+            .fetch_json(request_factory)
+            .await
+            .map_err(|err| err.for_title(movie_id))?;
+        Ok(select_digital_release(
+            &payload.results,
+            today,
+            &self.priority_regions,
+        ))
     }
 
     async fn fetch_discover_movies_for_region(
@@ -706,16 +1283,33 @@ impl TmdbClient {
         T: DeserializeOwned,
         F: Fn() -> RequestBuilder,
     {
-        let response = self.execute_with_retry(request_factory).await?;
+        let endpoint = request_endpoint(&request_factory);
+        let response = self.execute_with_retry(&endpoint, request_factory).await?;
         let status = response.status();
+        let body = response.text().await.map_err(|source| TmdbError::Http {
+            endpoint: endpoint.clone(),
+            source: source.without_url(),
+        })?;
         if !status.is_success() {
-            return Err(TmdbError::UnexpectedStatus(status));
+            return Err(TmdbError::UnexpectedStatus {
+                endpoint,
+                status,
+                body: body_excerpt(&body),
+            });
         }
 
-        Ok(response.json().await?)
+        serde_json::from_str(&body).map_err(|source| TmdbError::Decode {
+            endpoint,
+            body: body_excerpt(&body),
+            source,
+        })
     }
 
-    async fn execute_with_retry<F>(&self, request_factory: F) -> Result<Response, TmdbError>
+    async fn execute_with_retry<F>(
+        &self,
+        endpoint: &str,
+        request_factory: F,
+    ) -> Result<Response, TmdbError>
     where
         F: Fn() -> RequestBuilder,
     {
@@ -745,11 +1339,18 @@ impl TmdbClient {
 
                     break;
                 }
-                Err(err) => return Err(TmdbError::Http(err)),
+                Err(err) => {
+                    return Err(TmdbError::Http {
+                        endpoint: endpoint.to_string(),
+                        source: err.without_url(),
+                    });
+                }
             }
         }
 
-        Err(TmdbError::RetryLimitExceeded)
+        Err(TmdbError::RetryLimitExceeded {
+            endpoint: endpoint.to_string(),
+        })
     }
 }
 
@@ -766,7 +1367,54 @@ enum TvDiscoverFilter {
     AirDate,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChangeFeed {
+    Movie,
+    Tv,
+}
+
+impl ChangeFeed {
+    fn path(self) -> &'static str {
+        match self {
+            Self::Movie => "movie",
+            Self::Tv => "tv",
+        }
+    }
+
+    fn relevant_keys(self) -> &'static [&'static str] {
+        match self {
+            Self::Movie => &MOVIE_CHANGE_KEYS,
+            Self::Tv => &TV_CHANGE_KEYS,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChangesResponse {
+    total_pages: u32,
+    #[serde(default)]
+    results: Vec<ChangeEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChangeEntry {
+    id: u64,
+    adult: Option<bool>,
+}
+
+/// Изменения тайтла из `append_to_response=changes`.
+#[derive(Debug, Default, Deserialize)]
+struct TitleChangesResponse {
+    #[serde(default)]
+    changes: Vec<TitleChange>,
+}
+
 #[derive(Debug, Deserialize)]
+struct TitleChange {
+    key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
 struct DiscoverMovie {
     id: u64,
     title: String,
@@ -798,6 +1446,12 @@ struct DiscoverTvShow {
 
 #[derive(Debug, Deserialize)]
 struct MovieDetailsResponse {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    release_date: Option<String>,
+    #[serde(default)]
+    original_language: Option<String>,
     homepage: Option<String>,
     imdb_id: Option<String>,
     #[serde(rename = "watch/providers")]
@@ -811,6 +1465,35 @@ struct MovieDetailsResponse {
     #[serde(default)]
     runtime: Option<u32>,
     popularity: Option<f64>,
+    #[serde(default)]
+    belongs_to_collection: Option<CollectionRef>,
+    #[serde(default)]
+    changes: TitleChangesResponse,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CollectionRef {
+    id: u64,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CollectionResponse {
+    #[serde(default)]
+    parts: Vec<CollectionPart>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CollectionPart {
+    id: u64,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    release_date: Option<String>,
+    #[serde(default)]
+    original_language: String,
+    #[serde(default)]
+    popularity: f64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -824,6 +1507,8 @@ struct TvShowDetailsResponse {
     vote_average: Option<f64>,
     vote_count: Option<u32>,
     popularity: Option<f64>,
+    #[serde(default)]
+    changes: TitleChangesResponse,
 }
 
 #[derive(Debug, Deserialize)]
@@ -879,6 +1564,55 @@ pub struct MovieDetails {
     genres: Vec<Genre>,
     runtime: Option<u32>,
     popularity: Option<f64>,
+    collection: Option<CollectionRef>,
+}
+
+impl From<MovieDetailsResponse> for MovieDetails {
+    fn from(payload: MovieDetailsResponse) -> Self {
+        let watch_providers = payload
+            .watch_providers
+            .map(|providers| collect_providers(providers.results))
+            .unwrap_or_default();
+
+        Self {
+            homepage: payload.homepage,
+            imdb_id: payload.imdb_id,
+            watch_providers,
+            production_countries: payload.production_countries,
+            vote_average: payload.vote_average,
+            vote_count: payload.vote_count,
+            genres: payload.genres,
+            runtime: payload.runtime,
+            popularity: payload.popularity,
+            collection: payload.belongs_to_collection,
+        }
+    }
+}
+
+/// Счётчики фильтров movie-пайплайна для сводных логов.
+#[derive(Debug, Default)]
+struct MovieFilterStats {
+    details_enriched: usize,
+    logged_candidates: usize,
+    skipped_missing_date: usize,
+    skipped_missing_original_date: usize,
+    skipped_missing_digital_date: usize,
+    skipped_outside_window: usize,
+    skipped_old: usize,
+    after_basic_filter: usize,
+    skipped_by_imdb: usize,
+    skipped_by_country: usize,
+    skipped_by_genre: usize,
+    skipped_by_quality: usize,
+    skipped_by_runtime: usize,
+    skipped_by_other: usize,
+}
+
+#[derive(Debug, Default)]
+struct TvFilterStats {
+    skipped_missing_date: usize,
+    skipped_outside_window: usize,
+    skipped_quality: usize,
 }
 
 #[derive(Debug)]
@@ -892,6 +1626,20 @@ struct TvShowDetails {
     popularity: Option<f64>,
 }
 
+impl From<TvShowDetailsResponse> for TvShowDetails {
+    fn from(payload: TvShowDetailsResponse) -> Self {
+        Self {
+            name: payload.name,
+            original_language: payload.original_language,
+            first_air_date: payload.first_air_date,
+            seasons: payload.seasons,
+            vote_average: payload.vote_average,
+            vote_count: payload.vote_count,
+            popularity: payload.popularity,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ProductionCountry {
     #[serde(rename = "iso_3166_1")]
@@ -960,6 +1708,50 @@ fn release_dates_request(client: Client, url: String, api_key: String) -> Reques
     client.get(url).query(&query)
 }
 
+fn collection_request(client: Client, url: String, api_key: String) -> RequestBuilder {
+    let query = vec![("api_key".to_string(), api_key)];
+
+    client.get(url).query(&query)
+}
+
+fn title_changes_request(
+    client: Client,
+    url: String,
+    api_key: String,
+    append: &str,
+    start: String,
+    end: String,
+) -> RequestBuilder {
+    let query = vec![
+        ("api_key".to_string(), api_key),
+        ("append_to_response".to_string(), append.to_string()),
+        ("start_date".to_string(), start),
+        ("end_date".to_string(), end),
+    ];
+
+    client.get(url).query(&query)
+}
+
+fn changes_request(
+    client: Client,
+    url: String,
+    api_key: String,
+    start: String,
+    end: String,
+    page: Option<u32>,
+) -> RequestBuilder {
+    let mut query = vec![
+        ("api_key".to_string(), api_key),
+        ("start_date".to_string(), start),
+        ("end_date".to_string(), end),
+    ];
+    if let Some(page) = page {
+        query.push(("page".to_string(), page.to_string()));
+    }
+
+    client.get(url).query(&query)
+}
+
 fn discover_tv_request(
     client: Client,
     url: String,
@@ -1006,11 +1798,11 @@ fn collect_providers(regions: HashMap<String, WatchProviderRegion>) -> Vec<Strin
     providers.into_iter().collect()
 }
 
-fn select_digital_release_date(
+fn select_digital_release(
     results: &[ReleaseDatesRegion],
     today: NaiveDate,
     priority_regions: &[String],
-) -> Result<Option<NaiveDate>, TmdbError> {
+) -> Option<DigitalReleaseDate> {
     let mut by_region: HashMap<String, Vec<NaiveDate>> = HashMap::new();
 
     for region in results {
@@ -1030,16 +1822,38 @@ fn select_digital_release_date(
         }
     }
 
+    let regional_dates: BTreeMap<String, NaiveDate> = by_region
+        .iter()
+        .filter_map(|(region, dates)| {
+            select_preferred_date(dates.iter().copied(), today).map(|date| (region.clone(), date))
+        })
+        .collect();
+
     for region in priority_regions {
         if let Some(dates) = by_region.get(region) {
-            return Ok(select_preferred_date(dates.iter().copied(), today));
+            return select_preferred_date(dates.iter().copied(), today).map(|date| {
+                DigitalReleaseDate {
+                    date,
+                    region: Some(region.clone()),
+                    regional_dates,
+                }
+            });
         }
     }
 
-    Ok(select_preferred_date(
-        by_region.into_values().flatten(),
-        today,
-    ))
+    let date = select_preferred_date(by_region.values().flatten().copied(), today)?;
+    let mut regions: Vec<&String> = by_region
+        .iter()
+        .filter(|(_, dates)| dates.contains(&date))
+        .map(|(region, _)| region)
+        .collect();
+    regions.sort();
+
+    Some(DigitalReleaseDate {
+        date,
+        region: regions.first().map(|region| (*region).clone()),
+        regional_dates,
+    })
 }
 
 fn resolve_priority_regions() -> Vec<String> {
@@ -1059,6 +1873,41 @@ fn resolve_priority_regions_from(raw: Option<&str>) -> Vec<String> {
         })
 }
 
+fn resolve_error_budget() -> usize {
+    resolve_error_budget_from(env::var(ERROR_BUDGET_ENV).ok().as_deref())
+}
+
+fn resolve_error_budget_from(raw: Option<&str>) -> usize {
+    raw.and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_ERROR_BUDGET)
+}
+
+fn check_error_budget(failures: usize, budget: usize) -> Result<(), TmdbError> {
+    if failures > budget {
+        return Err(TmdbError::ErrorBudgetExceeded { failures, budget });
+    }
+    Ok(())
+}
+
+/// Путь запроса без query-параметров, чтобы в ошибки не попадал `api_key`.
+fn request_endpoint<F>(request_factory: &F) -> String
+where
+    F: Fn() -> RequestBuilder,
+{
+    request_factory()
+        .build()
+        .map(|request| request.url().path().to_string())
+        .unwrap_or_else(|_| "<unknown>".to_string())
+}
+
+fn body_excerpt(body: &str) -> String {
+    let trimmed = body.trim();
+    match trimmed.char_indices().nth(BODY_EXCERPT_CHARS) {
+        Some((index, _)) => format!("{}…", &trimmed[..index]),
+        None => trimmed.to_string(),
+    }
+}
+
 fn parse_priority_regions(raw: &str) -> Vec<String> {
     let mut unique = HashSet::new();
     raw.split(',')
@@ -1143,6 +1992,236 @@ fn movie_discover_window(window: ReleaseWindow) -> ReleaseWindow {
     window
 }
 
+/// Окно для `/changes`: от курсора до конца прогона, но не длиннее лимита TMDB.
+fn changes_window(since: DateTime<Utc>, end: DateTime<Utc>) -> ReleaseWindow {
+    let earliest = end - chrono::Duration::days(MAX_CHANGES_WINDOW_DAYS);
+    ReleaseWindow {
+        start: since.clamp(earliest, end),
+        end,
+    }
+}
+
+/// Окно ленты изменений по суткам UTC: курсор сдвигается на границу суток.
+fn change_days(window: ReleaseWindow) -> Vec<ReleaseWindow> {
+    let mut days = Vec::new();
+    let mut start = window.start;
+    while start < window.end {
+        let next_midnight = (start.date_naive() + chrono::Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .expect("полночь существует")
+            .and_utc();
+        let end = next_midnight.min(window.end);
+        days.push(ReleaseWindow { start, end });
+        start = end;
+    }
+    days
+}
+
+/// Фильм-кандидат ленты изменений из ответа `/movie/{id}`.
+fn movie_candidate(movie_id: u64, payload: MovieDetailsResponse) -> (DiscoverMovie, MovieDetails) {
+    let movie = DiscoverMovie {
+        id: movie_id,
+        title: payload.title.clone().unwrap_or_default(),
+        original_release_date: payload.release_date.clone().unwrap_or_default(),
+        release_date: payload.release_date.clone().unwrap_or_default(),
+        original_language: payload.original_language.clone().unwrap_or_default(),
+        popularity: payload.popularity.unwrap_or_default(),
+    };
+
+    (movie, MovieDetails::from(payload))
+}
+
+fn has_relevant_change(feed: ChangeFeed, changes: &TitleChangesResponse) -> bool {
+    changes
+        .changes
+        .iter()
+        .any(|change| feed.relevant_keys().contains(&change.key.as_str()))
+}
+
+fn collect_tv_events(
+    show_id: u64,
+    details: &TvShowDetails,
+    window: ReleaseWindow,
+    stats: &mut TvFilterStats,
+    report: &mut FilterReport,
+) -> Vec<TvEvent> {
+    let mut events = Vec::new();
+    let mut has_premiere = false;
+    let quality_ok =
+        passes_quality_filters(details.vote_average, details.vote_count, details.popularity);
+    let first_air_date = details.first_air_date.as_deref();
+
+    let verdict = match first_air_date.and_then(parse_optional_release_date) {
+        None => {
+            stats.skipped_missing_date += 1;
+            MovieFilterVerdict::rejected(MovieRejectionReason::Other, "missing_first_air_date")
+        }
+        Some(date) if !date_in_window(date, window) => {
+            stats.skipped_outside_window += 1;
+            MovieFilterVerdict::rejected(MovieRejectionReason::Other, "air_date_outside_window")
+        }
+        Some(_) if !quality_ok => {
+            stats.skipped_quality += 1;
+            MovieFilterVerdict::rejected(
+                MovieRejectionReason::RatingVoteCount,
+                "quality_filters_failed",
+            )
+        }
+        Some(date) => {
+            events.push(TvEvent {
+                show_id,
+                show_name: details.name.clone(),
+                original_language: details.original_language.clone(),
+                event_date: date,
+                kind: TvEventKind::Premiere,
+                vote_average: details.vote_average,
+                vote_count: details.vote_count,
+                popularity: details.popularity,
+            });
+            has_premiere = true;
+            MovieFilterVerdict::accepted()
+        }
+    };
+    report.push(FilterRecord::tv(
+        show_id,
+        details,
+        CandidateKind::TvPremiere,
+        first_air_date,
+        first_air_date.and_then(parse_optional_release_date),
+        verdict,
+    ));
+
+    for season in details.seasons.iter() {
+        let air_date = season.air_date.as_deref();
+        let parsed = air_date.and_then(parse_optional_release_date);
+        let verdict = match parsed {
+            None => {
+                stats.skipped_missing_date += 1;
+                MovieFilterVerdict::rejected(MovieRejectionReason::Other, "missing_air_date")
+            }
+            Some(date) if !date_in_window(date, window) => {
+                stats.skipped_outside_window += 1;
+                MovieFilterVerdict::rejected(MovieRejectionReason::Other, "air_date_outside_window")
+            }
+            Some(_) if !quality_ok => {
+                stats.skipped_quality += 1;
+                MovieFilterVerdict::rejected(
+                    MovieRejectionReason::RatingVoteCount,
+                    "quality_filters_failed",
+                )
+            }
+            Some(_) if has_premiere && season.season_number == 1 => MovieFilterVerdict::rejected(
+                MovieRejectionReason::Other,
+                "season_covered_by_premiere",
+            ),
+            Some(date) => {
+                events.push(TvEvent {
+                    show_id,
+                    show_name: details.name.clone(),
+                    original_language: details.original_language.clone(),
+                    event_date: date,
+                    kind: TvEventKind::Season {
+                        season_number: season.season_number,
+                    },
+                    vote_average: details.vote_average,
+                    vote_count: details.vote_count,
+                    popularity: details.popularity,
+                });
+                MovieFilterVerdict::accepted()
+            }
+        };
+        report.push(FilterRecord::tv(
+            show_id,
+            details,
+            CandidateKind::TvSeason,
+            air_date,
+            parsed,
+            verdict,
+        ));
+    }
+
+    events
+}
+
+fn collection_entry_from_parts(
+    movie_id: u64,
+    collection: &CollectionRef,
+    parts: &[CollectionPart],
+) -> CollectionEntry {
+    let (part, total) = collection_position(movie_id, parts)
+        .map(|part| (Some(part), Some(parts.len())))
+        .unwrap_or((None, None));
+    CollectionEntry {
+        id: collection.id,
+        name: collection.name.clone(),
+        part,
+        total,
+    }
+}
+
+/// Уже вышедшие части отслеживаемых коллекций, которых нет среди кандидатов discover.
+fn followed_collection_candidates(
+    followed_parts: &HashMap<u64, Vec<CollectionPart>>,
+    seen_ids: &HashSet<u64>,
+    today: NaiveDate,
+) -> Vec<DiscoverMovie> {
+    let mut seen_ids = seen_ids.clone();
+    let mut candidates = Vec::new();
+    for parts in followed_parts.values() {
+        for part in parts {
+            let Some(release_date) = part
+                .release_date
+                .as_deref()
+                .and_then(parse_optional_release_date)
+            else {
+                continue;
+            };
+            if release_date > today || !seen_ids.insert(part.id) {
+                continue;
+            }
+            let release_date = part.release_date.clone().unwrap_or_default();
+            candidates.push(DiscoverMovie {
+                id: part.id,
+                title: part.title.clone(),
+                original_release_date: release_date.clone(),
+                release_date,
+                original_language: part.original_language.clone(),
+                popularity: part.popularity,
+            });
+        }
+    }
+    candidates
+}
+
+/// Номер фильма (с единицы) среди частей коллекции, упорядоченных по дате выхода;
+/// части без даты считаются ещё не вышедшими и идут в конце.
+fn collection_position(movie_id: u64, parts: &[CollectionPart]) -> Option<usize> {
+    let mut ordered: Vec<(Option<NaiveDate>, u64)> = parts
+        .iter()
+        .map(|part| {
+            let date = part
+                .release_date
+                .as_deref()
+                .and_then(parse_optional_release_date);
+            (date, part.id)
+        })
+        .collect();
+    ordered.sort_by(|(left_date, left_id), (right_date, right_id)| {
+        match (left_date, right_date) {
+            (Some(left), Some(right)) => left.cmp(right),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+        .then_with(|| left_id.cmp(right_id))
+    });
+
+    ordered
+        .iter()
+        .position(|(_, id)| *id == movie_id)
+        .map(|index| index + 1)
+}
+
 fn limit_total_pages(total_pages: u32) -> u32 {
     total_pages.clamp(1, MAX_DISCOVER_PAGES)
 }
@@ -1165,20 +2244,29 @@ pub fn is_relevant_release(details: &MovieDetails) -> bool {
     movie_filter_verdict(details).is_accepted()
 }
 
+/// Решение фильтров по кандидату: причина отказа и короткая машинная пометка.
 #[derive(Debug, Clone, Copy)]
-struct MovieFilterVerdict {
+pub struct MovieFilterVerdict {
     rejection_reason: Option<MovieRejectionReason>,
     note: &'static str,
 }
 
 impl MovieFilterVerdict {
-    fn accepted() -> Self {
+    pub fn accepted() -> Self {
         Self {
             rejection_reason: None,
             note: "passed_all_filters",
         }
     }
 
+    /// Фильм не прошёл общие фильтры, но входит в отслеживаемую коллекцию.
+    pub fn followed_collection() -> Self {
+        Self {
+            rejection_reason: None,
+            note: "followed_collection",
+        }
+    }
+
     fn rejected(reason: MovieRejectionReason, note: &'static str) -> Self {
         Self {
             rejection_reason: Some(reason),
@@ -1186,11 +2274,15 @@ impl MovieFilterVerdict {
         }
     }
 
-    fn is_accepted(self) -> bool {
+    pub fn is_accepted(self) -> bool {
         self.rejection_reason.is_none()
     }
 
-    fn kind(self) -> &'static str {
+    pub fn note(self) -> &'static str {
+        self.note
+    }
+
+    pub fn kind(self) -> &'static str {
         match self.rejection_reason {
             None => "accepted",
             Some(reason) => reason.as_str(),
@@ -1337,6 +2429,7 @@ mod tests {
             }],
             runtime: Some(95),
             popularity: Some(1.0),
+            collection: None,
         };
 
         transform(&mut details);
@@ -1546,8 +2639,8 @@ mod tests {
         ];
 
         let selected =
-            select_digital_release_date(&results, today, &["RU".to_string(), "US".to_string()])
-                .expect("дата выбирается");
+            select_digital_release(&results, today, &["RU".to_string(), "US".to_string()])
+                .map(|digital| digital.date);
         assert_eq!(
             selected,
             Some(NaiveDate::from_ymd_opt(2024, 2, 5).expect("валидная дата"))
@@ -1562,14 +2655,40 @@ mod tests {
             make_region("CA", vec![make_release_entry("2024-01-05", 4)]),
         ];
 
-        let selected = select_digital_release_date(&results, today, &default_priority_regions())
-            .expect("дата выбирается");
+        let selected = select_digital_release(&results, today, &default_priority_regions())
+            .map(|digital| digital.date);
         assert_eq!(
             selected,
             Some(NaiveDate::from_ymd_opt(2024, 1, 5).expect("валидная дата"))
         );
     }
 
+    #[test]
+    fn digital_release_reports_source_region() {
+        let today = NaiveDate::from_ymd_opt(2026, 2, 14).expect("валидная дата");
+        let results = vec![
+            make_region("BR", vec![make_release_entry("2024-01-02", 4)]),
+            make_region("GB", vec![make_release_entry("2024-01-03", 4)]),
+            make_region("US", vec![make_release_entry("2024-01-01", 4)]),
+        ];
+
+        let from_priority =
+            select_digital_release(&results, today, &default_priority_regions()).expect("дата");
+        let fallback = select_digital_release(&results, today, &["JP".to_string()]).expect("дата");
+
+        assert_eq!(from_priority.region.as_deref(), Some("US"));
+        assert_eq!(fallback.region.as_deref(), Some("GB"));
+        assert_eq!(from_priority.regional_dates.len(), 3);
+        assert_eq!(
+            from_priority.regional_dates.get("BR"),
+            NaiveDate::from_ymd_opt(2024, 1, 2).as_ref()
+        );
+        assert_eq!(
+            fallback.date,
+            NaiveDate::from_ymd_opt(2024, 1, 3).expect("валидная дата")
+        );
+    }
+
     #[test]
     fn digital_release_date_ignores_non_digital_entries() {
         let today = NaiveDate::from_ymd_opt(2026, 2, 14).expect("валидная дата");
@@ -1581,8 +2700,8 @@ mod tests {
             ],
         )];
 
-        let selected = select_digital_release_date(&results, today, &default_priority_regions())
-            .expect("дата выбирается");
+        let selected = select_digital_release(&results, today, &default_priority_regions())
+            .map(|digital| digital.date);
         assert_eq!(
             selected,
             Some(NaiveDate::from_ymd_opt(2024, 1, 4).expect("валидная дата"))
@@ -1630,8 +2749,8 @@ mod tests {
             ],
         )];
 
-        let selected = select_digital_release_date(&results, today, &default_priority_regions())
-            .expect("дата выбирается");
+        let selected = select_digital_release(&results, today, &default_priority_regions())
+            .map(|digital| digital.date);
         assert_eq!(selected, Some(past));
     }
 
@@ -1648,8 +2767,8 @@ mod tests {
             ],
         )];
 
-        let selected = select_digital_release_date(&results, today, &default_priority_regions())
-            .expect("дата выбирается");
+        let selected = select_digital_release(&results, today, &default_priority_regions())
+            .map(|digital| digital.date);
         assert_eq!(selected, Some(first_future));
     }
 
@@ -1754,6 +2873,131 @@ mod tests {
         assert!(!date_in_window(outside, window));
     }
 
+    #[test]
+    fn changes_window_starts_at_cursor() {
+        let end = DateTime::parse_from_rfc3339("2026-03-20T12:00:00Z")
+            .expect("валидная дата")
+            .with_timezone(&Utc);
+        let since = end - chrono::Duration::days(2);
+
+        let window = changes_window(since, end);
+
+        assert_eq!(window.start, since);
+        assert_eq!(window.end, end);
+    }
+
+    #[test]
+    fn changes_window_is_clamped_to_tmdb_limit() {
+        let end = DateTime::parse_from_rfc3339("2026-03-20T12:00:00Z")
+            .expect("валидная дата")
+            .with_timezone(&Utc);
+
+        let stale = changes_window(end - chrono::Duration::days(60), end);
+        let future = changes_window(end + chrono::Duration::days(1), end);
+
+        assert_eq!(
+            stale.start,
+            end - chrono::Duration::days(MAX_CHANGES_WINDOW_DAYS)
+        );
+        assert_eq!(future.start, end);
+    }
+
+    #[test]
+    fn change_days_split_window_at_utc_midnight() {
+        let start = DateTime::parse_from_rfc3339("2026-03-18T15:00:00Z")
+            .expect("валидная дата")
+            .with_timezone(&Utc);
+        let end = DateTime::parse_from_rfc3339("2026-03-20T12:00:00Z")
+            .expect("валидная дата")
+            .with_timezone(&Utc);
+
+        let days = change_days(ReleaseWindow { start, end });
+
+        let bounds: Vec<_> = days
+            .iter()
+            .map(|day| (day.start.to_rfc3339(), day.end.to_rfc3339()))
+            .collect();
+        assert_eq!(
+            bounds,
+            vec![
+                (
+                    "2026-03-18T15:00:00+00:00".to_string(),
+                    "2026-03-19T00:00:00+00:00".to_string()
+                ),
+                (
+                    "2026-03-19T00:00:00+00:00".to_string(),
+                    "2026-03-20T00:00:00+00:00".to_string()
+                ),
+                (
+                    "2026-03-20T00:00:00+00:00".to_string(),
+                    "2026-03-20T12:00:00+00:00".to_string()
+                ),
+            ]
+        );
+        assert!(change_days(ReleaseWindow { start: end, end }).is_empty());
+    }
+
+    #[test]
+    fn relevant_change_keys_depend_on_feed() {
+        let changes = |keys: &[&str]| TitleChangesResponse {
+            changes: keys
+                .iter()
+                .map(|key| TitleChange {
+                    key: key.to_string(),
+                })
+                .collect(),
+        };
+        let release_dates = changes(&["title", "release_dates"]);
+        let seasons = changes(&["season"]);
+
+        assert!(has_relevant_change(ChangeFeed::Movie, &release_dates));
+        assert!(!has_relevant_change(ChangeFeed::Movie, &seasons));
+        assert!(has_relevant_change(ChangeFeed::Tv, &seasons));
+        assert!(!has_relevant_change(ChangeFeed::Tv, &release_dates));
+    }
+
+    #[test]
+    fn tv_events_skip_first_season_when_premiere_is_announced() {
+        let end = DateTime::parse_from_rfc3339("2026-03-20T12:00:00Z")
+            .expect("валидная дата")
+            .with_timezone(&Utc);
+        let window = ReleaseWindow {
+            start: end - chrono::Duration::days(7),
+            end,
+        };
+        let details = TvShowDetails {
+            name: "Сериал".to_string(),
+            original_language: "en".to_string(),
+            first_air_date: Some("2026-03-18".to_string()),
+            seasons: vec![
+                TvSeason {
+                    air_date: Some("2026-03-18".to_string()),
+                    season_number: 1,
+                },
+                TvSeason {
+                    air_date: Some("2026-03-19".to_string()),
+                    season_number: 2,
+                },
+            ],
+            vote_average: None,
+            vote_count: None,
+            popularity: None,
+        };
+        let mut stats = TvFilterStats::default();
+        let mut report = FilterReport::default();
+
+        let events = collect_tv_events(7, &details, window, &mut stats, &mut report);
+
+        let keys: Vec<String> = events.iter().map(TvEvent::event_key).collect();
+        assert_eq!(keys, vec!["tv:7:premiere", "tv:7:season:2"]);
+        assert_eq!(report.records.len(), 3);
+        assert_eq!(report.rejected(), 1);
+        assert_eq!(
+            report.records[1].verdict.note(),
+            "season_covered_by_premiere"
+        );
+    }
+
     #[test]
     fn discover_pagination_is_limited() {
         assert_eq!(limit_total_pages(1), 1);
@@ -1814,4 +3058,101 @@ mod tests {
         assert!(is_recent_original_release(2025, current_year));
         assert!(!is_recent_original_release(2024, current_year));
     }
+
+    #[test]
+    fn collection_position_orders_parts_by_release_date() {
+        let part = |id, release_date: Option<&str>| CollectionPart {
+            id,
+            release_date: release_date.map(str::to_string),
+            ..CollectionPart::default()
+        };
+        let parts = vec![
+            part(30, Some("2019-05-01")),
+            part(50, None),
+            part(10, Some("2010-01-01")),
+            part(40, Some("2026-02-01")),
+            part(20, Some("")),
+        ];
+
+        assert_eq!(collection_position(10, &parts), Some(1));
+        assert_eq!(collection_position(40, &parts), Some(3));
+        assert_eq!(collection_position(50, &parts), Some(5));
+        assert_eq!(collection_position(99, &parts), None);
+    }
+
+    #[test]
+    fn followed_collection_candidates_skip_unreleased_and_known_parts() {
+        let part = |id, release_date: Option<&str>| CollectionPart {
+            id,
+            title: format!("Part {id}"),
+            release_date: release_date.map(str::to_string),
+            ..CollectionPart::default()
+        };
+        let followed_parts = HashMap::from([(
+            7,
+            vec![
+                part(1, Some("2001-05-01")),
+                part(2, Some("2026-03-01")),
+                part(3, Some("2027-01-01")),
+                part(4, None),
+            ],
+        )]);
+        let seen_ids = HashSet::from([2]);
+        let today = NaiveDate::from_ymd_opt(2026, 3, 20).expect("валидная дата");
+
+        let candidates = followed_collection_candidates(&followed_parts, &seen_ids, today);
+
+        let ids: Vec<u64> = candidates.iter().map(|movie| movie.id).collect();
+        assert_eq!(ids, vec![1]);
+        assert_eq!(candidates[0].original_release_date, "2001-05-01");
+    }
+
+    #[test]
+    fn body_excerpt_truncates_on_char_boundary() {
+        let body = "ы".repeat(BODY_EXCERPT_CHARS + 10);
+
+        let excerpt = body_excerpt(&body);
+
+        assert_eq!(excerpt.chars().count(), BODY_EXCERPT_CHARS + 1);
+        assert!(excerpt.ends_with('…'));
+        assert_eq!(
+            body_excerpt("  {\"status_code\":34}  "),
+            "{\"status_code\":34}"
+        );
+    }
+
+    #[test]
+    fn title_error_carries_endpoint_id_and_body() {
+        let err = TmdbError::UnexpectedStatus {
+            endpoint: "/3/movie/77".to_string(),
+            status: StatusCode::NOT_FOUND,
+            body: "{\"status_code\":34}".to_string(),
+        }
+        .for_title(77);
+
+        let message = err.to_string();
+
+        assert_eq!(err.title_id(), Some(77));
+        assert!(message.contains("TMDB id 77"));
+        assert!(message.contains("/3/movie/77"));
+        assert!(message.contains("404"));
+        assert!(message.contains("status_code"));
+    }
+
+    #[test]
+    fn error_budget_allows_failures_up_to_limit() {
+        assert!(check_error_budget(3, 3).is_ok());
+        assert!(matches!(
+            check_error_budget(4, 3),
+            Err(TmdbError::ErrorBudgetExceeded {
+                failures: 4,
+                budget: 3
+            })
+        ));
+        assert_eq!(resolve_error_budget_from(Some(" 25 ")), 25);
+        assert_eq!(
+            resolve_error_budget_from(Some("много")),
+            DEFAULT_ERROR_BUDGET
+        );
+    }
 }
@@ -0,0 +1,358 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde::ser::SerializeStruct;
+
+use tracing::warn;
+
+use super::{
+    DigitalReleaseDate, DiscoverMovie, MovieDetails, MovieFilterVerdict, TmdbError, TvShowDetails,
+};
+
+const CSV_HEADER: &str = "kind,id,title,release_date,original_language,discover_regions,production_countries,genres,vote_average,vote_count,popularity,runtime,imdb_id,verdict,reason,digital_release_date,source_region";
+
+/// Тип кандидата в отчёте фильтрации.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateKind {
+    Movie,
+    TvPremiere,
+    TvSeason,
+    /// Сериал целиком: используется для сбоев до разбора премьер и сезонов.
+    TvShow,
+}
+
+impl CandidateKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Movie => "movie",
+            Self::TvPremiere => "tv_premiere",
+            Self::TvSeason => "tv_season",
+            Self::TvShow => "tv_show",
+        }
+    }
+}
+
+/// Запись о решении фильтров по одному кандидату (фильму или событию сериала).
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterRecord {
+    pub kind: CandidateKind,
+    pub id: u64,
+    pub title: String,
+    pub release_date: Option<String>,
+    pub original_language: String,
+    pub discover_regions: Vec<String>,
+    pub production_countries: Vec<String>,
+    pub genres: Vec<String>,
+    pub vote_average: Option<f64>,
+    pub vote_count: Option<u32>,
+    pub popularity: Option<f64>,
+    pub runtime: Option<u32>,
+    pub imdb_id: Option<String>,
+    #[serde(flatten)]
+    pub verdict: MovieFilterVerdict,
+    pub digital_release_date: Option<NaiveDate>,
+    pub source_region: Option<String>,
+}
+
+impl FilterRecord {
+    pub(super) fn movie(
+        movie: &DiscoverMovie,
+        discover_regions: &[String],
+        details: Option<&MovieDetails>,
+        verdict: MovieFilterVerdict,
+        digital: Option<&DigitalReleaseDate>,
+    ) -> Self {
+        Self {
+            kind: CandidateKind::Movie,
+            id: movie.id,
+            title: movie.title.clone(),
+            release_date: (!movie.release_date.trim().is_empty())
+                .then(|| movie.release_date.clone()),
+            original_language: movie.original_language.clone(),
+            discover_regions: discover_regions.to_vec(),
+            production_countries: details
+                .map(|details| {
+                    details
+                        .production_countries
+                        .iter()
+                        .map(|country| country.code.clone())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            genres: details
+                .map(|details| {
+                    details
+                        .genres
+                        .iter()
+                        .map(|genre| genre.name.clone())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            vote_average: details.and_then(|details| details.vote_average),
+            vote_count: details.and_then(|details| details.vote_count),
+            popularity: details
+                .and_then(|details| details.popularity)
+                .or(Some(movie.popularity)),
+            runtime: details.and_then(|details| details.runtime),
+            imdb_id: details
+                .and_then(|details| details.imdb_id.clone())
+                .filter(|id| !id.trim().is_empty()),
+            verdict,
+            digital_release_date: digital.map(|digital| digital.date),
+            source_region: digital.and_then(|digital| digital.region.clone()),
+        }
+    }
+
+    pub(super) fn tv(
+        show_id: u64,
+        details: &TvShowDetails,
+        kind: CandidateKind,
+        air_date: Option<&str>,
+        event_date: Option<NaiveDate>,
+        verdict: MovieFilterVerdict,
+    ) -> Self {
+        Self {
+            kind,
+            id: show_id,
+            title: details.name.clone(),
+            release_date: air_date.map(str::to_string),
+            original_language: details.original_language.clone(),
+            discover_regions: Vec::new(),
+            production_countries: Vec::new(),
+            genres: Vec::new(),
+            vote_average: details.vote_average,
+            vote_count: details.vote_count,
+            popularity: details.popularity,
+            runtime: None,
+            imdb_id: None,
+            verdict,
+            digital_release_date: event_date,
+            source_region: None,
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        let fields = [
+            self.kind.as_str().to_string(),
+            self.id.to_string(),
+            self.title.clone(),
+            self.release_date.clone().unwrap_or_default(),
+            self.original_language.clone(),
+            self.discover_regions.join(";"),
+            self.production_countries.join(";"),
+            self.genres.join(";"),
+            optional_to_string(self.vote_average),
+            optional_to_string(self.vote_count),
+            optional_to_string(self.popularity),
+            optional_to_string(self.runtime),
+            self.imdb_id.clone().unwrap_or_default(),
+            self.verdict.kind().to_string(),
+            self.verdict.note.to_string(),
+            optional_to_string(self.digital_release_date),
+            self.source_region.clone().unwrap_or_default(),
+        ];
+
+        fields
+            .iter()
+            .map(|field| escape_csv(field))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Тайтл, пропущенный из-за ошибки TMDB (404, битый JSON, исчерпанные повторы).
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemFailure {
+    pub kind: CandidateKind,
+    pub id: u64,
+    pub error: String,
+}
+
+impl ItemFailure {
+    /// Строка CSV с вердиктом `failed` и текстом ошибки вместо причины.
+    fn csv_row(&self) -> String {
+        CSV_HEADER
+            .split(',')
+            .map(|column| match column {
+                "kind" => self.kind.as_str().to_string(),
+                "id" => self.id.to_string(),
+                "verdict" => "failed".to_string(),
+                "reason" => escape_csv(&self.error),
+                _ => String::new(),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Полный отчёт фильтрации за прогон: по записи на каждого кандидата.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FilterReport {
+    pub records: Vec<FilterRecord>,
+    pub failures: Vec<ItemFailure>,
+}
+
+impl FilterReport {
+    pub fn push(&mut self, record: FilterRecord) {
+        self.records.push(record);
+    }
+
+    /// Фиксирует сбой по отдельному тайтлу; сам тайтл в прогон не попадает.
+    pub fn record_failure(&mut self, kind: CandidateKind, id: u64, error: &TmdbError) {
+        warn!(
+            target: "tmdb",
+            kind = kind.as_str(),
+            id,
+            error = %error,
+            "Тайтл пропущен из-за ошибки TMDB"
+        );
+        self.failures.push(ItemFailure {
+            kind,
+            id,
+            error: error.to_string(),
+        });
+    }
+
+    pub fn extend(&mut self, other: FilterReport) {
+        self.records.extend(other.records);
+        self.failures.extend(other.failures);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty() && self.failures.is_empty()
+    }
+
+    pub fn accepted(&self) -> usize {
+        self.records
+            .iter()
+            .filter(|record| record.verdict.is_accepted())
+            .count()
+    }
+
+    pub fn rejected(&self) -> usize {
+        self.records.len() - self.accepted()
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut lines = Vec::with_capacity(self.records.len() + 1);
+        lines.push(CSV_HEADER.to_string());
+        lines.extend(self.records.iter().map(FilterRecord::csv_row));
+        lines.extend(self.failures.iter().map(ItemFailure::csv_row));
+        lines.join("\n")
+    }
+}
+
+impl Serialize for MovieFilterVerdict {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("MovieFilterVerdict", 2)?;
+        state.serialize_field("verdict", self.kind())?;
+        state.serialize_field("reason", self.note)?;
+        state.end()
+    }
+}
+
+fn optional_to_string<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tmdb::MovieRejectionReason;
+
+    fn sample_movie(title: &str) -> DiscoverMovie {
+        DiscoverMovie {
+            id: 7,
+            title: title.to_string(),
+            original_release_date: "2026-01-10".to_string(),
+            release_date: "2026-01-10".to_string(),
+            original_language: "en".to_string(),
+            popularity: 12.5,
+        }
+    }
+
+    #[test]
+    fn csv_escapes_commas_and_quotes() {
+        let mut report = FilterReport::default();
+        report.push(FilterRecord::movie(
+            &sample_movie("Hello, \"World\""),
+            &["US".to_string(), "GB".to_string()],
+            None,
+            MovieFilterVerdict::rejected(MovieRejectionReason::Other, "missing_release_date"),
+            None,
+        ));
+
+        let csv = report.to_csv();
+        let row = csv.lines().nth(1).expect("строка кандидата присутствует");
+
+        assert!(csv.starts_with("kind,id,title"));
+        assert!(row.starts_with("movie,7,\"Hello, \"\"World\"\"\",2026-01-10,en,US;GB"));
+        assert!(row.contains("rejected: other,missing_release_date"));
+    }
+
+    #[test]
+    fn json_contains_verdict_and_source_region() {
+        let mut report = FilterReport::default();
+        let digital = DigitalReleaseDate {
+            date: NaiveDate::from_ymd_opt(2026, 2, 1).expect("валидная дата"),
+            region: Some("GB".to_string()),
+            regional_dates: Default::default(),
+        };
+        report.push(FilterRecord::movie(
+            &sample_movie("Фильм"),
+            &[],
+            None,
+            MovieFilterVerdict::accepted(),
+            Some(&digital),
+        ));
+
+        let json = report.to_json().expect("отчёт сериализуется");
+
+        assert!(json.contains("\"verdict\": \"accepted\""));
+        assert!(json.contains("\"reason\": \"passed_all_filters\""));
+        assert!(json.contains("\"source_region\": \"GB\""));
+        assert_eq!(report.accepted(), 1);
+        assert_eq!(report.rejected(), 0);
+    }
+
+    #[test]
+    fn failures_are_kept_separately_from_verdicts() {
+        let mut report = FilterReport::default();
+        report.record_failure(
+            CandidateKind::TvShow,
+            42,
+            &TmdbError::RetryLimitExceeded {
+                endpoint: "/3/tv/42".to_string(),
+            }
+            .for_title(42),
+        );
+
+        let json = report.to_json().expect("отчёт сериализуется");
+
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.rejected(), 0);
+        assert!(!report.is_empty());
+        assert!(json.contains("\"kind\": \"tv_show\""));
+        assert!(json.contains("ошибка обработки TMDB id 42"));
+        let csv = report.to_csv();
+        let row = csv.lines().nth(1).expect("строка сбоя присутствует");
+        assert_eq!(row.split(',').count(), CSV_HEADER.split(',').count());
+        assert!(row.starts_with("tv_show,42,"));
+        assert!(row.contains(",failed,ошибка обработки TMDB id 42"));
+    }
+}
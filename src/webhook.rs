@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout};
+use tracing::{info, warn};
+
+use crate::github::artifacts::ArtifactStore;
+use crate::orchestrator::{MessageDispatcher, Orchestrator, ReleaseProvider};
+use crate::telegram::Update;
+
+pub const SECRET_HEADER: &str = "x-telegram-bot-api-secret-token";
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// Сколько ждать полный запрос от клиента, прежде чем закрыть соединение.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Принятые, но ещё не обработанные обновления; при заполнении очереди новые
+/// соединения ждут ответа, и Telegram сам притормаживает доставку.
+const UPDATE_QUEUE_CAPACITY: usize = 256;
+/// Сколько обновлений из очереди обрабатывается за раз с одним сохранением состояния.
+const MAX_UPDATES_PER_BATCH: usize = 100;
+const PROCESS_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// Сколько раз пачка повторяется после ошибки, прежде чем её отбросить.
+const MAX_PROCESS_RETRIES: usize = 5;
+
+/// Параметры HTTP-приёмника вебхука.
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// Путь, на который Telegram отправляет обновления, например `/telegram/webhook`.
+    pub path: String,
+    /// Значение `secret_token`, переданное в `setWebhook`.
+    pub secret: String,
+}
+
+/// Разобранный HTTP-запрос; имена заголовков приведены к нижнему регистру.
+#[derive(Debug, Clone, Default)]
+pub struct WebhookRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("ошибка ввода-вывода: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("некорректный HTTP-запрос: {0}")]
+    Malformed(&'static str),
+    #[error("запрос не получен за {0:?}")]
+    Timeout(Duration),
+    #[error("очередь обновлений закрыта")]
+    QueueClosed,
+}
+
+/// Причина, по которой запрос не принят; определяет HTTP-статус ответа.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WebhookRejection {
+    #[error("неизвестный путь")]
+    NotFound,
+    #[error("поддерживается только POST")]
+    MethodNotAllowed,
+    #[error("неверный секрет вебхука")]
+    Unauthorized,
+    #[error("тело запроса не является Update: {0}")]
+    BadRequest(String),
+}
+
+impl WebhookRejection {
+    fn status_line(&self) -> &'static str {
+        match self {
+            Self::NotFound => "404 Not Found",
+            Self::MethodNotAllowed => "405 Method Not Allowed",
+            Self::Unauthorized => "401 Unauthorized",
+            Self::BadRequest(_) => "400 Bad Request",
+        }
+    }
+}
+
+/// Проверяет путь, метод и секрет запроса и декодирует из тела `Update`.
+pub fn decode_update(
+    request: &WebhookRequest,
+    config: &WebhookConfig,
+) -> Result<Update, WebhookRejection> {
+    if request.path != config.path {
+        return Err(WebhookRejection::NotFound);
+    }
+    if request.method != "POST" {
+        return Err(WebhookRejection::MethodNotAllowed);
+    }
+    let secret = request
+        .headers
+        .get(SECRET_HEADER)
+        .map(String::as_str)
+        .unwrap_or_default();
+    if !secrets_match(secret, &config.secret) {
+        return Err(WebhookRejection::Unauthorized);
+    }
+
+    serde_json::from_slice(&request.body)
+        .map_err(|err| WebhookRejection::BadRequest(err.to_string()))
+}
+
+/// Принимает вебхуки, пока не завершится `shutdown`.
+///
+/// Соединения читаются параллельно: Telegram получает 200 сразу после проверки
+/// секрета и тела, а команды выполняет одна задача-обработчик из очереди, так что
+/// медленная команда не задерживает приём следующих обновлений.
+pub async fn serve<C, P, D, F>(
+    listener: &TcpListener,
+    config: &WebhookConfig,
+    orchestrator: &mut Orchestrator<C, P, D>,
+    shutdown: F,
+) -> Result<(), WebhookError>
+where
+    C: ArtifactStore,
+    P: ReleaseProvider,
+    D: MessageDispatcher,
+    F: std::future::Future<Output = ()>,
+{
+    info!(
+        target: "webhook",
+        address = ?listener.local_addr().ok(),
+        path = %config.path,
+        "Приём вебхуков Telegram запущен"
+    );
+    let config = Arc::new(config.clone());
+    let (sender, receiver) = mpsc::channel(UPDATE_QUEUE_CAPACITY);
+    let accept = async {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    warn!(
+                        target: "webhook",
+                        error = %err,
+                        "Не удалось принять соединение вебхука"
+                    );
+                    continue;
+                }
+            };
+            let config = Arc::clone(&config);
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                if let Err(err) = handle_connection(stream, &config, &sender).await {
+                    warn!(
+                        target: "webhook",
+                        error = %err,
+                        "Не удалось обработать запрос вебхука"
+                    );
+                }
+            });
+        }
+    };
+
+    tokio::select! {
+        () = accept => {}
+        () = process_queue(receiver, orchestrator, shutdown) => {}
+    }
+    Ok(())
+}
+
+/// Читает один запрос, отвечает на него и ставит принятое обновление в очередь.
+///
+/// Место в очереди резервируется до ответа 200, поэтому принятое обновление
+/// не теряется, даже если очередь закрывается сразу после ответа.
+pub async fn handle_connection(
+    mut stream: TcpStream,
+    config: &WebhookConfig,
+    sender: &mpsc::Sender<Update>,
+) -> Result<(), WebhookError> {
+    let request = match timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(request)) => request,
+        Ok(Err(err)) => {
+            write_status(&mut stream, "400 Bad Request").await?;
+            return Err(err);
+        }
+        Err(_) => {
+            write_status(&mut stream, "408 Request Timeout").await?;
+            return Err(WebhookError::Timeout(READ_TIMEOUT));
+        }
+    };
+
+    let update = match decode_update(&request, config) {
+        Ok(update) => update,
+        Err(rejection) => {
+            warn!(target: "webhook", %rejection, "Запрос вебхука отклонён");
+            return write_status(&mut stream, rejection.status_line()).await;
+        }
+    };
+    let Ok(permit) = sender.reserve().await else {
+        write_status(&mut stream, "503 Service Unavailable").await?;
+        return Err(WebhookError::QueueClosed);
+    };
+    let written = write_status(&mut stream, "200 OK").await;
+    permit.send(update);
+    written
+}
+
+/// Выполняет обновления из очереди, пока она не закрыта или не завершился
+/// `shutdown`.
+///
+/// Накопившиеся обновления обрабатываются пачкой по возрастанию `update_id`,
+/// и смещение сохраняется один раз на пачку. Остановка ждёт, пока очередь
+/// опустеет, но прерывает паузу между повторами пачки.
+pub async fn process_queue<C, P, D, F>(
+    mut receiver: mpsc::Receiver<Update>,
+    orchestrator: &mut Orchestrator<C, P, D>,
+    shutdown: F,
+) where
+    C: ArtifactStore,
+    P: ReleaseProvider,
+    D: MessageDispatcher,
+    F: std::future::Future<Output = ()>,
+{
+    tokio::pin!(shutdown);
+    let mut batch = Vec::new();
+    loop {
+        let received = tokio::select! {
+            biased;
+            received = receiver.recv_many(&mut batch, MAX_UPDATES_PER_BATCH) => received,
+            () = &mut shutdown => return,
+        };
+        if received == 0 {
+            return;
+        }
+        batch.sort_by_key(|update| update.update_id);
+        let first_update_id = batch.first().map(|update| update.update_id);
+        let last_update_id = batch.last().map(|update| update.update_id);
+        let mut retries = 0usize;
+        loop {
+            let err = match orchestrator
+                .process_webhook_updates(batch.clone(), Utc::now())
+                .await
+            {
+                Ok(_) => break,
+                Err(err) => err,
+            };
+            // Обновления уже подтверждены Telegram и заново не придут, поэтому
+            // пачка повторяется, а отбрасывается только после всех повторов.
+            if retries == MAX_PROCESS_RETRIES {
+                warn!(
+                    target: "webhook",
+                    first_update_id,
+                    last_update_id,
+                    retries,
+                    error = %err,
+                    "Пачка обновлений из вебхука отброшена после повторов"
+                );
+                break;
+            }
+            retries += 1;
+            warn!(
+                target: "webhook",
+                first_update_id,
+                last_update_id,
+                retry = retries,
+                error = %err,
+                "Ошибка обработки обновлений из вебхука, повтор через паузу"
+            );
+            tokio::select! {
+                () = &mut shutdown => {
+                    warn!(
+                        target: "webhook",
+                        first_update_id,
+                        last_update_id,
+                        "Остановка: пачка обновлений из вебхука не обработана"
+                    );
+                    return;
+                }
+                () = sleep(PROCESS_RETRY_DELAY) => {}
+            }
+        }
+        batch.clear();
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<WebhookRequest, WebhookError> {
+    let mut buffer = Vec::new();
+    let header_end = loop {
+        if let Some(position) = find_header_end(&buffer) {
+            break position;
+        }
+        if buffer.len() > MAX_HEADER_BYTES {
+            return Err(WebhookError::Malformed("слишком длинные заголовки"));
+        }
+        let mut chunk = [0u8; 4096];
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(WebhookError::Malformed(
+                "соединение закрыто до конца заголовков",
+            ));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = std::str::from_utf8(&buffer[..header_end])
+        .map_err(|_| WebhookError::Malformed("заголовки не в UTF-8"))?;
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines
+        .next()
+        .ok_or(WebhookError::Malformed("нет строки запроса"))?
+        .split_whitespace();
+    let method = request_line
+        .next()
+        .ok_or(WebhookError::Malformed("нет метода"))?
+        .to_string();
+    let target = request_line
+        .next()
+        .ok_or(WebhookError::Malformed("нет пути"))?;
+    let path = target.split('?').next().unwrap_or_default().to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let content_length: usize = headers
+        .get("content-length")
+        .map(|value| value.parse())
+        .transpose()
+        .map_err(|_| WebhookError::Malformed("некорректный Content-Length"))?
+        .unwrap_or(0);
+    if content_length > MAX_BODY_BYTES {
+        return Err(WebhookError::Malformed("слишком большое тело"));
+    }
+
+    let mut body = buffer[header_end + 4..].to_vec();
+    while body.len() < content_length {
+        let mut chunk = vec![0u8; content_length - body.len()];
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(WebhookError::Malformed("тело короче Content-Length"));
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(content_length);
+
+    Ok(WebhookRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+async fn write_status(stream: &mut TcpStream, status: &str) -> Result<(), WebhookError> {
+    let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn find_header_end(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(4).position(|window| window == b"\r\n\r\n")
+}
+
+/// Сравнение без раннего выхода, чтобы время ответа не выдавало совпавший префикс.
+fn secrets_match(actual: &str, expected: &str) -> bool {
+    actual.len() == expected.len()
+        && actual
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (left, right)| acc | (left ^ right))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> WebhookConfig {
+        WebhookConfig {
+            path: "/telegram/webhook".to_string(),
+            secret: "s3cret".to_string(),
+        }
+    }
+
+    fn request(secret: Option<&str>, body: &str) -> WebhookRequest {
+        let mut headers = HashMap::new();
+        if let Some(secret) = secret {
+            headers.insert(SECRET_HEADER.to_string(), secret.to_string());
+        }
+        WebhookRequest {
+            method: "POST".to_string(),
+            path: "/telegram/webhook".to_string(),
+            headers,
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn rejects_missing_or_wrong_secret() {
+        let body = r#"{"update_id":1}"#;
+
+        assert_eq!(
+            decode_update(&request(None, body), &config()).unwrap_err(),
+            WebhookRejection::Unauthorized
+        );
+        assert_eq!(
+            decode_update(&request(Some("s3creT"), body), &config()).unwrap_err(),
+            WebhookRejection::Unauthorized
+        );
+        assert_eq!(
+            decode_update(&request(Some("s3cret"), body), &config())
+                .expect("обновление принимается")
+                .update_id,
+            1
+        );
+    }
+
+    #[test]
+    fn rejects_foreign_path_and_invalid_body() {
+        let mut foreign = request(Some("s3cret"), "{}");
+        foreign.path = "/other".to_string();
+
+        assert_eq!(
+            decode_update(&foreign, &config()).unwrap_err(),
+            WebhookRejection::NotFound
+        );
+        assert!(matches!(
+            decode_update(&request(Some("s3cret"), "не json"), &config()),
+            Err(WebhookRejection::BadRequest(_))
+        ));
+    }
+}
@@ -51,6 +51,11 @@ fn sample_release(id: u64) -> MovieRelease {
         vote_count: None,
         homepage: None,
         watch_providers: Vec::new(),
+        genres: Vec::new(),
+        regional_digital_dates: Default::default(),
+        only_for_regions: false,
+        collection: None,
+        only_for_followers: false,
     }
 }
 
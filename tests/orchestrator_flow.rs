@@ -1,12 +1,28 @@
 use async_trait::async_trait;
-use chrono::{NaiveDate, Utc};
-use movie_notifier_bot::config::{ChatConfig, TelegramConfig};
+use chrono::DateTime;
+use chrono::{NaiveDate, Utc, Weekday};
+use chrono_tz::Tz;
+use movie_notifier_bot::config::{
+    BacklogConfig, BacklogPolicy, Cadence, ChatConfig, ChatMessageOptions, CorrectionAction,
+    CorrectionPolicy, ForumTopics, LinkPreview, MessageKind, MessageOptions, QuietHours, QuietMode,
+    ScoringConfig, TelegramConfig,
+};
 use movie_notifier_bot::github::artifacts::{ArtifactError, ArtifactStore};
 use movie_notifier_bot::orchestrator::{
-    BoxError, MessageDispatcher, Orchestrator, ReleaseBatch, ReleaseProvider,
+    BoxError, DeliveryOutcome, MessageDispatcher, Orchestrator, OrchestratorError,
+    OrchestratorSettings, ReleaseBatch, ReleaseProvider, SendFailure, UpdateChannel,
+};
+use movie_notifier_bot::state::{
+    BotStateStore, ChatHistoryStore, PossibleDuplicateRecord, ReportArchive, SentEventHistory,
+    SentHistory,
 };
-use movie_notifier_bot::state::{SentEventHistory, SentHistory};
-use movie_notifier_bot::tmdb::{MovieRelease, ReleaseWindow};
+use movie_notifier_bot::telegram::{PossibleDuplicate, TelegramError, Update};
+use movie_notifier_bot::tmdb::{
+    CandidateKind, DigitalReleaseDate, FilterRecord, FilterReport, ItemFailure, MovieFilterVerdict,
+    MovieRelease, ReleaseWindow,
+};
+use movie_notifier_bot::webhook::{self, WebhookConfig};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use tempfile::tempdir;
 
@@ -14,6 +30,8 @@ type UploadEntry = (String, String, Vec<u8>);
 type UploadLog = Arc<Mutex<Vec<UploadEntry>>>;
 type SentEntry = (i64, Vec<String>);
 type SentMessages = Arc<Mutex<Vec<SentEntry>>>;
+type EditEntry = (i64, i64, String);
+type ThreadEntry = (i64, Option<i64>);
 
 #[derive(Default, Clone)]
 struct MemoryStore {
@@ -44,6 +62,10 @@ impl ArtifactStore for MemoryStore {
 struct StubProvider {
     batch: ReleaseBatch,
     last_window: Arc<Mutex<Option<ReleaseWindow>>>,
+    /// Ответы перепроверки цифровых дат; для остальных фильмов — ошибка.
+    digital_releases: HashMap<u64, Option<DigitalReleaseDate>>,
+    /// Фильмы, чьи даты перепроверялись, в порядке запросов.
+    rechecked: Arc<Mutex<Vec<u64>>>,
 }
 
 impl StubProvider {
@@ -51,6 +73,8 @@ impl StubProvider {
         Self {
             batch,
             last_window: Arc::new(Mutex::new(None)),
+            digital_releases: HashMap::new(),
+            rechecked: Arc::new(Mutex::new(Vec::new())),
         }
     }
 }
@@ -64,22 +88,202 @@ impl ReleaseProvider for StubProvider {
             .replace(window);
         Ok(self.batch.clone())
     }
+
+    async fn digital_release(
+        &self,
+        movie_id: u64,
+        _today: NaiveDate,
+    ) -> Result<Option<DigitalReleaseDate>, BoxError> {
+        self.rechecked
+            .lock()
+            .expect("блокировка доступна")
+            .push(movie_id);
+        self.digital_releases
+            .get(&movie_id)
+            .cloned()
+            .ok_or_else(|| "дата не задана".into())
+    }
 }
 
 #[derive(Default, Clone)]
 struct StubDispatcher {
     sent: SentMessages,
+    migrations: Arc<Mutex<HashMap<i64, i64>>>,
+    forbidden: Arc<Mutex<HashSet<i64>>>,
+    /// Чаты, где у бота нет права писать.
+    no_rights: Arc<Mutex<HashSet<i64>>>,
+    failing: Arc<Mutex<HashSet<i64>>>,
+    /// Чаты, где одна отправка обрывается после указанного числа сообщений.
+    fail_after: Arc<Mutex<HashMap<i64, usize>>>,
+    /// Чаты, которые переезжают в супергруппу после указанного числа сообщений.
+    migrate_after: Arc<Mutex<HashMap<i64, (usize, i64)>>>,
+    silent: Arc<Mutex<Vec<i64>>>,
+    edits: Arc<Mutex<Vec<EditEntry>>>,
+    deleted: Arc<Mutex<Vec<(i64, i64)>>>,
+    /// Тема каждой отправки, в порядке `sent`.
+    threads: Arc<Mutex<Vec<ThreadEntry>>>,
+    /// Параметры каждой отправки, в порядке `sent`.
+    options: Arc<Mutex<Vec<MessageOptions>>>,
+    /// Возможные дубли, которые диспетчер отдаст после рассылки.
+    duplicates: Arc<Mutex<Vec<PossibleDuplicate>>>,
+}
+
+impl StubDispatcher {
+    fn record_sent(
+        &self,
+        chat_id: i64,
+        thread_id: Option<i64>,
+        messages: Vec<String>,
+        options: &MessageOptions,
+    ) -> Vec<Option<i64>> {
+        let mut sent = self.sent.lock().expect("блокировка доступна");
+        let first_id = sent
+            .iter()
+            .map(|(_, messages)| messages.len())
+            .sum::<usize>() as i64
+            + 1;
+        let message_ids = (first_id..).take(messages.len()).map(Some).collect();
+        sent.push((chat_id, messages));
+        self.threads
+            .lock()
+            .expect("блокировка доступна")
+            .push((chat_id, thread_id));
+        self.options
+            .lock()
+            .expect("блокировка доступна")
+            .push(options.clone());
+        if options.disable_notification {
+            self.silent
+                .lock()
+                .expect("блокировка доступна")
+                .push(chat_id);
+        }
+        message_ids
+    }
+}
+
+fn failure(error: TelegramError) -> SendFailure {
+    SendFailure::from(Box::new(error) as BoxError)
 }
 
 #[async_trait]
 impl MessageDispatcher for StubDispatcher {
-    async fn send_messages(&self, chat_id: i64, messages: Vec<String>) -> Result<(), BoxError> {
-        self.sent
+    async fn send_messages(
+        &self,
+        chat_id: i64,
+        thread_id: Option<i64>,
+        messages: Vec<String>,
+        options: &MessageOptions,
+    ) -> Result<Vec<Option<i64>>, SendFailure> {
+        if let Some(to) = self
+            .migrations
+            .lock()
+            .expect("блокировка доступна")
+            .get(&chat_id)
+        {
+            return Err(failure(TelegramError::ChatMigrated {
+                chat_id,
+                migrate_to_chat_id: *to,
+            }));
+        }
+        if self
+            .forbidden
+            .lock()
+            .expect("блокировка доступна")
+            .contains(&chat_id)
+        {
+            return Err(failure(TelegramError::Forbidden {
+                chat_id,
+                description: "Forbidden: bot was kicked".to_string(),
+            }));
+        }
+        if self
+            .no_rights
+            .lock()
+            .expect("блокировка доступна")
+            .contains(&chat_id)
+        {
+            return Err(failure(TelegramError::NotEnoughRights {
+                chat_id,
+                description: "Bad Request: not enough rights to send text messages to the chat"
+                    .to_string(),
+            }));
+        }
+        if self
+            .failing
+            .lock()
+            .expect("блокировка доступна")
+            .contains(&chat_id)
+        {
+            return Err(SendFailure::from(BoxError::from("сеть недоступна")));
+        }
+        let migration = self
+            .migrate_after
             .lock()
             .expect("блокировка доступна")
-            .push((chat_id, messages));
+            .remove(&chat_id);
+        if let Some((limit, to)) = migration {
+            let delivered = messages.into_iter().take(limit).collect();
+            return Err(SendFailure {
+                sent: self.record_sent(chat_id, thread_id, delivered, options),
+                error: Box::new(TelegramError::ChatMigrated {
+                    chat_id,
+                    migrate_to_chat_id: to,
+                }),
+            });
+        }
+        let limit = self
+            .fail_after
+            .lock()
+            .expect("блокировка доступна")
+            .remove(&chat_id);
+        if let Some(limit) = limit.filter(|limit| *limit < messages.len()) {
+            let delivered = messages.into_iter().take(limit).collect();
+            return Err(SendFailure {
+                sent: self.record_sent(chat_id, thread_id, delivered, options),
+                error: "соединение оборвалось".into(),
+            });
+        }
+        Ok(self.record_sent(chat_id, thread_id, messages, options))
+    }
+
+    async fn edit_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        text: String,
+        _options: &MessageOptions,
+    ) -> Result<(), BoxError> {
+        self.edits
+            .lock()
+            .expect("блокировка доступна")
+            .push((chat_id, message_id, text));
+        Ok(())
+    }
+
+    async fn delete_message(&self, chat_id: i64, message_id: i64) -> Result<(), BoxError> {
+        self.deleted
+            .lock()
+            .expect("блокировка доступна")
+            .push((chat_id, message_id));
         Ok(())
     }
+
+    fn take_possible_duplicates(&self) -> Vec<PossibleDuplicate> {
+        std::mem::take(&mut *self.duplicates.lock().expect("блокировка доступна"))
+    }
+}
+
+/// Ответ перепроверки с датой приоритетного региона `US` и датами по регионам.
+fn digital_release(date: NaiveDate, regional: &[(&str, NaiveDate)]) -> DigitalReleaseDate {
+    DigitalReleaseDate {
+        date,
+        region: Some("US".to_string()),
+        regional_dates: regional
+            .iter()
+            .map(|(region, date)| (region.to_string(), *date))
+            .collect(),
+    }
 }
 
 fn sample_release(id: u64, title: &str) -> MovieRelease {
@@ -95,6 +299,11 @@ fn sample_release(id: u64, title: &str) -> MovieRelease {
         vote_count: Some(120),
         homepage: Some("https://example.org".to_string()),
         watch_providers: vec!["Kinopoisk".to_string()],
+        genres: vec!["Drama".to_string()],
+        regional_digital_dates: Default::default(),
+        only_for_regions: false,
+        collection: None,
+        only_for_followers: false,
     }
 }
 
@@ -116,6 +325,7 @@ async fn orchestrator_runs_full_cycle() {
     let batch = ReleaseBatch {
         movies: releases,
         tv_events: Vec::new(),
+        ..ReleaseBatch::default()
     };
     let provider = StubProvider::new(batch);
     let dispatcher = StubDispatcher::default();
@@ -123,6 +333,13 @@ async fn orchestrator_runs_full_cycle() {
         chats: vec![ChatConfig {
             chat_id: 99,
             locales: vec!["ru".to_string()],
+            regions: Vec::new(),
+            collections: Vec::new(),
+            cadence: Cadence::Instant,
+            timezone: Tz::UTC,
+            quiet_hours: None,
+            topics: ForumTopics::default(),
+            message_options: ChatMessageOptions::default(),
         }],
     };
 
@@ -192,12 +409,20 @@ async fn orchestrator_skips_dispatch_when_no_new_releases() {
     let provider = StubProvider::new(ReleaseBatch {
         movies: Vec::new(),
         tv_events: Vec::new(),
+        ..ReleaseBatch::default()
     });
     let dispatcher = StubDispatcher::default();
     let telegram_config = TelegramConfig {
         chats: vec![ChatConfig {
             chat_id: 99,
             locales: vec!["ru".to_string()],
+            regions: Vec::new(),
+            collections: Vec::new(),
+            cadence: Cadence::Instant,
+            timezone: Tz::UTC,
+            quiet_hours: None,
+            topics: ForumTopics::default(),
+            message_options: ChatMessageOptions::default(),
         }],
     };
 
@@ -227,3 +452,2333 @@ async fn orchestrator_skips_dispatch_when_no_new_releases() {
         "диспетчер не должен вызываться при пустом списке релизов"
     );
 }
+
+#[derive(Clone)]
+struct ChangeFeedProvider {
+    batch: ReleaseBatch,
+    changed: ReleaseBatch,
+    last_since: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// Допустимое число сбоев тайтлов; `None` — без бюджета.
+    error_budget: Option<usize>,
+}
+
+#[async_trait]
+impl ReleaseProvider for ChangeFeedProvider {
+    async fn fetch_releases(&self, _window: ReleaseWindow) -> Result<ReleaseBatch, BoxError> {
+        Ok(self.batch.clone())
+    }
+
+    fn check_error_budget(&self, report: &FilterReport) -> Result<(), BoxError> {
+        match self.error_budget {
+            Some(budget) if report.failures.len() > budget => {
+                Err(format!("сбоев TMDB: {}", report.failures.len()).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn fetch_changed_releases(
+        &self,
+        since: DateTime<Utc>,
+        _window: ReleaseWindow,
+    ) -> Result<ReleaseBatch, BoxError> {
+        self.last_since
+            .lock()
+            .expect("блокировка доступна")
+            .replace(since);
+        Ok(self.changed.clone())
+    }
+}
+
+#[tokio::test]
+async fn change_feed_releases_are_merged_and_cursor_is_persisted() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let state_path = dir.path().join("bot_state.json");
+    let state_store = BotStateStore::with_store(&state_path, "bot-state", store.clone());
+
+    let provider = ChangeFeedProvider {
+        batch: ReleaseBatch {
+            movies: vec![sample_release(1, "Из discover")],
+            tv_events: Vec::new(),
+            ..ReleaseBatch::default()
+        },
+        changed: ReleaseBatch {
+            movies: vec![
+                sample_release(1, "Из discover"),
+                sample_release(2, "Из ленты изменений"),
+            ],
+            tv_events: Vec::new(),
+            ..ReleaseBatch::default()
+        },
+        last_since: Arc::new(Mutex::new(None)),
+        error_budget: None,
+    };
+    let dispatcher = StubDispatcher::default();
+    let telegram_config = TelegramConfig {
+        chats: vec![ChatConfig {
+            chat_id: 99,
+            locales: Vec::new(),
+            regions: Vec::new(),
+            collections: Vec::new(),
+            cadence: Cadence::Instant,
+            timezone: Tz::UTC,
+            quiet_hours: None,
+            topics: ForumTopics::default(),
+            message_options: ChatMessageOptions::default(),
+        }],
+    };
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider.clone(),
+        dispatcher.clone(),
+        telegram_config,
+    )
+    .with_settings(OrchestratorSettings {
+        change_feed: true,
+        ..OrchestratorSettings::default()
+    })
+    .with_state_store(state_store);
+
+    let now = Utc::now();
+    let summary = orchestrator
+        .run(now)
+        .await
+        .expect("оркестратор должен завершиться успешно");
+
+    assert_eq!(summary.fetched, 2);
+    assert_eq!(summary.change_feed_releases, 1);
+    assert_eq!(summary.duplicates, 0);
+    assert_eq!(summary.sent_releases, 2);
+
+    let since = provider
+        .last_since
+        .lock()
+        .expect("блокировка доступна")
+        .expect("лента изменений должна запрашиваться");
+    assert_eq!(since, now - chrono::Duration::days(7));
+    assert_eq!(orchestrator.state().change_feed.synced_until, Some(now));
+
+    let saved = std::fs::read_to_string(&state_path).expect("состояние должно сохраняться");
+    assert!(saved.contains("synced_until"));
+}
+
+#[tokio::test]
+async fn change_feed_cursor_stops_where_feed_was_read() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let now = Utc::now();
+    let read_until = now - chrono::Duration::days(3);
+    let provider = ChangeFeedProvider {
+        batch: ReleaseBatch::default(),
+        changed: ReleaseBatch {
+            movies: vec![sample_release(2, "Из ленты изменений")],
+            synced_until: Some(read_until),
+            ..ReleaseBatch::default()
+        },
+        last_since: Arc::new(Mutex::new(None)),
+        error_budget: None,
+    };
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        StubDispatcher::default(),
+        TelegramConfig {
+            chats: vec![ChatConfig::new(99)],
+        },
+    )
+    .with_settings(OrchestratorSettings {
+        change_feed: true,
+        ..OrchestratorSettings::default()
+    });
+
+    let summary = orchestrator
+        .run(now)
+        .await
+        .expect("оркестратор должен завершиться успешно");
+
+    assert_eq!(summary.change_feed_releases, 1);
+    assert_eq!(
+        orchestrator.state().change_feed.synced_until,
+        Some(read_until),
+        "непрочитанный остаток ленты дочитывается следующим прогоном"
+    );
+}
+
+#[tokio::test]
+async fn filter_report_is_archived_with_run() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let reports_dir = dir.path().join("reports");
+
+    let mut report = FilterReport::default();
+    report.push(FilterRecord {
+        kind: CandidateKind::Movie,
+        id: 5,
+        title: "Принятый фильм".to_string(),
+        release_date: Some("2024-01-01".to_string()),
+        original_language: "ru".to_string(),
+        discover_regions: vec!["US".to_string()],
+        production_countries: vec!["US".to_string()],
+        genres: vec!["Drama".to_string()],
+        vote_average: Some(7.2),
+        vote_count: Some(120),
+        popularity: Some(10.0),
+        runtime: Some(100),
+        imdb_id: Some("tt0000005".to_string()),
+        verdict: MovieFilterVerdict::accepted(),
+        digital_release_date: NaiveDate::from_ymd_opt(2024, 1, 1),
+        source_region: Some("US".to_string()),
+    });
+    report.failures.push(ItemFailure {
+        kind: CandidateKind::TvShow,
+        id: 404,
+        error: "ошибка обработки TMDB id 404".to_string(),
+    });
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: Vec::new(),
+        tv_events: Vec::new(),
+        report,
+        ..ReleaseBatch::default()
+    });
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        StubDispatcher::default(),
+        TelegramConfig::single_global_chat(99),
+    )
+    .with_report_archive(ReportArchive::with_store(&reports_dir, store.clone()));
+
+    let summary = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("оркестратор должен завершиться успешно");
+    assert_eq!(summary.filter_rejected, 0);
+    assert_eq!(summary.tmdb_failures, 1);
+
+    let json = std::fs::read_to_string(reports_dir.join("filter_report.json"))
+        .expect("JSON-отчёт должен сохраняться");
+    let csv = std::fs::read_to_string(reports_dir.join("filter_report.csv"))
+        .expect("CSV-отчёт должен сохраняться");
+    assert!(json.contains("Принятый фильм"));
+    assert!(json.contains("ошибка обработки TMDB id 404"));
+    assert!(
+        csv.lines()
+            .nth(1)
+            .expect("строка кандидата")
+            .contains("accepted")
+    );
+
+    let uploads = store.uploads.lock().expect("блокировка доступна");
+    let artifacts: Vec<&str> = uploads.iter().map(|upload| upload.0.as_str()).collect();
+    assert!(artifacts.contains(&"filter-report"));
+    assert!(artifacts.contains(&"filter-report-csv"));
+}
+
+#[tokio::test]
+async fn filter_report_is_archived_when_error_budget_is_exceeded() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let reports_dir = dir.path().join("reports");
+    let failure = |id| ItemFailure {
+        kind: CandidateKind::Movie,
+        id,
+        error: format!("ошибка обработки TMDB id {id}"),
+    };
+    let provider = ChangeFeedProvider {
+        batch: ReleaseBatch {
+            movies: vec![sample_release(1, "Фильм")],
+            report: FilterReport {
+                failures: vec![failure(404)],
+                ..FilterReport::default()
+            },
+            ..ReleaseBatch::default()
+        },
+        changed: ReleaseBatch {
+            report: FilterReport {
+                failures: vec![failure(405)],
+                ..FilterReport::default()
+            },
+            ..ReleaseBatch::default()
+        },
+        last_since: Arc::new(Mutex::new(None)),
+        error_budget: Some(1),
+    };
+    let dispatcher = StubDispatcher::default();
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig::single_global_chat(99),
+    )
+    .with_settings(OrchestratorSettings {
+        change_feed: true,
+        ..OrchestratorSettings::default()
+    })
+    .with_report_archive(ReportArchive::with_store(&reports_dir, store.clone()));
+    let err = orchestrator
+        .run(Utc::now())
+        .await
+        .expect_err("бюджет ошибок превышен вместе с лентой изменений");
+
+    assert!(matches!(err, OrchestratorError::ErrorBudget { .. }));
+    let csv = std::fs::read_to_string(reports_dir.join("filter_report.csv"))
+        .expect("отчёт сохраняется и при превышении бюджета");
+    assert!(csv.contains("movie,404,"));
+    assert!(
+        csv.contains("movie,405,"),
+        "сбой ленты изменений в том же отчёте"
+    );
+    assert!(
+        dispatcher
+            .sent
+            .lock()
+            .expect("блокировка доступна")
+            .is_empty()
+    );
+}
+
+#[derive(Default, Clone)]
+struct StubUpdateChannel {
+    updates: Arc<Mutex<Vec<Update>>>,
+    offsets: Arc<Mutex<Vec<Option<i64>>>>,
+    replies: SentMessages,
+    /// Long polling без ответа: `fetch_updates` не завершается.
+    hanging: bool,
+    /// `getMe` недоступен: обработка обновлений завершается ошибкой.
+    no_username: bool,
+}
+
+impl StubUpdateChannel {
+    fn with_messages(messages: &[(i64, i64, &str, &str)]) -> Self {
+        let updates = messages
+            .iter()
+            .map(|(update_id, chat_id, chat_type, text)| {
+                serde_json::from_value(serde_json::json!({
+                    "update_id": update_id,
+                    "message": {
+                        "message_id": update_id,
+                        "chat": { "id": chat_id, "type": chat_type },
+                        "from": { "id": 7 },
+                        "text": text
+                    }
+                }))
+                .expect("обновление десериализуется")
+            })
+            .collect();
+        Self {
+            updates: Arc::new(Mutex::new(updates)),
+            ..Self::default()
+        }
+    }
+}
+
+#[async_trait]
+impl UpdateChannel for StubUpdateChannel {
+    async fn fetch_updates(
+        &self,
+        offset: Option<i64>,
+        _timeout_secs: u64,
+    ) -> Result<Vec<Update>, BoxError> {
+        if self.hanging {
+            std::future::pending::<()>().await;
+        }
+        self.offsets
+            .lock()
+            .expect("блокировка доступна")
+            .push(offset);
+        Ok(std::mem::take(
+            &mut *self.updates.lock().expect("блокировка доступна"),
+        ))
+    }
+
+    async fn reply(&self, chat_id: i64, text: String) -> Result<(), BoxError> {
+        self.replies
+            .lock()
+            .expect("блокировка доступна")
+            .push((chat_id, vec![text]));
+        Ok(())
+    }
+
+    async fn is_chat_admin(&self, _chat_id: i64, _user_id: i64) -> Result<bool, BoxError> {
+        Ok(false)
+    }
+
+    async fn bot_username(&self) -> Result<String, BoxError> {
+        if self.no_username {
+            return Err("getMe недоступен".into());
+        }
+        Ok("movie_bot".to_string())
+    }
+}
+
+#[tokio::test]
+async fn chat_commands_update_subscriptions_used_by_run() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let state_path = dir.path().join("bot_state.json");
+    let state_store = BotStateStore::with_store(&state_path, "bot-state", store.clone());
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![sample_release(1, "Фильм")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+    let channel = StubUpdateChannel::with_messages(&[
+        (10, 555, "private", "/subscribe"),
+        (11, 555, "private", "/filters lang ru"),
+        (12, 99, "group", "/unsubscribe@movie_bot"),
+        (13, 99, "group", "привет"),
+        (14, 99, "group", "/help@other_bot"),
+    ]);
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig::single_global_chat(99),
+    )
+    .with_state_store(state_store)
+    .with_update_channel(channel.clone());
+
+    let now = Utc::now();
+    let handled = orchestrator
+        .process_updates(now, 0)
+        .await
+        .expect("обновления обрабатываются");
+    orchestrator
+        .run(now)
+        .await
+        .expect("оркестратор должен завершиться успешно");
+
+    assert_eq!(handled, 3, "команда другому боту не выполняется");
+    assert_eq!(orchestrator.state().updates.offset, Some(15));
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    let mut chats: Vec<i64> = sent.iter().map(|(chat_id, _)| *chat_id).collect();
+    chats.sort();
+    assert_eq!(
+        chats,
+        vec![99, 555],
+        "отписка не-админа в группе игнорируется"
+    );
+
+    let replies = channel.replies.lock().expect("блокировка доступна");
+    assert_eq!(replies.len(), 3);
+    assert!(replies[1].1[0].contains("Языки: ru"));
+    assert!(replies[2].1[0].contains("администраторы"));
+
+    let saved = std::fs::read_to_string(&state_path).expect("состояние сохраняется");
+    assert!(saved.contains("\"offset\": 15"));
+}
+
+#[tokio::test]
+async fn update_polling_stops_waiting_on_cancel() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let channel = StubUpdateChannel {
+        hanging: true,
+        ..StubUpdateChannel::default()
+    };
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        StubProvider::new(ReleaseBatch::default()),
+        StubDispatcher::default(),
+        TelegramConfig::single_global_chat(99),
+    )
+    .with_update_channel(channel);
+
+    let handled = orchestrator
+        .process_updates_until(Utc::now(), 30, std::future::ready(()))
+        .await
+        .expect("отмена не считается ошибкой");
+
+    assert_eq!(handled, 0);
+    assert_eq!(orchestrator.state().updates.offset, None);
+}
+
+#[tokio::test]
+async fn webhook_posts_are_routed_to_commands() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let state_path = dir.path().join("bot_state.json");
+    let state_store = BotStateStore::with_store(&state_path, "bot-state", store.clone());
+    let channel = StubUpdateChannel::default();
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        StubProvider::new(ReleaseBatch::default()),
+        StubDispatcher::default(),
+        TelegramConfig::single_global_chat(99),
+    )
+    .with_state_store(state_store)
+    .with_update_channel(channel.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("порт открывается");
+    let url = format!(
+        "http://{}/telegram/webhook",
+        listener.local_addr().expect("адрес известен")
+    );
+    let config = WebhookConfig {
+        path: "/telegram/webhook".to_string(),
+        secret: "s3cret".to_string(),
+    };
+    let fixture = r#"{
+        "update_id": 20,
+        "message": {
+            "message_id": 1,
+            "chat": { "id": 555, "type": "private" },
+            "from": { "id": 7 },
+            "text": "/subscribe"
+        }
+    }"#;
+    let client = reqwest::Client::new();
+    let (sender, receiver) = tokio::sync::mpsc::channel(8);
+    let serve_next = || async {
+        let (stream, _) = listener.accept().await.expect("соединение принимается");
+        webhook::handle_connection(stream, &config, &sender).await
+    };
+
+    let (served, response) = tokio::join!(
+        serve_next(),
+        client
+            .post(&url)
+            .header(webhook::SECRET_HEADER, "s3cret")
+            .body(fixture)
+            .send()
+    );
+    served.expect("запрос обрабатывается");
+    assert_eq!(response.expect("ответ получен").status(), 200);
+    assert!(
+        channel
+            .replies
+            .lock()
+            .expect("блокировка доступна")
+            .is_empty(),
+        "ответ 200 уходит до выполнения команды"
+    );
+
+    let (served, response) = tokio::join!(
+        serve_next(),
+        client
+            .post(&url)
+            .header(webhook::SECRET_HEADER, "wrong")
+            .body(fixture.replace("/subscribe", "/unsubscribe"))
+            .send()
+    );
+    served.expect("запрос обрабатывается");
+    assert_eq!(response.expect("ответ получен").status(), 401);
+
+    drop(sender);
+    webhook::process_queue(receiver, &mut orchestrator, std::future::pending()).await;
+
+    assert_eq!(orchestrator.state().updates.offset, Some(21));
+    assert!(orchestrator.state().subscriptions[&555].active);
+    let replies = channel.replies.lock().expect("блокировка доступна");
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0].0, 555);
+    let saved = std::fs::read_to_string(&state_path).expect("состояние сохраняется");
+    assert!(saved.contains("\"offset\": 21"));
+}
+
+#[tokio::test]
+async fn failing_webhook_batch_stops_retrying_on_shutdown() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let channel = StubUpdateChannel {
+        no_username: true,
+        ..StubUpdateChannel::with_messages(&[(30, 555, "private", "/subscribe")])
+    };
+    let updates = std::mem::take(&mut *channel.updates.lock().expect("блокировка доступна"));
+    let mut orchestrator = Orchestrator::new(
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+        SentEventHistory::with_store(
+            dir.path().join("tv_history.txt"),
+            "tv-artifact",
+            store.clone(),
+        ),
+        StubProvider::new(ReleaseBatch::default()),
+        StubDispatcher::default(),
+        TelegramConfig::single_global_chat(99),
+    )
+    .with_update_channel(channel.clone());
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(8);
+    for update in updates {
+        sender.send(update).await.expect("очередь открыта");
+    }
+    // Остановка приходит, пока пачка ждёт повтора: паузу ждать не нужно.
+    let processed = tokio::time::timeout(
+        std::time::Duration::from_secs(2),
+        webhook::process_queue(receiver, &mut orchestrator, async {}),
+    )
+    .await;
+
+    assert!(
+        processed.is_ok(),
+        "обработка останавливается без ожидания повтора"
+    );
+    assert_eq!(orchestrator.state().updates.offset, None);
+    assert!(
+        channel
+            .replies
+            .lock()
+            .expect("блокировка доступна")
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn migrated_and_forbidden_chats_do_not_abort_run() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let state_path = dir.path().join("bot_state.json");
+    let state_store = BotStateStore::with_store(&state_path, "bot-state", store.clone());
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![sample_release(1, "Фильм")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+    dispatcher
+        .migrations
+        .lock()
+        .expect("блокировка доступна")
+        .insert(-100, -100500);
+    dispatcher
+        .forbidden
+        .lock()
+        .expect("блокировка доступна")
+        .insert(30);
+    let chat = |chat_id| ChatConfig {
+        chat_id,
+        locales: Vec::new(),
+        regions: Vec::new(),
+        collections: Vec::new(),
+        cadence: Cadence::Instant,
+        timezone: Tz::UTC,
+        quiet_hours: None,
+        topics: ForumTopics::default(),
+        message_options: ChatMessageOptions::default(),
+    };
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![chat(-100), chat(30), chat(40)],
+        },
+    )
+    .with_state_store(state_store);
+
+    let summary = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("прогон не прерывается");
+
+    assert_eq!(summary.migrated_chats(), vec![(-100, -100500)]);
+    assert_eq!(summary.inactive_chats(), vec![30]);
+    assert_eq!(summary.messages_sent, 2);
+    let mut chats: Vec<i64> = dispatcher
+        .sent
+        .lock()
+        .expect("блокировка доступна")
+        .iter()
+        .map(|(chat_id, _)| *chat_id)
+        .collect();
+    chats.sort();
+    assert_eq!(chats, vec![-100500, 40]);
+
+    let state = orchestrator.state();
+    assert_eq!(state.chat_migrations.get(&-100), Some(&-100500));
+    assert!(!state.subscriptions[&-100].active);
+    assert!(state.subscriptions[&-100500].active);
+    assert!(!state.subscriptions[&30].active);
+    let saved = std::fs::read_to_string(&state_path).expect("состояние сохраняется");
+    assert!(saved.contains("\"chat_migrations\""));
+}
+
+#[tokio::test]
+async fn migration_mid_delivery_resends_only_undelivered_messages() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![
+            sample_release(1, &"А".repeat(1500)),
+            sample_release(2, &"Б".repeat(1500)),
+        ],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+    dispatcher
+        .migrate_after
+        .lock()
+        .expect("блокировка доступна")
+        .insert(-100, (1, -100500));
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig::single_global_chat(-100),
+    );
+    let summary = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("прогон завершается");
+
+    assert_eq!(summary.migrated_chats(), vec![(-100, -100500)]);
+    assert_eq!(summary.messages_sent, 2);
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].0, -100);
+    assert!(sent[0].1[0].contains(&"А".repeat(1500)));
+    assert_eq!(sent[1].0, -100500);
+    assert_eq!(
+        sent[1].1.len(),
+        1,
+        "первое сообщение на новый id не повторяется"
+    );
+    assert!(sent[1].1[0].contains(&"Б".repeat(1500)));
+}
+
+#[tokio::test]
+async fn restored_chat_migrations_rename_environment_chats() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let state_path = dir.path().join("bot_state.json");
+    std::fs::write(
+        &state_path,
+        r#"{"chat_migrations": {"-100": -100200, "-100200": -100300}}"#,
+    )
+    .expect("состояние записывается");
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![sample_release(1, "Фильм")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig::single_global_chat(-100),
+    )
+    .with_state_store(BotStateStore::with_store(
+        &state_path,
+        "bot-state",
+        store.clone(),
+    ));
+    orchestrator
+        .run(Utc::now())
+        .await
+        .expect("прогон завершается");
+
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    let chats: Vec<i64> = sent.iter().map(|(chat_id, _)| *chat_id).collect();
+    assert_eq!(
+        chats,
+        vec![-100300],
+        "чат из окружения пишется по последнему id"
+    );
+}
+
+#[tokio::test]
+async fn chat_without_rights_is_skipped_without_failing_run() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![sample_release(1, "Фильм")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+    dispatcher
+        .no_rights
+        .lock()
+        .expect("блокировка доступна")
+        .insert(30);
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![ChatConfig::new(30), ChatConfig::new(40)],
+        },
+    );
+
+    let summary = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("прогон не прерывается");
+
+    assert_eq!(summary.skipped_chats(), vec![30]);
+    assert!(
+        summary.failed_chats().is_empty(),
+        "пропуск не считается ошибкой доставки"
+    );
+    assert!(
+        summary.inactive_chats().is_empty(),
+        "чат остаётся в рассылке"
+    );
+    assert!(
+        summary
+            .render_markdown()
+            .contains("чат 30: пропущено: у бота нет прав")
+    );
+    assert_eq!(summary.messages_sent, 1);
+    assert!(orchestrator.state().last_run.succeeded_at.is_some());
+    let queued: Vec<&str> = orchestrator.state().backlog[&30]
+        .iter()
+        .map(|release| release.event_key.as_str())
+        .collect();
+    assert_eq!(
+        queued,
+        vec!["movie:1"],
+        "релизы пропущенного чата ждут в очереди"
+    );
+
+    // Права вернули: отложенный релиз доходит до чата.
+    dispatcher
+        .no_rights
+        .lock()
+        .expect("блокировка доступна")
+        .clear();
+    let summary = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("прогон завершается");
+    assert_eq!(summary.messages_sent, 1);
+    assert!(!orchestrator.state().backlog.contains_key(&30));
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    assert_eq!(sent.last().map(|(chat_id, _)| *chat_id), Some(30));
+}
+
+#[tokio::test]
+async fn failed_chat_does_not_block_other_chats_or_their_history() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history_path = dir.path().join("history.txt");
+    let history = SentHistory::with_store(&history_path, "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let mut english = sample_release(1, "Shared");
+    english.original_language = "en".to_string();
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![english, sample_release(2, "Только для второго")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+    dispatcher
+        .failing
+        .lock()
+        .expect("блокировка доступна")
+        .insert(20);
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![
+                ChatConfig {
+                    chat_id: 10,
+                    locales: vec!["en".to_string()],
+                    regions: Vec::new(),
+                    collections: Vec::new(),
+                    cadence: Cadence::Instant,
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                },
+                ChatConfig {
+                    chat_id: 20,
+                    locales: Vec::new(),
+                    regions: Vec::new(),
+                    collections: Vec::new(),
+                    cadence: Cadence::Instant,
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                },
+            ],
+        },
+    );
+
+    let summary = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("ошибка одного чата не прерывает прогон");
+
+    assert_eq!(summary.failed_chats(), vec![20]);
+    assert_eq!(summary.chats.len(), 2);
+    assert_eq!(summary.chats[0].outcome, DeliveryOutcome::Delivered);
+    assert_eq!(summary.chats[0].releases, 1);
+    assert!(matches!(
+        summary.chats[1].outcome,
+        DeliveryOutcome::Failed { ref error } if error.contains("сеть недоступна")
+    ));
+    assert_eq!(summary.messages_sent, 1);
+    assert_eq!(summary.sent_releases, 1);
+    assert!(summary.render_markdown().contains("чат 20: ошибка"));
+
+    let saved = std::fs::read_to_string(&history_path).expect("история сохраняется");
+    let ids: Vec<&str> = saved.lines().collect();
+    assert_eq!(ids, vec!["1"], "недоставленный релиз остаётся кандидатом");
+}
+
+#[tokio::test]
+async fn failed_chat_catches_up_from_its_own_history() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history_path = dir.path().join("history.txt");
+    std::fs::write(&history_path, "1").expect("общая история записывается");
+    let history = SentHistory::with_store(&history_path, "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let chat_history_path = dir.path().join("sent_per_chat.json");
+    let chat_history =
+        ChatHistoryStore::with_store(&chat_history_path, "sent-per-chat", store.clone());
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![sample_release(1, "Старый"), sample_release(2, "Новый")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+    dispatcher
+        .failing
+        .lock()
+        .expect("блокировка доступна")
+        .insert(20);
+    let chat = |chat_id| ChatConfig {
+        chat_id,
+        locales: Vec::new(),
+        regions: Vec::new(),
+        collections: Vec::new(),
+        cadence: Cadence::Instant,
+        timezone: Tz::UTC,
+        quiet_hours: None,
+        topics: ForumTopics::default(),
+        message_options: ChatMessageOptions::default(),
+    };
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![chat(10), chat(20)],
+        },
+    )
+    .with_chat_history_store(chat_history);
+
+    let first = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("первый прогон завершается");
+    dispatcher
+        .failing
+        .lock()
+        .expect("блокировка доступна")
+        .clear();
+    let second = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("второй прогон завершается");
+
+    assert_eq!(first.failed_chats(), vec![20]);
+    assert_eq!(first.duplicates, 1, "фильм 1 перенесён из общей истории");
+    assert_eq!(second.chats.len(), 1);
+    assert_eq!(second.chats[0].chat_id, 20);
+    assert_eq!(second.chats[0].releases, 1);
+
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].0, 10);
+    assert_eq!(sent[1].0, 20);
+    assert!(sent.iter().all(|(_, texts)| texts[0].contains("Новый")));
+    assert!(sent.iter().all(|(_, texts)| !texts[0].contains("Старый")));
+
+    let saved = std::fs::read_to_string(&chat_history_path).expect("история чатов сохраняется");
+    assert!(saved.contains("\"migrated_from_global\": true"));
+    assert!(saved.contains("movie:2"));
+}
+
+#[tokio::test]
+async fn outbox_resumes_pending_and_skips_sent_messages() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let state_path = dir.path().join("bot_state.json");
+    std::fs::write(
+        &state_path,
+        r#"{
+            "outbox": [
+                {"chat_id": 10, "messages": ["досылка"], "event_keys": ["movie:1"], "status": "pending"},
+                {"chat_id": 10, "messages": ["уже ушло"], "event_keys": ["movie:2"], "status": "sent"}
+            ]
+        }"#,
+    )
+    .expect("состояние записывается");
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![sample_release(1, "Первый"), sample_release(2, "Второй")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![ChatConfig {
+                chat_id: 10,
+                locales: Vec::new(),
+                regions: Vec::new(),
+                collections: Vec::new(),
+                cadence: Cadence::Instant,
+                timezone: Tz::UTC,
+                quiet_hours: None,
+                topics: ForumTopics::default(),
+                message_options: ChatMessageOptions::default(),
+            }],
+        },
+    )
+    .with_chat_history_store(ChatHistoryStore::with_store(
+        dir.path().join("sent_per_chat.json"),
+        "sent-per-chat",
+        store.clone(),
+    ))
+    .with_state_store(BotStateStore::with_store(
+        &state_path,
+        "bot-state",
+        store.clone(),
+    ));
+
+    let summary = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("прогон завершается");
+
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    assert_eq!(*sent, vec![(10, vec!["досылка".to_string()])]);
+    assert_eq!(summary.resumed_messages, 1);
+    assert_eq!(summary.messages_sent, 0, "оба релиза уже в истории чата");
+
+    let saved = std::fs::read_to_string(&state_path).expect("состояние сохраняется");
+    assert!(saved.contains("\"outbox\": []"), "outbox очищен: {saved}");
+}
+
+#[tokio::test]
+async fn possible_duplicates_are_kept_in_state() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let state_path = dir.path().join("bot_state.json");
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![sample_release(1, "Первый")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+    dispatcher
+        .duplicates
+        .lock()
+        .expect("блокировка доступна")
+        .push(PossibleDuplicate {
+            chat_id: 10,
+            message_id: Some(1),
+        });
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![ChatConfig {
+                chat_id: 10,
+                locales: Vec::new(),
+                regions: Vec::new(),
+                collections: Vec::new(),
+                cadence: Cadence::Instant,
+                timezone: Tz::UTC,
+                quiet_hours: None,
+                topics: ForumTopics::default(),
+                message_options: ChatMessageOptions::default(),
+            }],
+        },
+    )
+    .with_state_store(BotStateStore::with_store(
+        &state_path,
+        "bot-state",
+        store.clone(),
+    ));
+
+    let now = Utc::now();
+    let summary = orchestrator.run(now).await.expect("прогон завершается");
+    assert_eq!(summary.possible_duplicates, 1);
+
+    let state = BotStateStore::with_store(&state_path, "bot-state", MemoryStore::default())
+        .restore()
+        .expect("состояние читается");
+    assert_eq!(
+        state.possible_duplicates,
+        vec![PossibleDuplicateRecord {
+            chat_id: 10,
+            message_id: Some(1),
+            detected_at: now,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn partially_sent_chat_resumes_from_first_unsent_message() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let state_path = dir.path().join("bot_state.json");
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    // Два релиза не помещаются в одно сообщение Telegram и уходят отдельными.
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![
+            sample_release(1, &"А".repeat(1500)),
+            sample_release(2, &"Б".repeat(1500)),
+        ],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+    dispatcher
+        .fail_after
+        .lock()
+        .expect("блокировка доступна")
+        .insert(99, 1);
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig::single_global_chat(99),
+    )
+    .with_chat_history_store(ChatHistoryStore::with_store(
+        dir.path().join("sent_per_chat.json"),
+        "sent-per-chat",
+        store.clone(),
+    ))
+    .with_state_store(BotStateStore::with_store(
+        &state_path,
+        "bot-state",
+        store.clone(),
+    ));
+
+    let first = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("первый прогон завершается");
+    assert_eq!(first.failed_chats(), vec![99]);
+    assert_eq!(first.messages_sent, 1);
+    let outbox = &orchestrator.state().outbox;
+    assert_eq!(
+        outbox.len(),
+        1,
+        "недосланная запись ждёт следующего прогона"
+    );
+    assert_eq!(outbox[0].message_ids, vec![Some(1)]);
+    assert_eq!(orchestrator.state().announcements.len(), 1);
+
+    let second = orchestrator
+        .run(Utc::now())
+        .await
+        .expect("второй прогон завершается");
+    assert_eq!(second.resumed_messages, 1);
+    assert_eq!(second.messages_sent, 0, "оба релиза уже в истории чата");
+
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    assert_eq!(sent.len(), 2);
+    assert!(sent[0].1[0].contains(&"А".repeat(1500)));
+    assert!(sent[1].1[0].contains(&"Б".repeat(1500)));
+    assert_eq!(
+        sent[1].1.len(),
+        1,
+        "подтверждённое сообщение не повторяется"
+    );
+    assert!(orchestrator.state().outbox.is_empty());
+    assert_eq!(orchestrator.state().announcements.len(), 2);
+}
+
+#[derive(Default, Clone)]
+struct FailingUploadStore;
+
+impl ArtifactStore for FailingUploadStore {
+    fn download_artifact(&self, _artifact_name: &str) -> Result<Option<Vec<u8>>, ArtifactError> {
+        Ok(None)
+    }
+
+    fn upload_artifact(
+        &self,
+        _artifact_name: &str,
+        _file_name: &str,
+        _content: &[u8],
+    ) -> Result<(), ArtifactError> {
+        Err(ArtifactError::Io(std::io::Error::other(
+            "артефакты недоступны",
+        )))
+    }
+}
+
+#[tokio::test]
+async fn unsaved_outbox_blocks_dispatch() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let history = SentHistory::with_store(
+        dir.path().join("history.txt"),
+        "artifact",
+        FailingUploadStore,
+    );
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        FailingUploadStore,
+    );
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![sample_release(1, "Первый")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![ChatConfig {
+                chat_id: 10,
+                locales: Vec::new(),
+                regions: Vec::new(),
+                collections: Vec::new(),
+                cadence: Cadence::Instant,
+                timezone: Tz::UTC,
+                quiet_hours: None,
+                topics: ForumTopics::default(),
+                message_options: ChatMessageOptions::default(),
+            }],
+        },
+    )
+    .with_state_store(BotStateStore::with_store(
+        dir.path().join("bot_state.json"),
+        "bot-state",
+        FailingUploadStore,
+    ));
+
+    assert!(orchestrator.run(Utc::now()).await.is_err());
+    assert!(
+        dispatcher
+            .sent
+            .lock()
+            .expect("блокировка доступна")
+            .is_empty()
+    );
+}
+
+/// Хранилище, которое принимает первые `left` загрузок, а дальше отказывает.
+#[derive(Clone)]
+struct LimitedUploadStore {
+    left: Arc<Mutex<usize>>,
+}
+
+impl ArtifactStore for LimitedUploadStore {
+    fn download_artifact(&self, _artifact_name: &str) -> Result<Option<Vec<u8>>, ArtifactError> {
+        Ok(None)
+    }
+
+    fn upload_artifact(
+        &self,
+        _artifact_name: &str,
+        _file_name: &str,
+        _content: &[u8],
+    ) -> Result<(), ArtifactError> {
+        let mut left = self.left.lock().expect("блокировка доступна");
+        if *left == 0 {
+            return Err(ArtifactError::Io(std::io::Error::other(
+                "артефакты недоступны",
+            )));
+        }
+        *left -= 1;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn unsaved_dispatch_progress_stops_run() {
+    let dir = tempdir().expect("временная директория создаётся");
+    // Outbox сохраняется до рассылки, а прогресс после первого чата — уже нет.
+    let store = LimitedUploadStore {
+        left: Arc::new(Mutex::new(1)),
+    };
+    let history =
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone());
+    let tv_history = SentEventHistory::with_store(
+        dir.path().join("tv_history.txt"),
+        "tv-artifact",
+        store.clone(),
+    );
+    let provider = StubProvider::new(ReleaseBatch {
+        movies: vec![sample_release(1, "Первый")],
+        ..ReleaseBatch::default()
+    });
+    let dispatcher = StubDispatcher::default();
+    let chat = |chat_id| ChatConfig {
+        chat_id,
+        locales: Vec::new(),
+        regions: Vec::new(),
+        collections: Vec::new(),
+        cadence: Cadence::Instant,
+        timezone: Tz::UTC,
+        quiet_hours: None,
+        topics: ForumTopics::default(),
+        message_options: ChatMessageOptions::default(),
+    };
+
+    let mut orchestrator = Orchestrator::new(
+        history,
+        tv_history,
+        provider,
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![chat(10), chat(20)],
+        },
+    )
+    .with_state_store(BotStateStore::with_store(
+        dir.path().join("bot_state.json"),
+        "bot-state",
+        store,
+    ));
+
+    assert!(matches!(
+        orchestrator.run(Utc::now()).await,
+        Err(OrchestratorError::State(_))
+    ));
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    assert_eq!(sent.len(), 1, "после сбоя сохранения рассылка остановлена");
+}
+
+#[tokio::test]
+async fn releases_over_chat_limit_are_queued_and_age_out() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let state_path = dir.path().join("bot_state.json");
+    let settings = OrchestratorSettings {
+        backlog: BacklogConfig {
+            default: BacklogPolicy::default(),
+            chats: HashMap::from([(
+                10,
+                BacklogPolicy {
+                    max_releases_per_run: 1,
+                    max_age_days: 30,
+                },
+            )]),
+        },
+        ..OrchestratorSettings::default()
+    };
+    let build = |batch: ReleaseBatch, dispatcher: StubDispatcher| {
+        Orchestrator::new(
+            SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+            SentEventHistory::with_store(
+                dir.path().join("tv_history.txt"),
+                "tv-artifact",
+                store.clone(),
+            ),
+            StubProvider::new(batch),
+            dispatcher,
+            TelegramConfig {
+                chats: vec![ChatConfig {
+                    chat_id: 10,
+                    locales: Vec::new(),
+                    regions: Vec::new(),
+                    collections: Vec::new(),
+                    cadence: Cadence::Instant,
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                }],
+            },
+        )
+        .with_settings(settings.clone())
+        .with_state_store(BotStateStore::with_store(
+            &state_path,
+            "bot-state",
+            store.clone(),
+        ))
+    };
+    let at = |raw: &str| {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("валидная дата")
+            .with_timezone(&Utc)
+    };
+
+    let mut best = sample_release(1, "Лучший");
+    best.vote_average = Some(9.0);
+    let mut worst = sample_release(3, "Худший");
+    worst.vote_average = Some(5.0);
+    let dispatcher = StubDispatcher::default();
+    let mut orchestrator = build(
+        ReleaseBatch {
+            movies: vec![worst, sample_release(2, "Средний"), best],
+            ..ReleaseBatch::default()
+        },
+        dispatcher.clone(),
+    );
+
+    let first = orchestrator
+        .run(at("2024-01-02T09:00:00Z"))
+        .await
+        .expect("первый прогон завершается");
+    let second = orchestrator
+        .run(at("2024-01-02T15:00:00Z"))
+        .await
+        .expect("второй прогон завершается");
+
+    assert_eq!(
+        (first.sent_releases, first.truncated, first.backlog),
+        (1, 2, 2)
+    );
+    assert_eq!(
+        (second.sent_releases, second.truncated, second.backlog),
+        (1, 0, 1)
+    );
+    {
+        let sent = dispatcher.sent.lock().expect("блокировка доступна");
+        assert!(sent[0].1[0].contains("Лучший"));
+        assert!(sent[1].1[0].contains("Средний"));
+    }
+
+    // Месяц спустя релиз уже вне окна, а его очередь устарела.
+    let late_dispatcher = StubDispatcher::default();
+    let mut late = build(ReleaseBatch::default(), late_dispatcher.clone());
+    let third = late
+        .run(at("2024-03-01T09:00:00Z"))
+        .await
+        .expect("третий прогон завершается");
+
+    assert_eq!((third.sent_releases, third.backlog), (0, 0));
+    assert!(
+        late_dispatcher
+            .sent
+            .lock()
+            .expect("блокировка доступна")
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn preview_renders_messages_without_sending_or_persisting() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let history_path = dir.path().join("history.txt");
+    let state_path = dir.path().join("bot_state.json");
+    let dispatcher = StubDispatcher::default();
+
+    let mut orchestrator = Orchestrator::new(
+        SentHistory::with_store(&history_path, "artifact", store.clone()),
+        SentEventHistory::with_store(
+            dir.path().join("tv_history.txt"),
+            "tv-artifact",
+            store.clone(),
+        ),
+        StubProvider::new(ReleaseBatch {
+            movies: vec![sample_release(1, "Предпросмотр")],
+            ..ReleaseBatch::default()
+        }),
+        dispatcher.clone(),
+        TelegramConfig::single_global_chat(10),
+    )
+    .with_state_store(BotStateStore::with_store(
+        &state_path,
+        "bot-state",
+        store.clone(),
+    ));
+
+    let chats = orchestrator
+        .preview(Utc::now())
+        .await
+        .expect("предпросмотр строится");
+
+    assert_eq!(chats.len(), 1);
+    assert_eq!(chats[0].chat_id, 10);
+    assert_eq!(chats[0].event_keys, vec!["movie:1".to_string()]);
+    assert!(chats[0].messages[0].contains("Предпросмотр"));
+    assert!(
+        dispatcher
+            .sent
+            .lock()
+            .expect("блокировка доступна")
+            .is_empty()
+    );
+    assert!(
+        store
+            .uploads
+            .lock()
+            .expect("блокировка доступна")
+            .is_empty()
+    );
+    assert!(!state_path.exists());
+}
+
+#[tokio::test]
+async fn daily_digest_chat_accumulates_until_its_slot() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let dispatcher = StubDispatcher::default();
+    let mut series = sample_release(2, "Второй");
+    series.vote_average = Some(6.0);
+
+    let mut orchestrator = Orchestrator::new(
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+        SentEventHistory::with_store(
+            dir.path().join("tv_history.txt"),
+            "tv-artifact",
+            store.clone(),
+        ),
+        StubProvider::new(ReleaseBatch {
+            movies: vec![sample_release(1, "Первый"), series],
+            ..ReleaseBatch::default()
+        }),
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![
+                ChatConfig {
+                    chat_id: 10,
+                    locales: Vec::new(),
+                    regions: Vec::new(),
+                    collections: Vec::new(),
+                    cadence: Cadence::Instant,
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                },
+                ChatConfig {
+                    chat_id: 20,
+                    locales: Vec::new(),
+                    regions: Vec::new(),
+                    collections: Vec::new(),
+                    cadence: Cadence::Daily { hour: 9 },
+                    timezone: Tz::UTC,
+                    quiet_hours: None,
+                    topics: ForumTopics::default(),
+                    message_options: ChatMessageOptions::default(),
+                },
+            ],
+        },
+    );
+    let at = |raw: &str| {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("валидная дата")
+            .with_timezone(&Utc)
+    };
+
+    let first = orchestrator
+        .run(at("2024-01-02T08:00:00Z"))
+        .await
+        .expect("прогон завершается");
+    let before_slot = orchestrator
+        .run(at("2024-01-02T08:30:00Z"))
+        .await
+        .expect("прогон завершается");
+    let in_slot = orchestrator
+        .run(at("2024-01-02T09:05:00Z"))
+        .await
+        .expect("прогон завершается");
+    let after_slot = orchestrator
+        .run(at("2024-01-02T12:00:00Z"))
+        .await
+        .expect("прогон завершается");
+
+    assert_eq!(first.chats.len(), 1, "мгновенный чат получает релизы сразу");
+    assert!(before_slot.chats.is_empty());
+    assert_eq!(in_slot.chats.len(), 1);
+    assert_eq!(in_slot.chats[0].chat_id, 20);
+    assert_eq!(in_slot.chats[0].releases, 2);
+    assert!(after_slot.chats.is_empty());
+
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    assert_eq!(sent.len(), 2);
+    assert!(sent[1].1[0].starts_with("🗓 Дайджест релизов за день"));
+    assert!(sent[1].1[0].contains("🎬 Фильмы (2):"));
+    assert!(orchestrator.state().digests[&20].releases.is_empty());
+}
+
+#[tokio::test]
+async fn quiet_hours_defer_or_silence_delivery_in_chat_timezone() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let dispatcher = StubDispatcher::default();
+    let chat = |chat_id, timezone, quiet_hours| ChatConfig {
+        timezone,
+        quiet_hours,
+        ..ChatConfig::new(chat_id)
+    };
+
+    let mut orchestrator = Orchestrator::new(
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+        SentEventHistory::with_store(
+            dir.path().join("tv_history.txt"),
+            "tv-artifact",
+            store.clone(),
+        ),
+        StubProvider::new(ReleaseBatch {
+            movies: vec![sample_release(1, "Ночной")],
+            ..ReleaseBatch::default()
+        }),
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![
+                // Москва, тихо с 23 до 8: в 06:00 UTC там 09:00.
+                chat(
+                    10,
+                    Tz::Europe__Moscow,
+                    Some(QuietHours {
+                        start: 23,
+                        end: 8,
+                        mode: QuietMode::Defer,
+                    }),
+                ),
+                // Нью-Йорк, тихо с 22 до 7, но без откладывания.
+                chat(
+                    20,
+                    Tz::America__New_York,
+                    Some(QuietHours {
+                        start: 22,
+                        end: 7,
+                        mode: QuietMode::Silent,
+                    }),
+                ),
+                chat(30, Tz::UTC, None),
+            ],
+        },
+    );
+    let at = |raw: &str| {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("валидная дата")
+            .with_timezone(&Utc)
+    };
+
+    let night = orchestrator
+        .run(at("2024-01-02T03:00:00Z"))
+        .await
+        .expect("ночной прогон завершается");
+    let morning = orchestrator
+        .run(at("2024-01-02T06:00:00Z"))
+        .await
+        .expect("утренний прогон завершается");
+
+    assert_eq!(night.quiet_deferred, 1);
+    assert_eq!(night.backlog, 1);
+    assert_eq!(morning.backlog, 0);
+    let sent: Vec<i64> = dispatcher
+        .sent
+        .lock()
+        .expect("блокировка доступна")
+        .iter()
+        .map(|(chat_id, _)| *chat_id)
+        .collect();
+    assert_eq!(sent, vec![20, 30, 10]);
+    assert_eq!(
+        *dispatcher.silent.lock().expect("блокировка доступна"),
+        vec![20]
+    );
+}
+
+#[test]
+fn digest_slots_follow_daylight_saving_time() {
+    let at = |raw: &str| {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("валидная дата")
+            .with_timezone(&Utc)
+    };
+    let daily = Cadence::Daily { hour: 9 };
+    let berlin = Tz::Europe__Berlin;
+
+    // Зимой 09:00 в Берлине — 08:00 UTC, летом — 07:00 UTC.
+    assert!(!daily.is_due(
+        Some(at("2024-01-14T08:00:00Z")),
+        at("2024-01-15T07:30:00Z"),
+        berlin
+    ));
+    assert!(daily.is_due(
+        Some(at("2024-01-14T08:00:00Z")),
+        at("2024-01-15T08:00:00Z"),
+        berlin
+    ));
+    assert!(daily.is_due(
+        Some(at("2024-07-14T07:00:00Z")),
+        at("2024-07-15T07:00:00Z"),
+        berlin
+    ));
+    assert!(!daily.is_due(
+        Some(at("2024-07-14T07:00:00Z")),
+        at("2024-07-15T06:59:00Z"),
+        berlin
+    ));
+
+    // 02:00 31 марта в Берлине пропускается: слот сдвигается на 03:00 CEST.
+    let night = Cadence::Weekly {
+        weekday: Weekday::Sun,
+        hour: 2,
+    };
+    assert!(!night.is_due(
+        Some(at("2024-03-24T01:00:00Z")),
+        at("2024-03-31T00:30:00Z"),
+        berlin
+    ));
+    assert!(night.is_due(
+        Some(at("2024-03-24T01:00:00Z")),
+        at("2024-03-31T01:00:00Z"),
+        berlin
+    ));
+}
+
+#[tokio::test]
+async fn scoring_highlights_top_releases_and_silences_low_scores() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let dispatcher = StubDispatcher::default();
+    let chat = |chat_id, locale: &str| ChatConfig {
+        locales: vec![locale.to_string()],
+        ..ChatConfig::new(chat_id)
+    };
+
+    let mut hit = sample_release(1, "Хит");
+    hit.popularity = 900.0;
+    hit.vote_average = Some(8.8);
+    let mut obscure = sample_release(3, "Малоизвестный");
+    obscure.original_language = "en".to_string();
+    obscure.popularity = 0.0;
+    obscure.vote_average = None;
+    obscure.vote_count = None;
+    obscure.watch_providers = Vec::new();
+    let mut orchestrator = Orchestrator::new(
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+        SentEventHistory::with_store(
+            dir.path().join("tv_history.txt"),
+            "tv-artifact",
+            store.clone(),
+        ),
+        StubProvider::new(ReleaseBatch {
+            movies: vec![sample_release(2, "Обычный"), hit, obscure],
+            ..ReleaseBatch::default()
+        }),
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![chat(10, "ru"), chat(20, "en")],
+        },
+    )
+    .with_settings(OrchestratorSettings {
+        scoring: ScoringConfig {
+            highlight_top: 1,
+            silent_below: Some(4.0),
+            ..ScoringConfig::default()
+        },
+        ..OrchestratorSettings::default()
+    });
+    let now = DateTime::parse_from_rfc3339("2024-01-02T09:00:00Z")
+        .expect("валидная дата")
+        .with_timezone(&Utc);
+
+    let preview = orchestrator
+        .preview(now)
+        .await
+        .expect("предпросмотр строится");
+    assert!(preview[0].scores[0].starts_with("movie:1: "));
+    assert!(preview[0].scores[0].contains("популярность"));
+
+    orchestrator.run(now).await.expect("прогон завершается");
+
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    let lines: Vec<&str> = sent[0].1[0].lines().collect();
+    assert_eq!(sent[0].0, 10);
+    assert!(lines[0].starts_with("🔥 Хит"));
+    assert!(sent[0].1[0].contains("\n🎬 Обычный"));
+    assert_eq!(sent[1].0, 20);
+    assert!(sent[1].1[0].starts_with("🔥 Малоизвестный"));
+    assert_eq!(
+        *dispatcher.silent.lock().expect("блокировка доступна"),
+        vec![20]
+    );
+}
+
+#[tokio::test]
+async fn sent_messages_are_edited_when_tmdb_data_changes() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let dispatcher = StubDispatcher::default();
+    let build = |movies: Vec<MovieRelease>| {
+        Orchestrator::new(
+            SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+            SentEventHistory::with_store(
+                dir.path().join("tv_history.txt"),
+                "tv-artifact",
+                store.clone(),
+            ),
+            StubProvider::new(ReleaseBatch {
+                movies,
+                ..ReleaseBatch::default()
+            }),
+            dispatcher.clone(),
+            TelegramConfig {
+                chats: vec![ChatConfig::new(10)],
+            },
+        )
+        .with_state_store(BotStateStore::with_store(
+            dir.path().join("bot_state.json"),
+            "bot-state",
+            store.clone(),
+        ))
+    };
+    let at = |raw: &str| {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("валидная дата")
+            .with_timezone(&Utc)
+    };
+
+    let mut first = build(vec![
+        sample_release(1, "Фильм"),
+        sample_release(2, "Другой"),
+    ]);
+    first
+        .run(at("2024-01-02T09:00:00Z"))
+        .await
+        .expect("первый прогон завершается");
+
+    let mut rerated = sample_release(1, "Фильм");
+    rerated.vote_average = Some(8.1);
+    let mut second = build(vec![rerated, sample_release(2, "Другой")]);
+    let summary = second
+        .run(at("2024-01-03T09:00:00Z"))
+        .await
+        .expect("второй прогон завершается");
+    let mut third = build(vec![sample_release(2, "Другой")]);
+    let unchanged = third
+        .run(at("2024-01-04T09:00:00Z"))
+        .await
+        .expect("третий прогон завершается");
+
+    assert_eq!((summary.messages_sent, summary.edited_messages), (0, 1));
+    assert_eq!(unchanged.edited_messages, 0);
+    assert_eq!(
+        dispatcher.sent.lock().expect("блокировка доступна").len(),
+        1
+    );
+    let edits = dispatcher.edits.lock().expect("блокировка доступна");
+    assert_eq!(edits.len(), 1);
+    let (chat_id, message_id, text) = &edits[0];
+    assert_eq!((*chat_id, *message_id), (10, 1));
+    assert!(text.contains("Фильм — 2024-01-01 · ⭐ 8.1"));
+    assert!(text.contains("Другой — 2024-01-01 · ⭐ 7.2"));
+}
+
+#[tokio::test]
+async fn moved_digital_date_corrects_announcement_and_reopens_history() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let moved_to = NaiveDate::from_ymd_opt(2024, 2, 1).expect("валидная дата");
+    let build = |movies: Vec<MovieRelease>,
+                 digital_releases: HashMap<u64, Option<DigitalReleaseDate>>,
+                 action: CorrectionAction,
+                 dispatcher: StubDispatcher| {
+        let mut provider = StubProvider::new(ReleaseBatch {
+            movies,
+            ..ReleaseBatch::default()
+        });
+        provider.digital_releases = digital_releases;
+        Orchestrator::new(
+            SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+            SentEventHistory::with_store(
+                dir.path().join("tv_history.txt"),
+                "tv-artifact",
+                store.clone(),
+            ),
+            provider,
+            dispatcher,
+            TelegramConfig {
+                chats: vec![ChatConfig::new(10), ChatConfig::new(20)],
+            },
+        )
+        .with_settings(OrchestratorSettings {
+            corrections: CorrectionPolicy {
+                recheck_days: 14,
+                action,
+                ..CorrectionPolicy::default()
+            },
+            ..OrchestratorSettings::default()
+        })
+        .with_state_store(BotStateStore::with_store(
+            dir.path().join("bot_state.json"),
+            "bot-state",
+            store.clone(),
+        ))
+        .with_chat_history_store(ChatHistoryStore::with_store(
+            dir.path().join("chat_history.json"),
+            "chat-history",
+            store.clone(),
+        ))
+    };
+    let at = |raw: &str| {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("валидная дата")
+            .with_timezone(&Utc)
+    };
+
+    let dispatcher = StubDispatcher::default();
+    build(
+        vec![
+            sample_release(1, "Перенесённый"),
+            sample_release(2, "Настоящий"),
+        ],
+        HashMap::new(),
+        CorrectionAction::Post,
+        dispatcher.clone(),
+    )
+    .run(at("2024-01-02T09:00:00Z"))
+    .await
+    .expect("анонс отправлен");
+
+    // TMDB перенёс фильм 1 на февраль; фильм 2 вышел как объявлено.
+    let summary = build(
+        Vec::new(),
+        HashMap::from([
+            (1, Some(digital_release(moved_to, &[]))),
+            (
+                2,
+                Some(digital_release(
+                    NaiveDate::from_ymd_opt(2024, 1, 1).expect("валидная дата"),
+                    &[],
+                )),
+            ),
+        ]),
+        CorrectionAction::Edit,
+        dispatcher.clone(),
+    )
+    .run(at("2024-01-03T09:00:00Z"))
+    .await
+    .expect("поправка отправлена");
+
+    assert_eq!(summary.corrections, 2);
+    {
+        let edits = dispatcher.edits.lock().expect("блокировка доступна");
+        assert_eq!(edits.len(), 2);
+        assert!(
+            edits[0]
+                .2
+                .contains("⚠️ Перенесённый — цифровой релиз перенесён на 2024-02-01")
+        );
+        assert!(edits[0].2.contains("🔥 Настоящий — 2024-01-01"));
+    }
+
+    // В настоящую дату фильм анонсируется снова, а повторной поправки нет.
+    let mut released = sample_release(1, "Перенесённый");
+    released.digital_release_date = moved_to;
+    let summary = build(
+        vec![released],
+        HashMap::from([(1, Some(digital_release(moved_to, &[])))]),
+        CorrectionAction::Edit,
+        dispatcher.clone(),
+    )
+    .run(at("2024-02-01T09:00:00Z"))
+    .await
+    .expect("повторный анонс отправлен");
+
+    assert_eq!((summary.corrections, summary.sent_releases), (0, 1));
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    assert_eq!(sent.len(), 4);
+    assert!(sent[2].1[0].contains("Перенесённый — 2024-02-01"));
+}
+
+#[tokio::test]
+async fn rechecks_skip_fresh_announcements_and_rotate_under_cap() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let released = NaiveDate::from_ymd_opt(2024, 1, 1).expect("валидная дата");
+    let mut provider = StubProvider::new(ReleaseBatch {
+        movies: vec![
+            sample_release(1, "Первый"),
+            sample_release(2, "Второй"),
+            sample_release(3, "Третий"),
+        ],
+        ..ReleaseBatch::default()
+    });
+    provider.digital_releases = (1..=3)
+        .map(|id| (id, Some(digital_release(released, &[]))))
+        .collect();
+    let build = || {
+        Orchestrator::new(
+            SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+            SentEventHistory::with_store(
+                dir.path().join("tv_history.txt"),
+                "tv-artifact",
+                store.clone(),
+            ),
+            provider.clone(),
+            StubDispatcher::default(),
+            TelegramConfig {
+                chats: vec![ChatConfig::new(10)],
+            },
+        )
+        .with_settings(OrchestratorSettings {
+            corrections: CorrectionPolicy {
+                max_rechecks: 2,
+                ..CorrectionPolicy::default()
+            },
+            ..OrchestratorSettings::default()
+        })
+        .with_state_store(BotStateStore::with_store(
+            dir.path().join("bot_state.json"),
+            "bot-state",
+            store.clone(),
+        ))
+    };
+    let at = |raw: &str| {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("валидная дата")
+            .with_timezone(&Utc)
+    };
+    let rechecked =
+        || std::mem::take(&mut *provider.rechecked.lock().expect("блокировка доступна"));
+
+    for (run, expected) in [
+        ("2024-01-02T09:00:00Z", vec![]),
+        ("2024-01-03T09:00:00Z", vec![1, 2]),
+        ("2024-01-04T09:00:00Z", vec![3, 1]),
+    ] {
+        build().run(at(run)).await.expect("прогон завершается");
+        assert_eq!(rechecked(), expected, "прогон {run}");
+    }
+}
+
+#[tokio::test]
+async fn regional_announcement_is_rechecked_against_its_region() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let announced = NaiveDate::from_ymd_opt(2024, 1, 1).expect("валидная дата");
+    let moved_to = NaiveDate::from_ymd_opt(2024, 3, 1).expect("валидная дата");
+    let dispatcher = StubDispatcher::default();
+    let build = |movies: Vec<MovieRelease>,
+                 digital_releases: HashMap<u64, Option<DigitalReleaseDate>>| {
+        let mut provider = StubProvider::new(ReleaseBatch {
+            movies,
+            ..ReleaseBatch::default()
+        });
+        provider.digital_releases = digital_releases;
+        Orchestrator::new(
+            SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+            SentEventHistory::with_store(
+                dir.path().join("tv_history.txt"),
+                "tv-artifact",
+                store.clone(),
+            ),
+            provider,
+            dispatcher.clone(),
+            TelegramConfig {
+                chats: vec![
+                    ChatConfig::new(10),
+                    ChatConfig {
+                        regions: vec!["BR".to_string()],
+                        ..ChatConfig::new(20)
+                    },
+                ],
+            },
+        )
+        .with_settings(OrchestratorSettings {
+            corrections: CorrectionPolicy {
+                recheck_days: 14,
+                action: CorrectionAction::Post,
+                ..CorrectionPolicy::default()
+            },
+            ..OrchestratorSettings::default()
+        })
+        .with_state_store(BotStateStore::with_store(
+            dir.path().join("bot_state.json"),
+            "bot-state",
+            store.clone(),
+        ))
+        .with_chat_history_store(ChatHistoryStore::with_store(
+            dir.path().join("chat_history.json"),
+            "chat-history",
+            store.clone(),
+        ))
+    };
+    let at = |raw: &str| {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("валидная дата")
+            .with_timezone(&Utc)
+    };
+
+    let mut release = sample_release(1, "Фильм");
+    release.regional_digital_dates = [("BR".to_string(), announced)].into();
+    build(vec![release], HashMap::new())
+        .run(at("2024-01-02T09:00:00Z"))
+        .await
+        .expect("анонсы отправлены");
+
+    // В приоритетном регионе дату перенесли, в Бразилии фильм уже вышел.
+    let summary = build(
+        Vec::new(),
+        HashMap::from([(
+            1,
+            Some(digital_release(
+                moved_to,
+                &[("US", moved_to), ("BR", announced)],
+            )),
+        )]),
+    )
+    .run(at("2024-01-03T09:00:00Z"))
+    .await
+    .expect("перепроверка завершается");
+
+    assert_eq!(summary.corrections, 1);
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    let chats: Vec<i64> = sent.iter().map(|(chat_id, _)| *chat_id).collect();
+    assert_eq!(
+        chats,
+        vec![10, 20, 10],
+        "поправка только для чата без регионов"
+    );
+    assert!(sent[2].1[0].contains("цифровой релиз перенесён на 2024-03-01"));
+}
+
+#[tokio::test]
+async fn forum_chat_releases_are_routed_to_topics_by_genre_and_kind() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let dispatcher = StubDispatcher::default();
+    let forum = ChatConfig {
+        topics: ForumTopics {
+            movies: Some(12),
+            genres: [("horror".to_string(), 20)].into_iter().collect(),
+            ..ForumTopics::default()
+        },
+        ..ChatConfig::new(-100)
+    };
+
+    let mut horror = sample_release(2, "Ужасы");
+    horror.genres = vec!["Horror".to_string(), "Drama".to_string()];
+    let mut orchestrator = Orchestrator::new(
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+        SentEventHistory::with_store(
+            dir.path().join("tv_history.txt"),
+            "tv-artifact",
+            store.clone(),
+        ),
+        StubProvider::new(ReleaseBatch {
+            movies: vec![sample_release(1, "Драма"), horror],
+            ..ReleaseBatch::default()
+        }),
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![forum, ChatConfig::new(10)],
+        },
+    );
+    let now = DateTime::parse_from_rfc3339("2024-01-02T09:00:00Z")
+        .expect("валидная дата")
+        .with_timezone(&Utc);
+
+    let preview = orchestrator
+        .preview(now)
+        .await
+        .expect("предпросмотр строится");
+    let threads: Vec<ThreadEntry> = preview
+        .iter()
+        .map(|chat| (chat.chat_id, chat.thread_id))
+        .collect();
+    assert_eq!(
+        threads,
+        vec![(-100, Some(12)), (-100, Some(20)), (10, None)]
+    );
+
+    let summary = orchestrator.run(now).await.expect("прогон завершается");
+
+    assert_eq!(summary.chats.len(), 3);
+    assert_eq!(
+        *dispatcher.threads.lock().expect("блокировка доступна"),
+        vec![(-100, Some(12)), (-100, Some(20)), (10, None)]
+    );
+    let sent = dispatcher.sent.lock().expect("блокировка доступна");
+    assert!(sent[0].1[0].contains("Драма") && !sent[0].1[0].contains("Ужасы"));
+    assert!(sent[1].1[0].contains("Ужасы") && !sent[1].1[0].contains("Драма"));
+    assert!(sent[2].1[0].contains("Драма") && sent[2].1[0].contains("Ужасы"));
+}
+
+#[tokio::test]
+async fn chat_message_options_apply_per_release_kind() {
+    let dir = tempdir().expect("временная директория создаётся");
+    let store = MemoryStore::default();
+    let dispatcher = StubDispatcher::default();
+    let protected = MessageOptions {
+        protect_content: true,
+        ..MessageOptions::default()
+    };
+    let movies = MessageOptions {
+        link_preview: LinkPreview {
+            is_disabled: false,
+            prefer_large_media: true,
+            ..LinkPreview::default()
+        },
+        ..protected.clone()
+    };
+    let channel = ChatConfig {
+        message_options: ChatMessageOptions {
+            default: protected,
+            kinds: [(MessageKind::Movie, movies.clone())].into_iter().collect(),
+        },
+        ..ChatConfig::new(-100)
+    };
+
+    let mut orchestrator = Orchestrator::new(
+        SentHistory::with_store(dir.path().join("history.txt"), "artifact", store.clone()),
+        SentEventHistory::with_store(
+            dir.path().join("tv_history.txt"),
+            "tv-artifact",
+            store.clone(),
+        ),
+        StubProvider::new(ReleaseBatch {
+            movies: vec![sample_release(1, "Фильм"), sample_release(2, "Другой")],
+            ..ReleaseBatch::default()
+        }),
+        dispatcher.clone(),
+        TelegramConfig {
+            chats: vec![channel, ChatConfig::new(10)],
+        },
+    );
+    let now = DateTime::parse_from_rfc3339("2024-01-02T09:00:00Z")
+        .expect("валидная дата")
+        .with_timezone(&Utc);
+
+    orchestrator.run(now).await.expect("прогон завершается");
+
+    assert_eq!(
+        dispatcher.sent.lock().expect("блокировка доступна").len(),
+        2
+    );
+    let options = dispatcher.options.lock().expect("блокировка доступна");
+    assert_eq!(*options, vec![movies, MessageOptions::default()]);
+    assert!(options[1].link_preview.is_disabled);
+}
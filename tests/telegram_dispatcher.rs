@@ -8,16 +8,22 @@ use std::{
 };
 
 use async_trait::async_trait;
+use movie_notifier_bot::config::{LinkPreview, MessageOptions, ParseMode};
 use movie_notifier_bot::telegram::{
-    SendMessageRequest, TelegramDispatcher, TelegramTransport, TelegramTransportResponse,
+    PossibleDuplicate, RateLimits, SendMessageRequest, TelegramDispatcher, TelegramError,
+    TelegramTransport, TelegramTransportResponse, TransportFailure,
 };
 use reqwest::StatusCode;
-use tokio::time::timeout;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    time::timeout,
+};
 
 #[derive(Clone)]
 struct MockTransport {
     responses: Arc<Mutex<VecDeque<TelegramTransportResponse>>>,
     calls: Arc<AtomicUsize>,
+    payloads: Arc<Mutex<Vec<serde_json::Value>>>,
 }
 
 impl MockTransport {
@@ -25,6 +31,7 @@ impl MockTransport {
         Self {
             responses: Arc::new(Mutex::new(responses.into())),
             calls: Arc::new(AtomicUsize::new(0)),
+            payloads: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -38,9 +45,29 @@ impl TelegramTransport for MockTransport {
     async fn post_json(
         &self,
         _url: &str,
-        _payload: &SendMessageRequest,
+        payload: &SendMessageRequest,
+    ) -> Result<TelegramTransportResponse, reqwest::Error> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.payloads
+            .lock()
+            .expect("журнал запросов доступен")
+            .push(serde_json::to_value(payload).expect("запрос сериализуется"));
+        let mut responses = self.responses.lock().expect("очередь ответов доступна");
+        Ok(responses
+            .pop_front()
+            .expect("ответы должны быть подготовлены заранее"))
+    }
+
+    async fn post_value(
+        &self,
+        _url: &str,
+        payload: &serde_json::Value,
     ) -> Result<TelegramTransportResponse, reqwest::Error> {
         self.calls.fetch_add(1, Ordering::SeqCst);
+        self.payloads
+            .lock()
+            .expect("журнал запросов доступен")
+            .push(payload.clone());
         let mut responses = self.responses.lock().expect("очередь ответов доступна");
         Ok(responses
             .pop_front()
@@ -53,6 +80,7 @@ fn dispatcher_for(transport: Arc<MockTransport>) -> TelegramDispatcher {
         .transport(transport)
         .retry_delays(vec![Duration::from_millis(10)])
         .max_retries(3)
+        .rate_limits(RateLimits::unlimited())
         .build()
 }
 
@@ -141,3 +169,679 @@ async fn unknown_chat_returns_error_without_calling_api() {
     assert!(result.is_err());
     assert_eq!(transport.call_count(), 0);
 }
+
+#[tokio::test]
+async fn get_updates_parses_bot_api_envelope() {
+    let transport = Arc::new(MockTransport::new(vec![TelegramTransportResponse {
+        status: StatusCode::OK,
+        body: r#"{"ok":true,"result":[{"update_id":5,"message":{"message_id":1,"chat":{"id":42,"type":"private"},"text":"/help"}}]}"#.to_string(),
+    }]));
+
+    let dispatcher = dispatcher_for(transport.clone());
+    let updates = dispatcher
+        .get_updates(Some(5), 0)
+        .await
+        .expect("обновления должны разбираться");
+
+    assert_eq!(updates.len(), 1);
+    let message = updates[0].message.as_ref().expect("сообщение присутствует");
+    assert_eq!(message.chat.id, 42);
+    assert_eq!(message.text.as_deref(), Some("/help"));
+}
+
+#[tokio::test]
+async fn migration_and_forbidden_responses_are_classified() {
+    let transport = Arc::new(MockTransport::new(vec![
+        TelegramTransportResponse {
+            status: StatusCode::BAD_REQUEST,
+            body: r#"{"ok":false,"error_code":400,"description":"Bad Request: group chat was upgraded to a supergroup chat","parameters":{"migrate_to_chat_id":-1001234}}"#.to_string(),
+        },
+        TelegramTransportResponse {
+            status: StatusCode::FORBIDDEN,
+            body: r#"{"ok":false,"error_code":403,"description":"Forbidden: bot was kicked from the group chat"}"#.to_string(),
+        },
+    ]));
+
+    let dispatcher = dispatcher_for(transport.clone());
+    let migrated = dispatcher
+        .send_batch(1, vec!["сообщение"])
+        .await
+        .expect_err("переезд чата возвращается ошибкой");
+    let forbidden = dispatcher
+        .send_batch(1, vec!["сообщение"])
+        .await
+        .expect_err("403 возвращается ошибкой");
+
+    assert!(matches!(
+        migrated,
+        TelegramError::ChatMigrated {
+            chat_id: 1,
+            migrate_to_chat_id: -1001234
+        }
+    ));
+    assert!(matches!(
+        forbidden,
+        TelegramError::Forbidden { chat_id: 1, ref description } if description.contains("kicked")
+    ));
+    assert_eq!(transport.call_count(), 2, "эти ошибки не повторяются");
+}
+
+#[tokio::test]
+async fn message_ids_are_returned_and_unmodified_edit_is_ok() {
+    let transport = Arc::new(MockTransport::new(vec![
+        TelegramTransportResponse {
+            status: StatusCode::OK,
+            body: r#"{"ok":true,"result":{"message_id":77,"chat":{"id":1,"type":"group"}}}"#
+                .to_string(),
+        },
+        TelegramTransportResponse {
+            status: StatusCode::OK,
+            body: String::new(),
+        },
+        TelegramTransportResponse {
+            status: StatusCode::BAD_REQUEST,
+            body: r#"{"ok":false,"error_code":400,"description":"Bad Request: message is not modified"}"#
+                .to_string(),
+        },
+    ]));
+
+    let dispatcher = dispatcher_for(transport.clone());
+    let ids = dispatcher
+        .send_batch(1, vec!["первое", "второе"])
+        .await
+        .expect("отправка должна завершиться успешно");
+    dispatcher
+        .edit_message_text(1, 77, "первое", &MessageOptions::default())
+        .await
+        .expect("неизменённый текст не считается ошибкой");
+
+    assert_eq!(ids, vec![Some(77), None]);
+    assert_eq!(transport.call_count(), 3);
+}
+
+#[tokio::test]
+async fn message_options_reach_send_message_request() {
+    let ok = || TelegramTransportResponse {
+        status: StatusCode::OK,
+        body: String::new(),
+    };
+    let transport = Arc::new(MockTransport::new(vec![ok(), ok()]));
+    let dispatcher = dispatcher_for(transport.clone());
+    let options = MessageOptions {
+        link_preview: LinkPreview {
+            is_disabled: false,
+            url: Some("https://www.themoviedb.org/movie/1".to_string()),
+            prefer_large_media: true,
+            show_above_text: true,
+        },
+        disable_notification: true,
+        protect_content: true,
+        reply_to_message_id: Some(42),
+        parse_mode: Some(ParseMode::Html),
+    };
+
+    dispatcher
+        .send_batch(1, vec!["по умолчанию"])
+        .await
+        .expect("отправка должна завершиться успешно");
+    dispatcher
+        .send_topic_batch(1, Some(7), vec!["с параметрами"], &options)
+        .await
+        .expect("отправка должна завершиться успешно");
+
+    let payloads = transport.payloads.lock().expect("журнал запросов доступен");
+    assert_eq!(
+        payloads[0],
+        serde_json::json!({
+            "chat_id": 1,
+            "text": "по умолчанию",
+            "link_preview_options": {"is_disabled": true},
+        })
+    );
+    assert_eq!(
+        payloads[1],
+        serde_json::json!({
+            "chat_id": 1,
+            "message_thread_id": 7,
+            "text": "с параметрами",
+            "link_preview_options": {
+                "url": "https://www.themoviedb.org/movie/1",
+                "prefer_large_media": true,
+                "show_above_text": true,
+            },
+            "disable_notification": true,
+            "protect_content": true,
+            "reply_parameters": {"message_id": 42, "allow_sending_without_reply": true},
+            "parse_mode": "HTML",
+        })
+    );
+}
+
+fn bad_request(description: &str) -> TelegramTransportResponse {
+    TelegramTransportResponse {
+        status: StatusCode::BAD_REQUEST,
+        body: serde_json::json!({"ok": false, "error_code": 400, "description": description})
+            .to_string(),
+    }
+}
+
+fn ok_with_id(message_id: i64) -> TelegramTransportResponse {
+    TelegramTransportResponse {
+        status: StatusCode::OK,
+        body: serde_json::json!({"ok": true, "result": {"message_id": message_id}}).to_string(),
+    }
+}
+
+#[tokio::test]
+async fn partial_batch_reports_ids_sent_before_error() {
+    let transport = Arc::new(MockTransport::new(vec![
+        ok_with_id(41),
+        TelegramTransportResponse {
+            status: StatusCode::FORBIDDEN,
+            body: r#"{"ok":false,"error_code":403,"description":"Forbidden: bot was kicked from the group chat"}"#
+                .to_string(),
+        },
+    ]));
+    let dispatcher = dispatcher_for(transport.clone());
+
+    let partial = dispatcher
+        .send_topic_batch_partial(
+            1,
+            None,
+            vec!["первое", "второе", "третье"],
+            &MessageOptions::default(),
+        )
+        .await
+        .expect_err("второе сообщение отклонено");
+
+    assert_eq!(partial.sent, vec![Some(41)]);
+    assert!(matches!(partial.error, TelegramError::Forbidden { .. }));
+    assert_eq!(
+        transport.call_count(),
+        2,
+        "после ошибки отправка прекращается"
+    );
+}
+
+#[tokio::test]
+async fn too_long_and_unparsable_messages_are_resent() {
+    let transport = Arc::new(MockTransport::new(vec![
+        bad_request("Bad Request: message is too long"),
+        ok_with_id(10),
+        ok_with_id(11),
+        bad_request(
+            "Bad Request: can't parse entities: Unsupported start tag \"x\" at byte offset 0",
+        ),
+        ok_with_id(12),
+    ]));
+    let dispatcher = dispatcher_for(transport.clone());
+    let options = MessageOptions {
+        parse_mode: Some(ParseMode::Html),
+        ..MessageOptions::default()
+    };
+
+    let ids = dispatcher
+        .send_topic_batch(
+            1,
+            None,
+            vec!["первая строка\nвторая строка", "<x>разметка</x>"],
+            &options,
+        )
+        .await
+        .expect("исправленные сообщения доставляются");
+
+    assert_eq!(
+        ids,
+        vec![None, Some(12)],
+        "у разделённого сообщения нет единого id"
+    );
+    let payloads = transport.payloads.lock().expect("журнал запросов доступен");
+    let texts: Vec<_> = payloads
+        .iter()
+        .map(|payload| payload["text"].clone())
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            "первая строка\nвторая строка",
+            "первая строка",
+            "вторая строка",
+            "<x>разметка</x>",
+            "<x>разметка</x>",
+        ]
+    );
+    assert_eq!(payloads[3]["parse_mode"], "HTML");
+    assert!(
+        payloads[4].get("parse_mode").is_none(),
+        "повтор уходит простым текстом"
+    );
+}
+
+#[tokio::test]
+async fn permission_errors_are_typed_and_flag_the_chat() {
+    let transport = Arc::new(MockTransport::new(vec![
+        bad_request("Bad Request: chat not found"),
+        bad_request("Bad Request: not enough rights to send text messages to the chat"),
+        bad_request("Bad Request: TOPIC_CLOSED"),
+        bad_request("Bad Request: TOPIC_CLOSED"),
+        ok_with_id(5),
+    ]));
+    let dispatcher = dispatcher_for(transport.clone());
+
+    let not_found = dispatcher
+        .send_batch(1, vec!["первое", "второе"])
+        .await
+        .expect_err("чат не найден");
+    assert!(matches!(
+        not_found,
+        TelegramError::ChatNotFound { chat_id: 1 }
+    ));
+    let skipped = dispatcher
+        .send_batch(1, vec!["третье"])
+        .await
+        .expect_err("помеченный чат пропускается");
+    assert!(matches!(
+        skipped,
+        TelegramError::ChatSkipped { chat_id: 1, .. }
+    ));
+    assert_eq!(
+        transport.call_count(),
+        1,
+        "после ошибки чат больше не запрашивается"
+    );
+    assert!(dispatcher.flagged_chats().contains_key(&1));
+
+    dispatcher.allow_chat(1);
+    let no_rights = dispatcher
+        .send_batch(1, vec!["после повторной подписки"])
+        .await
+        .expect_err("нет прав писать");
+    assert!(matches!(
+        no_rights,
+        TelegramError::NotEnoughRights { chat_id: 1, ref description }
+            if description.contains("not enough rights")
+    ));
+
+    dispatcher.allow_chat(1);
+    let options = MessageOptions::default();
+    let topic_closed = dispatcher
+        .send_topic_batch(1, Some(7), vec!["в тему"], &options)
+        .await
+        .expect_err("тема закрыта");
+    assert!(matches!(
+        topic_closed,
+        TelegramError::TopicClosed {
+            chat_id: 1,
+            thread_id: Some(7),
+            ..
+        }
+    ));
+    let _ = dispatcher
+        .send_topic_batch(1, Some(7), vec!["снова в тему"], &options)
+        .await
+        .expect_err("тема всё ещё закрыта");
+    let ids = dispatcher
+        .send_batch(1, vec!["в общий поток"])
+        .await
+        .expect("закрытая тема не блокирует остальной чат");
+    assert_eq!(ids, vec![Some(5)]);
+    assert!(dispatcher.flagged_chats().is_empty());
+}
+
+#[tokio::test]
+async fn rate_limiter_is_shared_between_clones() {
+    let ok = || TelegramTransportResponse {
+        status: StatusCode::OK,
+        body: String::new(),
+    };
+    let transport = Arc::new(MockTransport::new((0..4).map(|_| ok()).collect()));
+    let dispatcher = TelegramDispatcher::builder("TOKEN", vec![1])
+        .transport(transport.clone())
+        .rate_limits(RateLimits {
+            per_chat_interval: Duration::from_millis(100),
+            ..RateLimits::default()
+        })
+        .build();
+    let clone = dispatcher.clone();
+
+    let started = tokio::time::Instant::now();
+    let (first, second) = tokio::join!(
+        dispatcher.send_batch(1, vec!["а", "б"]),
+        clone.send_batch(1, vec!["в", "г"]),
+    );
+    first.expect("отправка должна завершиться успешно");
+    second.expect("отправка должна завершиться успешно");
+
+    assert_eq!(transport.call_count(), 4);
+    assert!(started.elapsed() >= Duration::from_millis(300));
+}
+
+/// Шаг сценария транспорта: ответ Telegram или настоящая ошибка `reqwest`.
+enum Step {
+    Respond(TelegramTransportResponse),
+    Refused,
+    TimedOut,
+}
+
+/// Транспорт, который получает ошибки соединения от настоящего HTTP-клиента:
+/// отказ в соединении от закрытого порта и таймаут от сервера, который
+/// принимает запрос и молчит.
+struct FlakyTransport {
+    steps: Mutex<VecDeque<Step>>,
+    calls: AtomicUsize,
+    silent_server: String,
+    client: reqwest::Client,
+}
+
+impl FlakyTransport {
+    async fn new(steps: Vec<Step>) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("локальный порт доступен");
+        let silent_server = format!("http://{}", listener.local_addr().expect("адрес сервера"));
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                connections.push(socket);
+            }
+        });
+        Self {
+            steps: Mutex::new(steps.into()),
+            calls: AtomicUsize::new(0),
+            silent_server,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_millis(50))
+                .build()
+                .expect("клиент собирается"),
+        }
+    }
+
+    async fn closed_port_error(&self) -> reqwest::Error {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("локальный порт доступен");
+        let url = format!("http://{}", listener.local_addr().expect("адрес порта"));
+        drop(listener);
+        self.client.post(url).send().await.expect_err("порт закрыт")
+    }
+}
+
+#[async_trait]
+impl TelegramTransport for FlakyTransport {
+    async fn post_json(
+        &self,
+        _url: &str,
+        _payload: &SendMessageRequest,
+    ) -> Result<TelegramTransportResponse, reqwest::Error> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let step = self
+            .steps
+            .lock()
+            .expect("сценарий доступен")
+            .pop_front()
+            .expect("шаги должны быть подготовлены заранее");
+        match step {
+            Step::Respond(response) => Ok(response),
+            Step::Refused => Err(self.closed_port_error().await),
+            Step::TimedOut => Err(self
+                .client
+                .post(&self.silent_server)
+                .body("{}")
+                .send()
+                .await
+                .expect_err("сервер не отвечает")),
+        }
+    }
+
+    async fn post_value(
+        &self,
+        _url: &str,
+        _payload: &serde_json::Value,
+    ) -> Result<TelegramTransportResponse, reqwest::Error> {
+        unreachable!("в сценарии только sendMessage")
+    }
+}
+
+fn sent(message_id: i64) -> Step {
+    Step::Respond(TelegramTransportResponse {
+        status: StatusCode::OK,
+        body: format!(r#"{{"ok":true,"result":{{"message_id":{message_id}}}}}"#),
+    })
+}
+
+#[tokio::test]
+async fn transport_errors_are_retried_and_timeouts_flagged_as_possible_duplicates() {
+    let transport =
+        Arc::new(FlakyTransport::new(vec![Step::Refused, sent(5), Step::TimedOut, sent(6)]).await);
+    let dispatcher = TelegramDispatcher::builder("TOKEN", vec![1])
+        .transport(transport.clone())
+        .retry_delays(vec![Duration::from_millis(10)])
+        .max_retries(3)
+        .rate_limits(RateLimits::unlimited())
+        .build();
+
+    let ids = dispatcher
+        .send_batch(1, vec!["первое", "второе"])
+        .await
+        .expect("ошибки соединения повторяются");
+
+    assert_eq!(ids, vec![Some(5), Some(6)]);
+    assert_eq!(transport.calls.load(Ordering::SeqCst), 4);
+    assert_eq!(
+        dispatcher.take_possible_duplicates(),
+        vec![PossibleDuplicate {
+            chat_id: 1,
+            message_id: Some(6),
+        }]
+    );
+    assert!(dispatcher.take_possible_duplicates().is_empty());
+}
+
+#[tokio::test]
+async fn exhausted_retries_after_timeout_report_unknown_delivery() {
+    let transport =
+        Arc::new(FlakyTransport::new(vec![Step::TimedOut, Step::Refused, Step::Refused]).await);
+    let refused = transport.closed_port_error().await;
+    assert_eq!(
+        TransportFailure::classify(&refused),
+        TransportFailure::NotSent
+    );
+
+    let dispatcher = TelegramDispatcher::builder("TOKEN", vec![1])
+        .transport(transport.clone())
+        .retry_delays(vec![Duration::from_millis(10)])
+        .max_retries(2)
+        .rate_limits(RateLimits::unlimited())
+        .build();
+
+    let err = dispatcher
+        .send_batch(1, vec!["первое"])
+        .await
+        .expect_err("повторы исчерпаны");
+
+    assert!(matches!(
+        err,
+        TelegramError::DeliveryUnknown { chat_id: 1, .. }
+    ));
+    assert_eq!(transport.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn unanswered_request_times_out() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("порт открывается");
+    let address = listener.local_addr().expect("адрес известен");
+    // Сервер принимает соединение, но так и не отвечает.
+    let server = tokio::spawn(async move {
+        let (_stream, _) = listener.accept().await.expect("соединение принято");
+        std::future::pending::<()>().await;
+    });
+
+    let dispatcher = TelegramDispatcher::builder("TOKEN", vec![1])
+        .base_url(format!("http://{address}"))
+        .request_timeout(Duration::from_millis(200))
+        .max_retries(0)
+        .rate_limits(RateLimits::unlimited())
+        .build();
+    let result = timeout(
+        Duration::from_secs(5),
+        dispatcher.send_batch(1, vec!["зависший"]),
+    )
+    .await
+    .expect("запрос прерывается по таймауту");
+    server.abort();
+
+    assert!(matches!(
+        result,
+        Err(TelegramError::DeliveryUnknown { chat_id: 1, .. })
+    ));
+}
+
+/// Сервер на одно соединение: читает начало запроса и отвечает `reply`
+/// (пустой ответ — просто закрывает соединение).
+async fn one_shot_server(reply: &'static [u8]) -> (String, tokio::task::JoinHandle<()>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("порт открывается");
+    let address = listener.local_addr().expect("адрес известен");
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.expect("соединение принято");
+        if reply.is_empty() {
+            return;
+        }
+        let mut buf = [0u8; 4096];
+        let _ = stream.read(&mut buf).await;
+        let _ = stream.write_all(reply).await;
+        let _ = stream.shutdown().await;
+    });
+    (format!("http://{address}"), server)
+}
+
+#[tokio::test]
+async fn transport_failures_are_classified_by_stage() {
+    let client = reqwest::Client::new();
+
+    // Порт закрыт: соединение не установлено.
+    let refused = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("порт открывается");
+        let address = listener.local_addr().expect("адрес известен");
+        drop(listener);
+        client
+            .post(format!("http://{address}"))
+            .send()
+            .await
+            .expect_err("порт закрыт")
+    };
+    assert_eq!(
+        TransportFailure::classify(&refused),
+        TransportFailure::NotSent
+    );
+
+    // Сервер закрыл соединение, не дождавшись ответа: это `is_request`, но не таймаут.
+    let (url, server) = one_shot_server(b"").await;
+    let dropped = client
+        .post(&url)
+        .body("{}")
+        .send()
+        .await
+        .expect_err("соединение закрыто");
+    server.await.expect("сервер завершился");
+    assert!(dropped.is_request() && !dropped.is_timeout());
+    assert_eq!(
+        TransportFailure::classify(&dropped),
+        TransportFailure::NotSent
+    );
+
+    // Ответа нет дольше таймаута: запрос записан и мог быть принят.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("порт открывается");
+    let address = listener.local_addr().expect("адрес известен");
+    let silent = tokio::spawn(async move {
+        let (_stream, _) = listener.accept().await.expect("соединение принято");
+        std::future::pending::<()>().await;
+    });
+    let timed_out = client
+        .post(format!("http://{address}"))
+        .timeout(Duration::from_millis(100))
+        .body("{}")
+        .send()
+        .await
+        .expect_err("сервер не отвечает");
+    silent.abort();
+    assert_eq!(
+        TransportFailure::classify(&timed_out),
+        TransportFailure::MaybeDelivered
+    );
+
+    // Заголовки пришли, тело оборвалось: запрос точно дошёл до сервера.
+    let (url, server) =
+        one_shot_server(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n{\"ok\":").await;
+    let response = client
+        .post(&url)
+        .body("{}")
+        .send()
+        .await
+        .expect("заголовки получены");
+    let truncated = response.text().await.expect_err("тело оборвано");
+    server.await.expect("сервер завершился");
+    assert_eq!(
+        TransportFailure::classify(&truncated),
+        TransportFailure::MaybeDelivered
+    );
+
+    // Некорректный URL: запрос не собрать, повтор бесполезен.
+    let invalid = client
+        .post("не адрес")
+        .send()
+        .await
+        .expect_err("URL некорректен");
+    assert_eq!(
+        TransportFailure::classify(&invalid),
+        TransportFailure::Fatal
+    );
+}
+
+#[tokio::test]
+async fn truncated_response_body_reports_unknown_delivery() {
+    let (url, server) =
+        one_shot_server(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n{\"ok\":").await;
+    let dispatcher = TelegramDispatcher::builder("TOKEN", vec![1])
+        .base_url(url)
+        .max_retries(0)
+        .rate_limits(RateLimits::unlimited())
+        .build();
+
+    let result = dispatcher.send_batch(1, vec!["оборванный"]).await;
+    server.await.expect("сервер завершился");
+
+    assert!(matches!(
+        result,
+        Err(TelegramError::DeliveryUnknown { chat_id: 1, .. })
+    ));
+}
+
+#[tokio::test]
+async fn edit_keeps_parse_mode_and_falls_back_to_plain_text() {
+    let transport = Arc::new(MockTransport::new(vec![
+        bad_request(
+            "Bad Request: can't parse entities: Unsupported start tag \"x\" at byte offset 0",
+        ),
+        ok_with_id(77),
+    ]));
+    let dispatcher = dispatcher_for(transport.clone());
+    let options = MessageOptions {
+        parse_mode: Some(ParseMode::Html),
+        ..MessageOptions::default()
+    };
+
+    dispatcher
+        .edit_message_text(1, 77, "<x>правка</x>", &options)
+        .await
+        .expect("правка уходит простым текстом");
+
+    let payloads = transport.payloads.lock().expect("журнал запросов доступен");
+    assert_eq!(payloads.len(), 2);
+    assert_eq!(payloads[0]["parse_mode"], "HTML");
+    assert!(payloads[1].get("parse_mode").is_none());
+    assert_eq!(payloads[1]["text"], "<x>правка</x>");
+}